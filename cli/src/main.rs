@@ -0,0 +1,529 @@
+//! Operator command-line tool for the wager program
+
+use anchor_lang::{prelude::Pubkey, AnchorDeserialize, Discriminator};
+use anyhow::{bail, Context, Result};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+use std::str::FromStr;
+use wager_client::{accounts::*, instructions, pda::*, GameMode, GameSession, GameStatus};
+use wager_program::state::{GameSessionV0, MatchState, SessionOptions};
+
+const USAGE: &str = "\
+Usage: wager-cli [--url <RPC_URL>] [--keypair <PATH>] <COMMAND>
+
+Commands:
+  create <SESSION_ID> <BET> <MODE> <TEAM_SIZE> <TEAM_COUNT>
+                           Create a session. MODE is winner-takes-all, pay-to-spawn,
+                           practice or free-for-all:<MAX_PLAYERS>:<1ST>,<2ND>,<3RD>
+  list                     List every game session
+  show <SESSION>           Show a session's status, teams and player stats
+  refund <SESSION>         Refund every player's stake
+  distribute <SESSION> [WINNING_TEAM | PLAYER...]
+                           Pay out a session. Team games take the winning team,
+                           free-for-all games the players from first place down
+  vault <SESSION>          Print a session's vault balance
+  migrate <SESSION_ID>     Move a session of the original layout to its hash address
+
+SESSION is a session ID or a game session address.
+Defaults: --url http://127.0.0.1:8899, --keypair ~/.config/solana/id.json";
+
+struct Cli {
+    rpc: RpcClient,
+    keypair_path: String,
+}
+
+/// Global options given alongside a command
+#[derive(Debug, PartialEq)]
+struct Options {
+    url: String,
+    keypair_path: String,
+}
+
+enum Command {
+    Help,
+    Create {
+        session_id: String,
+        bet: u64,
+        game_mode: GameMode,
+        team_size: u8,
+        team_count: u8,
+    },
+    List,
+    Show(String),
+    Refund(String),
+    Distribute(String, Vec<String>),
+    Vault(String),
+    Migrate(String),
+}
+
+fn main() {
+    if let Err(error) = run(std::env::args().skip(1).collect()) {
+        eprintln!("error: {error:#}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<()> {
+    let (options, command) = parse_args(args)?;
+    let cli = Cli {
+        rpc: RpcClient::new(options.url),
+        keypair_path: options.keypair_path,
+    };
+    match command {
+        Command::Help => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Command::Create {
+            session_id,
+            bet,
+            game_mode,
+            team_size,
+            team_count,
+        } => cli.create(&session_id, bet, game_mode, team_size, team_count),
+        Command::List => cli.list(),
+        Command::Show(session) => cli.show(&session),
+        Command::Refund(session) => cli.refund(&session),
+        Command::Distribute(session, winners) => cli.distribute(&session, &winners),
+        Command::Vault(session) => cli.vault(&session),
+        Command::Migrate(session_id) => cli.migrate(&session_id),
+    }
+}
+
+/// Splits the global options from the command and parses both
+fn parse_args(args: Vec<String>) -> Result<(Options, Command)> {
+    let mut options = Options {
+        url: "http://127.0.0.1:8899".to_string(),
+        keypair_path: format!(
+            "{}/.config/solana/id.json",
+            std::env::var("HOME").unwrap_or_default()
+        ),
+    };
+
+    let mut args = args.into_iter();
+    let mut command = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-u" | "--url" => options.url = args.next().context("--url needs a value")?,
+            "-k" | "--keypair" => {
+                options.keypair_path = args.next().context("--keypair needs a value")?
+            }
+            "-h" | "--help" => return Ok((options, Command::Help)),
+            _ => command.push(arg),
+        }
+    }
+
+    let command: Vec<&str> = command.iter().map(String::as_str).collect();
+    let command = match command.as_slice() {
+        ["create", session_id, bet, mode, team_size, team_count] => Command::Create {
+            session_id: session_id.to_string(),
+            bet: bet.parse().context("invalid bet")?,
+            game_mode: parse_game_mode(mode)?,
+            team_size: team_size.parse().context("invalid team size")?,
+            team_count: team_count.parse().context("invalid team count")?,
+        },
+        ["list"] => Command::List,
+        ["show", session] => Command::Show(session.to_string()),
+        ["refund", session] => Command::Refund(session.to_string()),
+        ["distribute", session, winners @ ..] => Command::Distribute(
+            session.to_string(),
+            winners.iter().map(|winner| winner.to_string()).collect(),
+        ),
+        ["vault", session] => Command::Vault(session.to_string()),
+        ["migrate", session_id] => Command::Migrate(session_id.to_string()),
+        _ => bail!("{USAGE}"),
+    };
+    Ok((options, command))
+}
+
+impl Cli {
+    fn keypair(&self) -> Result<Keypair> {
+        read_keypair_file(&self.keypair_path)
+            .map_err(|error| anyhow::anyhow!("failed to read {}: {error}", self.keypair_path))
+    }
+
+    fn send(&self, signer: &Keypair, instruction: Instruction) -> Result<()> {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&signer.pubkey()),
+            &[signer],
+            self.rpc.get_latest_blockhash()?,
+        );
+        let signature = self.rpc.send_and_confirm_transaction(&transaction)?;
+        println!("Signature: {signature}");
+        Ok(())
+    }
+
+    /// Returns an account's data, or `None` if it doesn't exist
+    fn get_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())?
+            .value
+            .map(|account| account.data))
+    }
+
+    /// Returns the balance of a token account, or `None` if it doesn't exist
+    fn get_token_balance(&self, token_account: &Pubkey) -> Result<Option<u64>> {
+        // The amount follows the mint and owner in the token account layout
+        Ok(self.get_account_data(token_account)?.and_then(|data| {
+            data.get(64..72)
+                .map(|amount| u64::from_le_bytes(amount.try_into().unwrap()))
+        }))
+    }
+
+    /// Finds a session by its readable ID or game session address
+    fn load_session(&self, session: &str) -> Result<(Pubkey, GameSession)> {
+        let address = Pubkey::from_str(session)
+            .unwrap_or_else(|_| find_game_session(&session_hash(session)).0);
+        let data = self
+            .get_account_data(&address)?
+            .with_context(|| format!("game session {session} not found"))?;
+        Ok((address, decode_game_session(&data)?))
+    }
+
+    fn create(
+        &self,
+        session_id: &str,
+        bet: u64,
+        game_mode: GameMode,
+        team_size: u8,
+        team_count: u8,
+    ) -> Result<()> {
+        let game_server = self.keypair()?;
+        let options = SessionOptions {
+            session_id: Some(session_id.to_string()),
+            ..SessionOptions::default()
+        };
+        let session_hash = session_hash(session_id);
+        self.send(
+            &game_server,
+            instructions::create_game_session(
+                &game_server.pubkey(),
+                session_hash,
+                bet,
+                game_mode,
+                team_size,
+                team_count,
+                options,
+            ),
+        )?;
+        println!("Game session: {}", find_game_session(&session_hash).0);
+        Ok(())
+    }
+
+    fn list(&self) -> Result<()> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                0,
+                GameSession::DISCRIMINATOR.to_vec(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let sessions = self
+            .rpc
+            .get_program_accounts_with_config(&wager_client::PROGRAM_ID, config)?;
+        for (address, account) in sessions {
+            let data = account.data;
+            // Sessions of the original layout still sit at their session ID address
+            if data.len() == GameSessionV0::LEN {
+                match GameSessionV0::deserialize(&mut &data[8..]) {
+                    Ok(legacy) => println!(
+                        "{address}  {:<20} legacy, run `wager-cli migrate {}`",
+                        legacy.session_id, legacy.session_id
+                    ),
+                    Err(_) => println!("{address}  <undecodable legacy session>"),
+                }
+                continue;
+            }
+            match decode_game_session(&data) {
+                Ok(session) => println!(
+                    "{address}  {:<20} {:<18} {:<20} bet {}",
+                    session.session_id.as_deref().unwrap_or("-"),
+                    status_name(&session.status),
+                    mode_name(&session.game_mode),
+                    session.session_bet
+                ),
+                Err(_) => println!("{address}  <undecodable>"),
+            }
+        }
+        Ok(())
+    }
+
+    fn show(&self, session: &str) -> Result<()> {
+        let (address, session) = self.load_session(session)?;
+        let match_state = self
+            .get_account_data(&find_match_state(&session.session_hash).0)?
+            .map(|data| decode_match_state(&data))
+            .transpose()?;
+
+        println!("Game session: {address}");
+        println!(
+            "Session ID:   {}",
+            session.session_id.as_deref().unwrap_or("-")
+        );
+        println!("Authority:    {}", session.authority);
+        println!("Status:       {}", status_name(&session.status));
+        println!("Mode:         {}", mode_name(&session.game_mode));
+        println!("Bet:          {}", session.session_bet);
+        if let Some(match_state) = &match_state {
+            println!("Match status: {}", match_status_name(match_state));
+        }
+
+        let print_player = |team: u8, player: &Pubkey| {
+            if *player == Pubkey::default() {
+                println!("    <empty>");
+                return;
+            }
+            let stats = match_state.as_ref().and_then(|state| {
+                let seat = state.find_seat(team, *player).ok()?;
+                Some((state.kills[seat], state.spawns[seat]))
+            });
+            match stats {
+                Some((kills, spawns)) => {
+                    println!("    {player}  kills {kills:>3}  spawns {spawns:>3}")
+                }
+                None => println!("    {player}"),
+            }
+        };
+        for (index, team) in session.teams.iter().enumerate() {
+            println!("Team {index} (pot {}):", team.total_bet);
+            team.players
+                .iter()
+                .for_each(|player| print_player(index as u8, player));
+        }
+        if session.is_free_for_all() {
+            println!("Players:");
+            session
+                .free_for_all_players
+                .iter()
+                .for_each(|player| print_player(0, player));
+        }
+        self.print_vault(&session)
+    }
+
+    fn refund(&self, session: &str) -> Result<()> {
+        let game_server = self.keypair()?;
+        let (_, session) = self.load_session(session)?;
+        self.send(
+            &game_server,
+            instructions::refund_wager(&game_server.pubkey(), &session),
+        )
+    }
+
+    fn distribute(&self, session: &str, winners: &[String]) -> Result<()> {
+        let game_server = self.keypair()?;
+        let (_, session) = self.load_session(session)?;
+        let instruction = if session.is_free_for_all() {
+            let placings = winners
+                .iter()
+                .map(|player| Pubkey::from_str(player).context("invalid player address"))
+                .collect::<Result<Vec<_>>>()?;
+            let paid_places = match session.game_mode {
+                GameMode::FreeForAll {
+                    payout_percentages, ..
+                } => payout_percentages.iter().filter(|p| **p > 0).count(),
+                _ => 0,
+            };
+            if placings.len() != paid_places {
+                bail!(
+                    "free-for-all games take the {paid_places} paid players from first place down"
+                );
+            }
+            instructions::distribute_free_for_all_winnings(
+                &game_server.pubkey(),
+                &session,
+                &placings,
+            )
+        } else {
+            // Pay-to-spawn games pay everyone by their stats and ignore the winning team
+            let winning_team = match winners {
+                [] if session.is_pay_to_spawn() => 0,
+                [team] => team.parse().context("invalid winning team")?,
+                _ => bail!("team games take the winning team"),
+            };
+            instructions::distribute_winnings(&game_server.pubkey(), &session, winning_team)
+        };
+        self.send(&game_server, instruction)
+    }
+
+    fn vault(&self, session: &str) -> Result<()> {
+        let (_, session) = self.load_session(session)?;
+        self.print_vault(&session)
+    }
+
+    fn print_vault(&self, session: &GameSession) -> Result<()> {
+        let vault_token_account = vault_token_account(&session.session_hash);
+        match self.get_token_balance(&vault_token_account)? {
+            Some(balance) => println!("Vault {vault_token_account}: {balance}"),
+            None => println!("Vault {vault_token_account}: closed"),
+        }
+        Ok(())
+    }
+
+    fn migrate(&self, session_id: &str) -> Result<()> {
+        let game_server = self.keypair()?;
+        let data = self
+            .get_account_data(&find_legacy_session(session_id).0)?
+            .with_context(|| format!("legacy session {session_id} not found"))?;
+        let legacy = decode_legacy_session(&data)?;
+        self.send(
+            &game_server,
+            instructions::migrate_session(&game_server.pubkey(), &legacy),
+        )?;
+        println!(
+            "Game session: {}",
+            find_game_session(&session_hash(session_id)).0
+        );
+        Ok(())
+    }
+}
+
+fn parse_game_mode(mode: &str) -> Result<GameMode> {
+    Ok(match mode.split(':').collect::<Vec<_>>().as_slice() {
+        ["winner-takes-all"] => GameMode::WinnerTakesAll,
+        ["pay-to-spawn"] => GameMode::PayToSpawn,
+        ["practice"] => GameMode::Practice,
+        ["free-for-all", max_players, payouts] => {
+            let payouts = payouts
+                .split(',')
+                .map(str::parse)
+                .collect::<Result<Vec<u8>, _>>()
+                .context("invalid payout percentages")?;
+            GameMode::FreeForAll {
+                max_players: max_players.parse().context("invalid player count")?,
+                payout_percentages: payouts
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("free-for-all takes three payout percentages"))?,
+            }
+        }
+        _ => bail!("unknown game mode {mode}"),
+    })
+}
+
+fn mode_name(mode: &GameMode) -> String {
+    match mode {
+        GameMode::WinnerTakesAll => "winner-takes-all".to_string(),
+        GameMode::PayToSpawn => "pay-to-spawn".to_string(),
+        GameMode::Practice => "practice".to_string(),
+        GameMode::FreeForAll {
+            max_players,
+            payout_percentages: [first, second, third],
+        } => format!("free-for-all:{max_players}:{first},{second},{third}"),
+    }
+}
+
+fn status_name(status: &GameStatus) -> &'static str {
+    match status {
+        GameStatus::WaitingForPlayers => "waiting-for-players",
+        GameStatus::InProgress => "in-progress",
+        GameStatus::Completed => "completed",
+        GameStatus::Ended => "ended",
+    }
+}
+
+fn match_status_name(match_state: &MatchState) -> &'static str {
+    [
+        GameStatus::WaitingForPlayers,
+        GameStatus::InProgress,
+        GameStatus::Completed,
+        GameStatus::Ended,
+    ]
+    .into_iter()
+    .find(|status| match_state.is_status(status.clone()))
+    .map_or("unknown", |status| status_name(&status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<(Options, Command)> {
+        parse_args(args.split_whitespace().map(String::from).collect())
+    }
+
+    #[test]
+    fn parses_global_options_around_the_command() {
+        let (options, command) = parse("--url http://rpc list -k server.json").unwrap();
+        assert_eq!(options.url, "http://rpc");
+        assert_eq!(options.keypair_path, "server.json");
+        assert!(matches!(command, Command::List));
+
+        assert!(matches!(parse("show x --help").unwrap().1, Command::Help));
+        assert!(parse("list --url").is_err());
+    }
+
+    #[test]
+    fn parses_create_arguments() {
+        let (_, command) = parse("create duel 1000 free-for-all:4:60,30,10 1 0").unwrap();
+        let Command::Create {
+            session_id,
+            bet,
+            game_mode,
+            team_size,
+            team_count,
+        } = command
+        else {
+            panic!("expected a create command");
+        };
+        assert_eq!(session_id, "duel");
+        assert_eq!((bet, team_size, team_count), (1000, 1, 0));
+        assert!(
+            game_mode
+                == GameMode::FreeForAll {
+                    max_players: 4,
+                    payout_percentages: [60, 30, 10],
+                }
+        );
+
+        assert!(parse("create duel lots winner-takes-all 1 2").is_err());
+        assert!(parse("create duel 1000 free-for-all:4:60,40 1 0").is_err());
+        assert!(parse("create duel 1000 king-of-the-hill 1 2").is_err());
+    }
+
+    #[test]
+    fn parses_session_commands() {
+        assert!(matches!(
+            parse("distribute duel 1").unwrap().1,
+            Command::Distribute(session, winners) if session == "duel" && winners == ["1"]
+        ));
+        assert!(matches!(
+            parse("migrate legacy").unwrap().1,
+            Command::Migrate(session_id) if session_id == "legacy"
+        ));
+        assert!(matches!(
+            parse("refund duel").unwrap().1,
+            Command::Refund(session) if session == "duel"
+        ));
+        assert!(parse("migrate").is_err());
+        assert!(parse("refund").is_err());
+    }
+
+    #[test]
+    fn game_mode_names_round_trip() {
+        for mode in [
+            GameMode::WinnerTakesAll,
+            GameMode::PayToSpawn,
+            GameMode::Practice,
+            GameMode::FreeForAll {
+                max_players: 8,
+                payout_percentages: [50, 30, 20],
+            },
+        ] {
+            assert!(parse_game_mode(&mode_name(&mode)).unwrap() == mode);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Decoding of the wager program's accounts from raw account data
+
+use anchor_lang::{error::ErrorCode, prelude::*, Discriminator};
+use bytemuck::Pod;
+use wager_program::state::{GameSession, GameSessionV0, MatchLog, MatchState, PlayerProfile};
+
+/// Decodes a game session, checking its discriminator
+pub fn decode_game_session(data: &[u8]) -> Result<GameSession> {
+    GameSession::try_deserialize(&mut &data[..])
+}
+
+/// Decodes a session of the original layout, still stored at its session ID address
+pub fn decode_legacy_session(data: &[u8]) -> Result<GameSessionV0> {
+    require!(
+        data.get(..8) == Some(&GameSession::DISCRIMINATOR[..]),
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    GameSessionV0::deserialize(&mut &data[8..])
+        .map_err(|_| ErrorCode::AccountDidNotDeserialize.into())
+}
+
+/// Decodes a player profile, checking its discriminator
+pub fn decode_player_profile(data: &[u8]) -> Result<PlayerProfile> {
+    PlayerProfile::try_deserialize(&mut &data[..])
+}
+
+/// Decodes a match state, checking its discriminator
+pub fn decode_match_state(data: &[u8]) -> Result<MatchState> {
+    decode_zero_copy(data)
+}
+
+/// Decodes a match log, checking its discriminator
+pub fn decode_match_log(data: &[u8]) -> Result<MatchLog> {
+    decode_zero_copy(data)
+}
+
+/// Reads a zero-copy account, which is stored as-is after its discriminator
+fn decode_zero_copy<T: Pod + Discriminator>(data: &[u8]) -> Result<T> {
+    let discriminator = data
+        .get(..8)
+        .ok_or(ErrorCode::AccountDiscriminatorNotFound)?;
+    require!(
+        discriminator == T::DISCRIMINATOR,
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    let body = data
+        .get(8..8 + std::mem::size_of::<T>())
+        .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+    Ok(bytemuck::pod_read_unaligned(body))
+}
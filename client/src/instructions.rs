@@ -0,0 +1,765 @@
+//! Builders for the wager program's instructions, deriving every PDA and
+//! assembling the `remaining_accounts` each instruction expects
+
+use crate::pda::*;
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, sysvar},
+    system_program, InstructionData,
+};
+use anchor_spl::{associated_token, token};
+use wager_program::{
+    accounts, instruction,
+    state::{GameMode, GameSession, GameSessionV0, JoinCredentials, ReplayEvent, SessionOptions},
+    ID, TOKEN_ID,
+};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn create_game_session(
+    game_server: &Pubkey,
+    session_hash: [u8; 32],
+    bet_amount: u64,
+    game_mode: GameMode,
+    team_size: u8,
+    team_count: u8,
+    options: SessionOptions,
+) -> Instruction {
+    let vault = find_vault(&session_hash).0;
+    build(
+        accounts::CreateGameSession {
+            game_server: *game_server,
+            game_session: find_game_session(&session_hash).0,
+            match_state: find_match_state(&session_hash).0,
+            vault,
+            vault_token_account: vault_token_account(&session_hash),
+            mint: TOKEN_ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreateGameSession {
+            session_hash,
+            bet_amount,
+            game_mode,
+            team_size,
+            team_count,
+            options,
+        },
+    )
+}
+
+fn join_accounts(
+    user: &Pubkey,
+    game_server: &Pubkey,
+    session_hash: &[u8; 32],
+    gate_token_account: Option<Pubkey>,
+    invite: Option<Pubkey>,
+) -> accounts::JoinUser {
+    accounts::JoinUser {
+        user: *user,
+        game_server: *game_server,
+        game_session: find_game_session(session_hash).0,
+        match_state: find_match_state(session_hash).0,
+        player_profile: find_player_profile(user).0,
+        user_token_account: player_token_account(user),
+        gate_token_account,
+        invite,
+        vault: find_vault(session_hash).0,
+        vault_token_account: vault_token_account(session_hash),
+        mint: TOKEN_ID,
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+/// Joins `team` of a team game. Gated sessions need the token account holding the gating
+/// token, and password protected ones the invite key, which must co-sign
+pub fn join_user(
+    user: &Pubkey,
+    game_server: &Pubkey,
+    session_hash: [u8; 32],
+    team: u8,
+    credentials: JoinCredentials,
+    gate_token_account: Option<Pubkey>,
+    invite: Option<Pubkey>,
+) -> Instruction {
+    build(
+        join_accounts(user, game_server, &session_hash, gate_token_account, invite),
+        instruction::JoinUser {
+            session_hash,
+            team,
+            credentials,
+        },
+    )
+}
+
+/// Joins a free-for-all game. Gated sessions need the token account holding the gating
+/// token, and password protected ones the invite key, which must co-sign
+pub fn join_free_for_all(
+    user: &Pubkey,
+    game_server: &Pubkey,
+    session_hash: [u8; 32],
+    credentials: JoinCredentials,
+    gate_token_account: Option<Pubkey>,
+    invite: Option<Pubkey>,
+) -> Instruction {
+    build(
+        join_accounts(user, game_server, &session_hash, gate_token_account, invite),
+        instruction::JoinFreeForAll {
+            session_hash,
+            credentials,
+        },
+    )
+}
+
+/// Buys more spawns. `match_log` must be set for sessions that keep a match log
+pub fn pay_to_spawn(
+    user: &Pubkey,
+    game_server: &Pubkey,
+    session_hash: [u8; 32],
+    team: u8,
+    match_log: bool,
+) -> Instruction {
+    build(
+        accounts::PayToSpawn {
+            user: *user,
+            game_server: *game_server,
+            game_session: find_game_session(&session_hash).0,
+            match_state: find_match_state(&session_hash).0,
+            match_log: match_log.then(|| find_match_log(&session_hash).0),
+            user_token_account: player_token_account(user),
+            vault: find_vault(&session_hash).0,
+            vault_token_account: vault_token_account(&session_hash),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::PayToSpawn { session_hash, team },
+    )
+}
+
+/// Records a kill. `match_log` must be set for sessions that keep a match log
+pub fn record_kill(
+    game_server: &Pubkey,
+    session_hash: [u8; 32],
+    killer_team: u8,
+    killer: Pubkey,
+    victim_team: u8,
+    victim: Pubkey,
+    match_log: bool,
+) -> Instruction {
+    build(
+        accounts::RecordKill {
+            match_state: find_match_state(&session_hash).0,
+            game_session: find_game_session(&session_hash).0,
+            killer_profile: find_player_profile(&killer).0,
+            victim_profile: find_player_profile(&victim).0,
+            match_log: match_log.then(|| find_match_log(&session_hash).0),
+            game_server: *game_server,
+        },
+        instruction::RecordKill {
+            session_hash,
+            killer_team,
+            killer,
+            victim_team,
+            victim,
+        },
+    )
+}
+
+/// Records the winner of a game of a best-of-N series
+pub fn record_series_game(
+    game_server: &Pubkey,
+    session_hash: [u8; 32],
+    winning_team: u8,
+) -> Instruction {
+    build(
+        accounts::RecordSeriesGame {
+            game_session: find_game_session(&session_hash).0,
+            match_state: find_match_state(&session_hash).0,
+            game_server: *game_server,
+        },
+        instruction::RecordSeriesGame {
+            session_hash,
+            winning_team,
+        },
+    )
+}
+
+/// Opens a match log, after which kills and spawn purchases must pass it
+pub fn create_match_log(game_server: &Pubkey, session_hash: [u8; 32]) -> Instruction {
+    build(
+        accounts::CreateMatchLog {
+            game_server: *game_server,
+            game_session: find_game_session(&session_hash).0,
+            match_state: find_match_state(&session_hash).0,
+            match_log: find_match_log(&session_hash).0,
+            system_program: system_program::ID,
+        },
+        instruction::CreateMatchLog { session_hash },
+    )
+}
+
+/// Commits the Merkle root of a settled session's full event log
+pub fn commit_replay(
+    game_server: &Pubkey,
+    session_hash: [u8; 32],
+    root: [u8; 32],
+    event_count: u32,
+) -> Instruction {
+    build(
+        accounts::CommitReplay {
+            game_session: find_game_session(&session_hash).0,
+            game_server: *game_server,
+        },
+        instruction::CommitReplay {
+            session_hash,
+            root,
+            event_count,
+        },
+    )
+}
+
+/// Proves `event` is part of a session's committed replay
+pub fn verify_replay_event(
+    session_hash: [u8; 32],
+    event: ReplayEvent,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    build(
+        accounts::VerifyReplayEvent {
+            game_session: find_game_session(&session_hash).0,
+        },
+        instruction::VerifyReplayEvent {
+            session_hash,
+            event,
+            proof,
+        },
+    )
+}
+
+/// Returns the (player, token account) pair a payout is sent through
+pub fn payout_pair(player: &Pubkey) -> [AccountMeta; 2] {
+    [
+        AccountMeta::new_readonly(*player, false),
+        AccountMeta::new(player_token_account(player), false),
+    ]
+}
+
+/// Returns the profiles of every joined player, in the order settlement expects them
+fn profile_accounts(session: &GameSession) -> Vec<AccountMeta> {
+    session
+        .get_joined_players()
+        .iter()
+        .map(|player| AccountMeta::new(find_player_profile(player).0, false))
+        .collect()
+}
+
+fn distribute_accounts(
+    game_server: &Pubkey,
+    session: &GameSession,
+) -> accounts::DistributeWinnings {
+    let session_hash = &session.session_hash;
+    accounts::DistributeWinnings {
+        game_server: *game_server,
+        game_session: find_game_session(session_hash).0,
+        match_state: find_match_state(session_hash).0,
+        vault: find_vault(session_hash).0,
+        vault_token_account: vault_token_account(session_hash),
+        tournament: session.tournament_match.map(|link| link.tournament),
+        league: session.league_match.map(|link| link.league),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+/// Settles a team game, paying `winning_team` for winner takes all games and every
+/// player's earnings for pay-to-spawn games
+pub fn distribute_winnings(
+    game_server: &Pubkey,
+    session: &GameSession,
+    winning_team: u8,
+) -> Instruction {
+    // Pay-to-spawn games look up every player's pair, other games only pay the winners
+    let payees = if session.is_pay_to_spawn() {
+        session.get_joined_players()
+    } else {
+        session
+            .teams
+            .get(winning_team as usize)
+            .map_or_else(Vec::new, |team| {
+                team.players
+                    .iter()
+                    .filter(|player| **player != Pubkey::default())
+                    .copied()
+                    .collect()
+            })
+    };
+
+    let mut ix = build(
+        distribute_accounts(game_server, session),
+        instruction::DistributeWinnings {
+            session_hash: session.session_hash,
+            winning_team,
+        },
+    );
+    ix.accounts.extend(payees.iter().flat_map(payout_pair));
+    ix.accounts.extend(profile_accounts(session));
+    ix
+}
+
+/// Settles a free-for-all game, `placings` holding the players from first place down
+pub fn distribute_free_for_all_winnings(
+    game_server: &Pubkey,
+    session: &GameSession,
+    placings: &[Pubkey],
+) -> Instruction {
+    let mut ix = build(
+        distribute_accounts(game_server, session),
+        instruction::DistributeWinnings {
+            session_hash: session.session_hash,
+            winning_team: 0,
+        },
+    );
+    ix.accounts.extend(placings.iter().flat_map(payout_pair));
+    ix.accounts.extend(profile_accounts(session));
+    ix
+}
+
+/// Accounts shared by the instructions that hand every player their stake back
+fn refund_accounts(game_server: &Pubkey, session: &GameSession) -> accounts::RefundWager {
+    let session_hash = &session.session_hash;
+    accounts::RefundWager {
+        game_server: *game_server,
+        game_session: find_game_session(session_hash).0,
+        match_state: find_match_state(session_hash).0,
+        vault: find_vault(session_hash).0,
+        vault_token_account: vault_token_account(session_hash),
+        league: session.league_match.map(|link| link.league),
+        token_program: token::ID,
+        associated_token_program: associated_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+/// Returns every player's stake and spawn purchases
+pub fn refund_wager(game_server: &Pubkey, session: &GameSession) -> Instruction {
+    let mut ix = build(
+        refund_accounts(game_server, session),
+        instruction::RefundWager {
+            session_hash: session.session_hash,
+        },
+    );
+    ix.accounts
+        .extend(session.get_joined_players().iter().flat_map(payout_pair));
+    ix.accounts.extend(profile_accounts(session));
+    ix
+}
+
+/// Settles a league fixture in play as a draw, returning every stake
+pub fn settle_league_draw(game_server: &Pubkey, session: &GameSession) -> Instruction {
+    let mut ix = build(
+        refund_accounts(game_server, session),
+        instruction::SettleLeagueDraw {
+            session_hash: session.session_hash,
+        },
+    );
+    ix.accounts
+        .extend(session.get_joined_players().iter().flat_map(payout_pair));
+    ix
+}
+
+/// Closes a settled session, along with its match log if it keeps one
+pub fn close_game_session(game_server: &Pubkey, session: &GameSession) -> Instruction {
+    let session_hash = session.session_hash;
+    build(
+        accounts::CloseGameSession {
+            game_server: *game_server,
+            game_session: find_game_session(&session_hash).0,
+            match_state: find_match_state(&session_hash).0,
+            match_log: session.match_log.map(|_| find_match_log(&session_hash).0),
+        },
+        instruction::CloseGameSession { session_hash },
+    )
+}
+
+/// Moves a session of the original layout from its session ID address to the
+/// accounts derived from its hash, creating the profiles of its players
+pub fn migrate_session(game_server: &Pubkey, legacy: &GameSessionV0) -> Instruction {
+    let session_id = legacy.session_id.as_str();
+    let session_hash = session_hash(session_id);
+    let mut ix = build(
+        accounts::MigrateSession {
+            game_server: *game_server,
+            legacy_session: find_legacy_session(session_id).0,
+            legacy_vault: find_legacy_vault(session_id).0,
+            legacy_vault_token_account: legacy_vault_token_account(session_id),
+            game_session: find_game_session(&session_hash).0,
+            match_state: find_match_state(&session_hash).0,
+            vault: find_vault(&session_hash).0,
+            vault_token_account: vault_token_account(&session_hash),
+            mint: TOKEN_ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::MigrateSession {
+            session_id: session_id.to_string(),
+            session_hash,
+        },
+    );
+    ix.accounts.extend(
+        legacy
+            .get_joined_players()
+            .iter()
+            .map(|player| AccountMeta::new(find_player_profile(player).0, false)),
+    );
+    ix
+}
+
+/// Grows a session allocated on an older layout version to the current one
+pub fn upgrade_session(game_server: &Pubkey, session_hash: [u8; 32]) -> Instruction {
+    build(
+        accounts::UpgradeSession {
+            game_server: *game_server,
+            game_session: find_game_session(&session_hash).0,
+            system_program: system_program::ID,
+        },
+        instruction::UpgradeSession { session_hash },
+    )
+}
+
+pub fn create_tournament(
+    game_server: &Pubkey,
+    tournament_id: &str,
+    entry_fee: u64,
+    max_players: u8,
+    prize_percentages: [u8; 3],
+) -> Instruction {
+    build(
+        accounts::CreateTournament {
+            game_server: *game_server,
+            tournament: find_tournament(tournament_id).0,
+            vault: find_tournament_vault(tournament_id).0,
+            vault_token_account: tournament_vault_token_account(tournament_id),
+            mint: TOKEN_ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreateTournament {
+            tournament_id: tournament_id.to_string(),
+            entry_fee,
+            max_players,
+            prize_percentages,
+        },
+    )
+}
+
+pub fn join_tournament(user: &Pubkey, tournament_id: &str) -> Instruction {
+    build(
+        accounts::JoinTournament {
+            user: *user,
+            tournament: find_tournament(tournament_id).0,
+            player_profile: find_player_profile(user).0,
+            user_token_account: player_token_account(user),
+            vault: find_tournament_vault(tournament_id).0,
+            vault_token_account: tournament_vault_token_account(tournament_id),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::JoinTournament {
+            tournament_id: tournament_id.to_string(),
+        },
+    )
+}
+
+/// Spawns the game session of bracket match `match_index` at `session_hash`
+pub fn create_tournament_match(
+    game_server: &Pubkey,
+    tournament_id: &str,
+    match_index: u8,
+    session_hash: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::CreateTournamentMatch {
+            game_server: *game_server,
+            tournament: find_tournament(tournament_id).0,
+            game_session: find_game_session(&session_hash).0,
+            match_state: find_match_state(&session_hash).0,
+            vault: find_vault(&session_hash).0,
+            vault_token_account: vault_token_account(&session_hash),
+            mint: TOKEN_ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreateTournamentMatch {
+            tournament_id: tournament_id.to_string(),
+            match_index,
+            session_hash,
+        },
+    )
+}
+
+/// Pays a finished tournament's prizes, `placings` holding the players from first place down
+pub fn distribute_tournament_prizes(
+    game_server: &Pubkey,
+    tournament_id: &str,
+    placings: &[Pubkey],
+) -> Instruction {
+    let mut ix = build(
+        accounts::DistributeTournamentPrizes {
+            game_server: *game_server,
+            tournament: find_tournament(tournament_id).0,
+            vault: find_tournament_vault(tournament_id).0,
+            vault_token_account: tournament_vault_token_account(tournament_id),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::DistributeTournamentPrizes {
+            tournament_id: tournament_id.to_string(),
+        },
+    );
+    ix.accounts.extend(placings.iter().flat_map(payout_pair));
+    ix
+}
+
+pub fn create_league(
+    game_server: &Pubkey,
+    league_id: &str,
+    entry_fee: u64,
+    max_teams: u8,
+    prize_percentages: [u8; 3],
+) -> Instruction {
+    build(
+        accounts::CreateLeague {
+            game_server: *game_server,
+            league: find_league(league_id).0,
+            vault: find_league_vault(league_id).0,
+            vault_token_account: league_vault_token_account(league_id),
+            mint: TOKEN_ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CreateLeague {
+            league_id: league_id.to_string(),
+            entry_fee,
+            max_teams,
+            prize_percentages,
+        },
+    )
+}
+
+/// Registers the captain's team, `roster` holding every wallet allowed to play for it
+pub fn register_league_team(captain: &Pubkey, league_id: &str, roster: Vec<Pubkey>) -> Instruction {
+    build(
+        accounts::RegisterLeagueTeam {
+            captain: *captain,
+            league: find_league(league_id).0,
+            captain_token_account: player_token_account(captain),
+            vault: find_league_vault(league_id).0,
+            vault_token_account: league_vault_token_account(league_id),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterLeagueTeam {
+            league_id: league_id.to_string(),
+            roster,
+        },
+    )
+}
+
+/// Schedules the session at `session_hash` as the fixture between league teams `home` and `away`
+pub fn link_league_match(
+    game_server: &Pubkey,
+    league_id: &str,
+    session_hash: [u8; 32],
+    home: u8,
+    away: u8,
+) -> Instruction {
+    build(
+        accounts::LinkLeagueMatch {
+            game_server: *game_server,
+            league: find_league(league_id).0,
+            game_session: find_game_session(&session_hash).0,
+        },
+        instruction::LinkLeagueMatch {
+            league_id: league_id.to_string(),
+            session_hash,
+            home,
+            away,
+        },
+    )
+}
+
+/// Pays a league's prizes, `captains` holding the team captains from first place down
+pub fn close_league_season(
+    game_server: &Pubkey,
+    league_id: &str,
+    captains: &[Pubkey],
+) -> Instruction {
+    let mut ix = build(
+        accounts::CloseLeagueSeason {
+            game_server: *game_server,
+            league: find_league(league_id).0,
+            vault: find_league_vault(league_id).0,
+            vault_token_account: league_vault_token_account(league_id),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CloseLeagueSeason {
+            league_id: league_id.to_string(),
+        },
+    );
+    ix.accounts.extend(captains.iter().flat_map(payout_pair));
+    ix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wager_program::state::{GameStatus, Team, GAME_SESSION_RESERVED, GAME_SESSION_VERSION};
+
+    fn session(game_mode: GameMode, teams: Vec<Vec<Pubkey>>) -> GameSession {
+        GameSession {
+            version: GAME_SESSION_VERSION,
+            session_hash: session_hash("test-session"),
+            session_id: None,
+            authority: Pubkey::new_unique(),
+            session_bet: 1_000_000,
+            game_mode,
+            team_size: 2,
+            teams: teams
+                .into_iter()
+                .map(|players| Team {
+                    players,
+                    total_bet: 0,
+                    player_bet: 1_000_000,
+                })
+                .collect(),
+            status: GameStatus::InProgress,
+            created_at: 0,
+            bump: 0,
+            vault_bump: 0,
+            vault_token_bump: 0,
+            free_for_all_players: Vec::new(),
+            series: None,
+            tournament_match: None,
+            league_match: None,
+            k_factor: 0,
+            rating_band: None,
+            linked_wallets: Vec::new(),
+            allowlist: None,
+            team_allowlists: Vec::new(),
+            invite_key: None,
+            token_gate: None,
+            match_log: None,
+            replay: None,
+            reserved: [0; GAME_SESSION_RESERVED],
+        }
+    }
+
+    /// Returns the accounts following the instruction's named accounts
+    fn remaining(ix: &Instruction, named: usize) -> Vec<Pubkey> {
+        ix.accounts[named..]
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect()
+    }
+
+    #[test]
+    fn distribute_winnings_pays_winning_team_then_lists_every_profile() {
+        let players: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let session = session(
+            GameMode::WinnerTakesAll,
+            vec![players[..2].to_vec(), players[2..].to_vec()],
+        );
+        let game_server = Pubkey::new_unique();
+
+        let ix = distribute_winnings(&game_server, &session, 1);
+        let named = distribute_accounts(&game_server, &session)
+            .to_account_metas(None)
+            .len();
+
+        let mut expected = Vec::new();
+        for winner in &players[2..] {
+            expected.extend([*winner, player_token_account(winner)]);
+        }
+        expected.extend(players.iter().map(|player| find_player_profile(player).0));
+        assert_eq!(remaining(&ix, named), expected);
+    }
+
+    #[test]
+    fn distribute_winnings_skips_empty_slots() {
+        let winner = Pubkey::new_unique();
+        let session = session(
+            GameMode::WinnerTakesAll,
+            vec![
+                vec![winner, Pubkey::default()],
+                vec![Pubkey::new_unique(); 2],
+            ],
+        );
+        let game_server = Pubkey::new_unique();
+
+        let ix = distribute_winnings(&game_server, &session, 0);
+        let named = distribute_accounts(&game_server, &session)
+            .to_account_metas(None)
+            .len();
+        assert_eq!(
+            remaining(&ix, named)[..2],
+            [winner, player_token_account(&winner)]
+        );
+        assert!(!remaining(&ix, named).contains(&Pubkey::default()));
+    }
+
+    #[test]
+    fn close_game_session_passes_the_match_log_only_when_kept() {
+        let mut session = session(GameMode::WinnerTakesAll, vec![Vec::new(); 2]);
+        let game_server = Pubkey::new_unique();
+        let match_log = find_match_log(&session.session_hash).0;
+
+        let ix = close_game_session(&game_server, &session);
+        assert!(!ix.accounts.iter().any(|meta| meta.pubkey == match_log));
+
+        session.match_log = Some(match_log);
+        let ix = close_game_session(&game_server, &session);
+        assert!(ix.accounts.iter().any(|meta| meta.pubkey == match_log));
+    }
+
+    #[test]
+    fn refund_wager_pairs_every_joined_player() {
+        let player = Pubkey::new_unique();
+        let session = session(
+            GameMode::WinnerTakesAll,
+            vec![vec![player, Pubkey::default()], vec![Pubkey::default(); 2]],
+        );
+
+        let ix = refund_wager(&Pubkey::new_unique(), &session);
+        let refunds = &ix.accounts[ix.accounts.len() - 3..];
+        assert_eq!(refunds[0].pubkey, player);
+        assert_eq!(refunds[1].pubkey, player_token_account(&player));
+        assert!(refunds[1].is_writable);
+        assert_eq!(refunds[2].pubkey, find_player_profile(&player).0);
+        assert!(refunds[2].is_writable);
+    }
+}
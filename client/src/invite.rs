@@ -0,0 +1,11 @@
+//! Invite keys of password protected lobbies
+
+use anchor_lang::solana_program::hash::hash;
+use solana_sdk::signer::keypair::{keypair_from_seed, Keypair};
+
+/// Returns the keypair an invite code stands for. Sessions store its public key and
+/// joiners co-sign with it, so the code itself never goes on chain
+pub fn invite_keypair(invite_code: &str) -> Keypair {
+    keypair_from_seed(&hash(invite_code.as_bytes()).to_bytes())
+        .expect("a SHA-256 digest is a valid keypair seed")
+}
@@ -0,0 +1,16 @@
+//! Rust client for the wager program: instruction builders, PDA helpers and
+//! account deserializers for game servers and operator tooling.
+
+pub mod accounts;
+pub mod instructions;
+pub mod invite;
+pub mod pda;
+pub mod replay;
+
+pub use wager_program::{
+    state::{
+        GameMode, GameSession, GameStatus, JoinCredentials, League, MatchLog, MatchState,
+        PlayerProfile, ReplayEvent, SessionOptions, Tournament,
+    },
+    ID as PROGRAM_ID, TOKEN_ID,
+};
@@ -0,0 +1,82 @@
+//! Addresses of the accounts the wager program derives from a session hash, competition
+//! ID or wallet
+
+use anchor_lang::solana_program::{hash::hash, pubkey::Pubkey};
+use anchor_spl::associated_token::get_associated_token_address;
+use wager_program::{ID, TOKEN_ID};
+
+/// Returns the hash a session's PDAs are derived from, the SHA-256 of its readable ID
+pub fn session_hash(session_id: &str) -> [u8; 32] {
+    hash(session_id.as_bytes()).to_bytes()
+}
+
+pub fn find_game_session(session_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"game_session", session_hash], &ID)
+}
+
+pub fn find_match_state(session_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"match_state", session_hash], &ID)
+}
+
+pub fn find_match_log(session_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"match_log", session_hash], &ID)
+}
+
+pub fn find_vault(session_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", session_hash], &ID)
+}
+
+/// Returns the vault's token account, the associated token account of the vault PDA
+pub fn vault_token_account(session_hash: &[u8; 32]) -> Pubkey {
+    get_associated_token_address(&find_vault(session_hash).0, &TOKEN_ID)
+}
+
+pub fn find_player_profile(player: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"player_profile", player.as_ref()], &ID)
+}
+
+/// Returns a player's token account for the wager token
+pub fn player_token_account(player: &Pubkey) -> Pubkey {
+    get_associated_token_address(player, &TOKEN_ID)
+}
+
+pub fn find_tournament(tournament_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"tournament", tournament_id.as_bytes()], &ID)
+}
+
+pub fn find_tournament_vault(tournament_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"tournament_vault", tournament_id.as_bytes()], &ID)
+}
+
+/// Returns the token account holding a tournament's entry fees
+pub fn tournament_vault_token_account(tournament_id: &str) -> Pubkey {
+    get_associated_token_address(&find_tournament_vault(tournament_id).0, &TOKEN_ID)
+}
+
+pub fn find_league(league_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"league", league_id.as_bytes()], &ID)
+}
+
+pub fn find_league_vault(league_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"league_vault", league_id.as_bytes()], &ID)
+}
+
+/// Returns the token account holding a league's entry fees
+pub fn league_vault_token_account(league_id: &str) -> Pubkey {
+    get_associated_token_address(&find_league_vault(league_id).0, &TOKEN_ID)
+}
+
+/// Finds a session of the original layout, which was addressed by its readable ID
+pub fn find_legacy_session(session_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"game_session", session_id.as_bytes()], &ID)
+}
+
+/// Finds the vault of a session of the original layout
+pub fn find_legacy_vault(session_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", session_id.as_bytes()], &ID)
+}
+
+/// Returns the token account of a legacy session's vault
+pub fn legacy_vault_token_account(session_id: &str) -> Pubkey {
+    get_associated_token_address(&find_legacy_vault(session_id).0, &TOKEN_ID)
+}
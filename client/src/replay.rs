@@ -0,0 +1,85 @@
+//! Merkle trees of match replays, committed on chain by commit_replay
+
+use anchor_lang::solana_program::hash::hashv;
+
+/// Builds the sorted-pair Merkle tree of `leaves`, returning its root and the proof of `index`.
+/// Pairs are hashed in sorted order like the program does
+pub fn merkle_root_and_proof(leaves: &[[u8; 32]], mut index: usize) -> ([u8; 32], Vec<[u8; 32]>) {
+    let hash_pair = |a: [u8; 32], b: [u8; 32]| hashv(&[&a.min(b), &a.max(b)]).to_bytes();
+
+    let mut level = leaves.to_vec();
+    let mut proof = Vec::new();
+    while level.len() > 1 {
+        // An odd node out is carried up to the next level unpaired
+        if index ^ 1 < level.len() {
+            proof.push(level[index ^ 1]);
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(*a, *b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        index /= 2;
+    }
+    (level[0], proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use wager_program::state::{MatchEventKind, ReplayCommitment, ReplayEvent};
+
+    fn kill_event(index: u32) -> ReplayEvent {
+        ReplayEvent {
+            index,
+            kind: MatchEventKind::Kill,
+            player: Pubkey::new_unique(),
+            team: 0,
+            target: Pubkey::new_unique(),
+            target_team: 1,
+            slot: 100 + index as u64,
+            timestamp: 1_700_000_000 + index as i64,
+        }
+    }
+
+    #[test]
+    fn replay_proves_committed_events() {
+        let events: Vec<ReplayEvent> = (0..5).map(kill_event).collect();
+        let leaves: Vec<[u8; 32]> = events.iter().map(ReplayEvent::leaf).collect();
+
+        for (index, event) in events.iter().enumerate() {
+            let (root, proof) = merkle_root_and_proof(&leaves, index);
+            let replay = ReplayCommitment {
+                root,
+                event_count: events.len() as u32,
+            };
+            assert!(replay.contains(event, &proof));
+        }
+    }
+
+    #[test]
+    fn replay_rejects_tampered_events() {
+        let events: Vec<ReplayEvent> = (0..4).map(kill_event).collect();
+        let leaves: Vec<[u8; 32]> = events.iter().map(ReplayEvent::leaf).collect();
+        let (root, proof) = merkle_root_and_proof(&leaves, 2);
+        let replay = ReplayCommitment {
+            root,
+            event_count: events.len() as u32,
+        };
+
+        let mut tampered = events[2];
+        tampered.target = Pubkey::new_unique();
+        assert!(!replay.contains(&tampered, &proof));
+
+        // Events past the committed count are rejected even with a valid proof
+        let short_replay = ReplayCommitment {
+            event_count: 2,
+            ..replay
+        };
+        assert!(!short_replay.contains(&events[2], &proof));
+    }
+}
@@ -5,7 +5,7 @@ pub enum WagerError {
     #[msg("Game session is not in the correct state")]
     InvalidGameState,
 
-    #[msg("Invalid team selection. Team must be 0 or 1")]
+    #[msg("Invalid team selection. Team must be one of the session's teams")]
     InvalidTeamSelection,
 
     #[msg("Team is already full")]
@@ -88,4 +88,97 @@ pub enum WagerError {
 
     #[msg("Game is not in progress")]
     GameNotInProgress,
+
+    #[msg("Instruction is not supported for this game mode")]
+    InvalidGameMode,
+
+    #[msg("Game session is already full")]
+    SessionIsFull,
+
+    #[msg("Invalid payout percentages")]
+    InvalidPayoutPercentages,
+
+    #[msg("Invalid number of teams for this game mode")]
+    InvalidTeamCount,
+
+    #[msg("Series must be an odd number of games between two teams")]
+    InvalidSeriesLength,
+
+    #[msg("Series has not been decided yet")]
+    SeriesNotDecided,
+
+    #[msg("Tournament must have a power of two number of players")]
+    InvalidTournamentSize,
+
+    #[msg("Invalid tournament bracket match")]
+    InvalidBracketMatch,
+
+    #[msg("Game session does not belong to this tournament")]
+    InvalidTournament,
+
+    #[msg("Tournament final has not been played")]
+    TournamentNotFinished,
+
+    #[msg("Invalid league fixture")]
+    InvalidLeagueMatch,
+
+    #[msg("Game session does not belong to this league")]
+    InvalidLeague,
+
+    #[msg("Player profile does not belong to this player")]
+    InvalidPlayerProfile,
+
+    #[msg("Player rating is outside the session's rating band")]
+    RatingOutOfBand,
+
+    #[msg("Invalid rating band")]
+    InvalidRatingBand,
+
+    #[msg("Player has already joined this session")]
+    PlayerAlreadyJoined,
+
+    #[msg("Player cannot play against a linked wallet")]
+    LinkedWalletOpponent,
+
+    #[msg("Too many linked wallets")]
+    TooManyLinkedWallets,
+
+    #[msg("Player is not on the session's allowlist")]
+    NotOnAllowlist,
+
+    #[msg("Invalid invite code")]
+    InvalidInviteCode,
+
+    #[msg("Player does not hold enough of the gating token")]
+    TokenGateNotMet,
+
+    #[msg("Bet amount is outside the limits for this mint")]
+    InvalidBetAmount,
+
+    #[msg("Session ID must be 1 to 32 letters, digits, dashes or underscores")]
+    InvalidSessionId,
+
+    #[msg("Game session is already on the current layout")]
+    SessionAlreadyMigrated,
+
+    #[msg("Game session has to be upgraded to the current layout first")]
+    SessionNotUpgraded,
+
+    #[msg("Allowlist has more wallets than a session can store")]
+    AllowlistTooLarge,
+
+    #[msg("Session records a match log that was not provided")]
+    MissingMatchLog,
+
+    #[msg("No replay has been committed for this session")]
+    ReplayNotCommitted,
+
+    #[msg("Event is not part of the committed replay")]
+    InvalidReplayProof,
+
+    #[msg("Tournament and league IDs must be 1 to 32 bytes")]
+    InvalidCompetitionId,
+
+    #[msg("Session hash is not the SHA-256 of the session ID")]
+    SessionHashMismatch,
 }
@@ -0,0 +1,173 @@
+use crate::state::{GameMode, GameStatus, ReplayEvent};
+use anchor_lang::prelude::*;
+
+/// A game session was opened
+#[event]
+pub struct SessionCreated {
+    pub session: Pubkey,        // Game session account
+    pub session_hash: [u8; 32], // Hash the session's PDAs are derived from
+    pub authority: Pubkey,      // Game server running the session
+    pub game_mode: GameMode,    // Game configuration
+    pub bet_amount: u64,        // Stake per player
+    pub team_size: u8,          // Players in the largest team
+    pub team_count: u8,         // Number of teams, 0 for free-for-all games
+}
+
+/// A player took a seat in a game session
+#[event]
+pub struct PlayerJoined {
+    pub session: Pubkey,  // Game session account
+    pub player: Pubkey,   // Joining wallet
+    pub team: Option<u8>, // Team joined, none for free-for-all games
+    pub amount: u64,      // Stake paid into the vault
+}
+
+/// A player bought more spawns in a pay-to-spawn game
+#[event]
+pub struct SpawnPurchased {
+    pub session: Pubkey, // Game session account
+    pub player: Pubkey,  // Buying wallet
+    pub team: u8,        // Team the player is on
+    pub amount: u64,     // Price paid into the vault
+}
+
+/// The game server recorded a kill
+#[event]
+pub struct KillRecorded {
+    pub session: Pubkey, // Game session account
+    pub killer: Pubkey,  // Player credited with the kill
+    pub killer_team: u8, // Team of the killer
+    pub victim: Pubkey,  // Player that lost a spawn
+    pub victim_team: u8, // Team of the victim
+}
+
+/// A game of a best-of-N series was settled
+#[event]
+pub struct SeriesGameRecorded {
+    pub session: Pubkey,  // Game session account
+    pub winning_team: u8, // Team that won the game
+    pub games_played: u8, // Games played in the series so far
+}
+
+/// A player was paid out of a game session's vault
+#[event]
+pub struct WinningsPaid {
+    pub session: Pubkey, // Game session account
+    pub player: Pubkey,  // Paid wallet
+    pub amount: u64,     // Tokens transferred
+}
+
+/// A player's stake was returned from a game session's vault
+#[event]
+pub struct RefundIssued {
+    pub session: Pubkey, // Game session account
+    pub player: Pubkey,  // Refunded wallet
+    pub amount: u64,     // Tokens transferred
+}
+
+/// A game session moved to a new status
+#[event]
+pub struct StatusChanged {
+    pub session: Pubkey,  // Game session account
+    pub from: GameStatus, // Previous status
+    pub to: GameStatus,   // New status
+}
+
+/// A tournament or league paid a prize out of its pool
+#[event]
+pub struct PrizePaid {
+    pub competition: Pubkey, // Tournament or league account
+    pub player: Pubkey,      // Paid wallet
+    pub place: u8,           // Finishing place, 1 for the champion
+    pub amount: u64,         // Tokens transferred
+}
+
+/// A game session's match log was opened
+#[event]
+pub struct MatchLogCreated {
+    pub session: Pubkey,   // Game session account
+    pub match_log: Pubkey, // Match log account
+}
+
+/// A settled game session was closed and its rent returned
+#[event]
+pub struct SessionClosed {
+    pub session: Pubkey,   // Game session account
+    pub authority: Pubkey, // Game server the rent was returned to
+}
+
+/// A tournament was opened for registration
+#[event]
+pub struct TournamentCreated {
+    pub tournament: Pubkey, // Tournament account
+    pub authority: Pubkey,  // Game server running the tournament
+    pub entry_fee: u64,     // Fee each player pays to enter
+    pub max_players: u8,    // Size of the bracket
+}
+
+/// A player entered a tournament
+#[event]
+pub struct TournamentJoined {
+    pub tournament: Pubkey, // Tournament account
+    pub player: Pubkey,     // Entering wallet
+    pub amount: u64,        // Entry fee paid into the vault
+}
+
+/// A league was opened for registration
+#[event]
+pub struct LeagueCreated {
+    pub league: Pubkey,    // League account
+    pub authority: Pubkey, // Game server running the league
+    pub entry_fee: u64,    // Fee each team pays to enter
+    pub max_teams: u8,     // Number of teams the league holds
+}
+
+/// A team registered for a league
+#[event]
+pub struct LeagueTeamRegistered {
+    pub league: Pubkey,  // League account
+    pub captain: Pubkey, // Wallet that registered the team
+    pub team: u8,        // Index of the team in the league
+    pub roster_size: u8, // Wallets allowed to play for the team
+    pub amount: u64,     // Entry fee paid into the vault
+}
+
+/// A game session was scheduled as a league fixture
+#[event]
+pub struct LeagueMatchLinked {
+    pub league: Pubkey,  // League account
+    pub session: Pubkey, // Game session account
+    pub home: u8,        // League team playing as team 0
+    pub away: u8,        // League team playing as team 1
+}
+
+/// A league fixture was settled as a draw, returning every stake
+#[event]
+pub struct LeagueMatchDrawn {
+    pub league: Pubkey,  // League account
+    pub session: Pubkey, // Game session account
+    pub home: u8,        // League team playing as team 0
+    pub away: u8,        // League team playing as team 1
+}
+
+/// The game server committed the Merkle root of a session's full event log
+#[event]
+pub struct ReplayCommitted {
+    pub session: Pubkey,  // Game session account
+    pub root: [u8; 32],   // Root of the tree of replay events
+    pub event_count: u32, // Number of events in the log
+}
+
+/// A replay event was proven to be part of a session's committed log
+#[event]
+pub struct ReplayEventVerified {
+    pub session: Pubkey,    // Game session account
+    pub event: ReplayEvent, // Event that was proven
+}
+
+/// A game session was rewritten on the current account layout
+#[event]
+pub struct SessionMigrated {
+    pub session: Pubkey, // Game session account
+    pub version: u8,     // Layout version after migration
+}
@@ -0,0 +1,61 @@
+use crate::{errors::WagerError, events::SessionClosed, state::*};
+use anchor_lang::prelude::*;
+
+pub fn close_game_session_handler(
+    ctx: Context<CloseGameSession>,
+    _session_hash: [u8; 32],
+) -> Result<()> {
+    let game_session = &ctx.accounts.game_session;
+
+    // Only settled sessions can be closed
+    require!(
+        game_session.status == GameStatus::Completed,
+        WagerError::GameNotCompleted
+    );
+
+    // A match log is closed together with its session
+    require!(
+        game_session.match_log.is_none() || ctx.accounts.match_log.is_some(),
+        WagerError::MissingMatchLog
+    );
+
+    emit!(SessionClosed {
+        session: game_session.key(),
+        authority: game_session.authority,
+    });
+
+    msg!("Closing game session: {}", game_session.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(session_hash: [u8; 32])]
+pub struct CloseGameSession<'info> {
+    #[account(mut)]
+    pub game_server: Signer<'info>,
+
+    #[account(
+        mut,
+        close = game_server,
+        seeds = [b"game_session", session_hash.as_ref()],
+        bump = game_session.bump,
+        constraint = game_session.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(
+        mut,
+        close = game_server,
+        seeds = [b"match_state", session_hash.as_ref()],
+        bump = match_state.load()?.bump,
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
+    #[account(
+        mut,
+        close = game_server,
+        seeds = [b"match_log", session_hash.as_ref()],
+        bump = match_log.load()?.bump,
+    )]
+    pub match_log: Option<AccountLoader<'info, MatchLog>>,
+}
@@ -0,0 +1,151 @@
+use crate::{errors::WagerError, events::PrizePaid, state::*, TOKEN_ID};
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Token, TokenAccount};
+
+pub fn close_league_season_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CloseLeagueSeason<'info>>,
+    league_id: String,
+) -> Result<()> {
+    let league = &ctx.accounts.league;
+    msg!("Closing season for league: {}", league_id);
+
+    require!(
+        league.status != LeagueStatus::Completed,
+        WagerError::InvalidGameState
+    );
+
+    // Calculate the prize pool (sum of every entry fee)
+    let prize_pool = league
+        .entry_fee
+        .checked_mul(league.teams.len() as u64)
+        .ok_or(WagerError::TotalPotCalculationError)?;
+    msg!("Prize pool calculated: {}", prize_pool);
+
+    // Work out each place's prize, first place taking the rounding dust and
+    // the share of any place no team finished in so the vault empties
+    let paid_places = league
+        .prize_percentages
+        .iter()
+        .take(league.standings.len())
+        .filter(|p| **p > 0)
+        .count();
+    let mut place_prizes = vec![0u64; paid_places];
+    for (place, percentage) in league
+        .prize_percentages
+        .iter()
+        .enumerate()
+        .take(paid_places)
+        .skip(1)
+    {
+        place_prizes[place] = prize_pool
+            .checked_mul(*percentage as u64)
+            .ok_or(WagerError::WinningsCalculationError)?
+            / 100;
+    }
+    if paid_places > 0 {
+        place_prizes[0] = prize_pool
+            .checked_sub(place_prizes.iter().sum())
+            .ok_or(WagerError::WinningsCalculationError)?;
+    }
+
+    // Remaining accounts hold (captain, token account) pairs in final standings order
+    require!(
+        ctx.remaining_accounts.len() >= 2 * paid_places,
+        WagerError::InvalidRemainingAccounts
+    );
+
+    for (place, prize) in place_prizes.iter().enumerate() {
+        let expected_captain = league.teams[league.standings[place] as usize].captain;
+
+        // Get captain and captain token account
+        let captain = &ctx.remaining_accounts[place * 2];
+        let captain_token_account_info = &ctx.remaining_accounts[place * 2 + 1];
+        let captain_token_account = Account::<TokenAccount>::try_from(captain_token_account_info)?;
+
+        require!(captain.key() == expected_captain, WagerError::InvalidWinner);
+
+        // Verify captain constraints
+        require!(
+            captain_token_account.owner == captain.key(),
+            WagerError::InvalidWinnerTokenAccount
+        );
+
+        // Verify token account mint
+        require!(
+            captain_token_account.mint == TOKEN_ID,
+            WagerError::InvalidTokenMint
+        );
+
+        msg!(
+            "Prize for place {} {}: {}",
+            place + 1,
+            expected_captain,
+            prize
+        );
+
+        // Transfer tokens from the league vault to the captain
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: captain_token_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                &[&[
+                    b"league_vault",
+                    league_id.as_bytes(),
+                    &[ctx.accounts.league.vault_bump],
+                ]],
+            ),
+            *prize,
+        )?;
+        emit!(PrizePaid {
+            competition: ctx.accounts.league.key(),
+            player: expected_captain,
+            place: place as u8 + 1,
+            amount: *prize,
+        });
+    }
+
+    // Mark season as completed
+    let league = &mut ctx.accounts.league;
+    league.status = LeagueStatus::Completed;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(league_id: String)]
+pub struct CloseLeagueSeason<'info> {
+    /// The admin that created the league
+    pub game_server: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"league", league_id.as_bytes()],
+        bump = league.bump,
+        constraint = league.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
+    )]
+    pub league: Account<'info, League>,
+
+    /// CHECK: Vault PDA that holds the entry fees
+    #[account(
+        mut,
+        seeds = [b"league_vault", league_id.as_bytes()],
+        bump = league.vault_bump,
+    )]
+    pub vault: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = TOKEN_ID,
+        associated_token::authority = vault
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,49 @@
+use crate::{errors::WagerError, events::ReplayCommitted, state::*};
+use anchor_lang::prelude::*;
+
+pub fn commit_replay_handler(
+    ctx: Context<CommitReplay>,
+    _session_hash: [u8; 32],
+    root: [u8; 32],
+    event_count: u32,
+) -> Result<()> {
+    let game_session = &mut ctx.accounts.game_session;
+
+    // Sessions allocated before replays were committed have no room for one
+    require!(
+        game_session.version == GAME_SESSION_VERSION,
+        WagerError::SessionNotUpgraded
+    );
+
+    // The replay is committed once, alongside settlement, before the session completes
+    require!(
+        matches!(
+            game_session.status,
+            GameStatus::InProgress | GameStatus::Ended
+        ) && game_session.replay.is_none(),
+        WagerError::InvalidGameState
+    );
+
+    game_session.replay = Some(ReplayCommitment { root, event_count });
+
+    emit!(ReplayCommitted {
+        session: game_session.key(),
+        root,
+        event_count,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(session_hash: [u8; 32])]
+pub struct CommitReplay<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_session", session_hash.as_ref()],
+        bump = game_session.bump,
+        constraint = game_session.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    pub game_server: Signer<'info>,
+}
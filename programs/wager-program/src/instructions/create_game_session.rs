@@ -1,28 +1,163 @@
 use crate::errors::WagerError;
+use crate::events::SessionCreated;
 use crate::state::*;
 use crate::TOKEN_ID;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{Token, TokenAccount};
 
 pub fn create_game_session_handler(
     ctx: Context<CreateGameSession>,
-    session_id: String,
+    session_hash: [u8; 32],
     bet_amount: u64,
     game_mode: GameMode,
+    team_size: u8,
+    team_count: u8,
+    options: SessionOptions,
 ) -> Result<()> {
     let clock = Clock::get()?;
+    // Sessions are addressed by their hash, the readable ID is only metadata but has
+    // to be what the hash was taken of
+    if let Some(session_id) = &options.session_id {
+        validate_session_id(session_id)?;
+        require!(
+            hash(session_id.as_bytes()).to_bytes() == session_hash,
+            WagerError::SessionHashMismatch
+        );
+    }
+    game_mode.validate(team_size, team_count)?;
+    game_mode.validate_bet(ctx.accounts.mint.key(), bet_amount)?;
+
+    // Teams share the session size and bet unless an asymmetric layout is given
+    let team_configs = &options.team_configs;
+    let teams = if team_configs.is_empty() {
+        vec![Team::new(team_size as usize, bet_amount); team_count as usize]
+    } else {
+        // Only winner takes all payouts account for differing stakes
+        require!(
+            game_mode == GameMode::WinnerTakesAll,
+            WagerError::InvalidGameMode
+        );
+        require!(
+            team_configs.len() == team_count as usize,
+            WagerError::InvalidTeamCount
+        );
+        require!(
+            team_configs
+                .iter()
+                .all(|config| (1..=team_size).contains(&config.size)),
+            WagerError::InvalidPlayerCount
+        );
+        for config in team_configs {
+            game_mode.validate_bet(ctx.accounts.mint.key(), config.player_bet)?;
+        }
+
+        team_configs
+            .iter()
+            .map(|config| Team::new(config.size as usize, config.player_bet))
+            .collect()
+    };
+
+    // A single game needs no series, anything longer is played out of the same escrow
+    let best_of = options.best_of;
+    let series = if best_of <= 1 {
+        None
+    } else {
+        require!(
+            matches!(game_mode, GameMode::WinnerTakesAll | GameMode::Practice),
+            WagerError::InvalidGameMode
+        );
+        require!(
+            best_of % 2 == 1 && best_of <= MAX_SERIES_GAMES && team_count == 2,
+            WagerError::InvalidSeriesLength
+        );
+
+        Some(Series {
+            best_of,
+            team_wins: vec![0; team_count as usize],
+            game_winners: Vec::with_capacity(best_of as usize),
+        })
+    };
+
+    // Rated sessions can restrict who joins to a band of ratings
+    if let Some(band) = options.rating_band {
+        require!(
+            band.min_rating <= band.max_rating,
+            WagerError::InvalidRatingBand
+        );
+    }
+
+    // Linked wallets are stored on the session, so keep the groups within their bounds
+    require!(
+        options.linked_wallets.len() <= MAX_LINKED_WALLET_GROUPS
+            && options
+                .linked_wallets
+                .iter()
+                .all(|group| group.len() <= MAX_LINKED_GROUP_SIZE),
+        WagerError::TooManyLinkedWallets
+    );
+
+    // Team allowlists lock each side of a team game
+    require!(
+        options.team_allowlists.is_empty() || options.team_allowlists.len() == team_count as usize,
+        WagerError::InvalidTeamCount
+    );
+    for allowlist in options
+        .allowlist
+        .iter()
+        .chain(options.team_allowlists.iter().flatten())
+    {
+        allowlist.validate()?;
+    }
+
+    let k_factor = if options.k_factor == 0 {
+        DEFAULT_K_FACTOR
+    } else {
+        options.k_factor
+    };
+
     let game_session = &mut ctx.accounts.game_session;
 
-    game_session.session_id = session_id;
+    game_session.version = GAME_SESSION_VERSION;
+    game_session.session_hash = session_hash;
+    game_session.session_id = options.session_id;
     game_session.authority = ctx.accounts.game_server.key();
     game_session.session_bet = bet_amount;
     game_session.game_mode = game_mode;
+    game_session.team_size = team_size;
+    game_session.teams = teams;
+    game_session.series = series;
+    game_session.k_factor = k_factor;
+    game_session.rating_band = options.rating_band;
+    game_session.linked_wallets = options.linked_wallets;
+    game_session.allowlist = options.allowlist;
+    game_session.team_allowlists = options.team_allowlists;
+    game_session.invite_key = options.invite_key;
+    game_session.token_gate = options.token_gate;
     game_session.status = GameStatus::WaitingForPlayers;
     game_session.created_at = clock.unix_timestamp;
     game_session.bump = ctx.bumps.game_session;
     game_session.vault_bump = ctx.bumps.vault;
 
+    ctx.accounts.match_state.load_init()?.init(
+        game_session.key(),
+        game_session.authority,
+        &game_mode,
+        team_count,
+        ctx.bumps.match_state,
+    );
+
+    emit!(SessionCreated {
+        session: game_session.key(),
+        session_hash,
+        authority: game_session.authority,
+        game_mode,
+        bet_amount,
+        team_size,
+        team_count,
+    });
+
     // Log all the accounts
     msg!("Game session: {}", game_session.key());
     msg!("Vault: {}", ctx.accounts.vault.key());
@@ -34,7 +169,7 @@ pub fn create_game_session_handler(
 }
 
 #[derive(Accounts)]
-#[instruction(session_id: String)]
+#[instruction(session_hash: [u8; 32], bet_amount: u64, game_mode: GameMode, team_size: u8, team_count: u8, options: SessionOptions)]
 pub struct CreateGameSession<'info> {
     #[account(mut)]
     pub game_server: Signer<'info>,
@@ -42,18 +177,28 @@ pub struct CreateGameSession<'info> {
     #[account(
         init,
         payer = game_server,
-        space = 8 + 4 + 10 + 32 + 8 + 1 + (2 * (32 * 5 + 16 * 5 + 16 * 5 + 8)) + 1 + 8 + 1 + 1 + 1,
-        seeds = [b"game_session", session_id.as_bytes()],
+        space = GameSession::LEN,
+        seeds = [b"game_session", session_hash.as_ref()],
         bump
     )]
     pub game_session: Account<'info, GameSession>,
 
+    /// Live combat state of the session, kept zero-copy for the hot path
+    #[account(
+        init,
+        payer = game_server,
+        space = MatchState::LEN,
+        seeds = [b"match_state", session_hash.as_ref()],
+        bump
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
     /// CHECK: This is safe as it's just used to store SOL
     #[account(
         init,
         payer = game_server,
         space = 0,
-        seeds = [b"vault", session_id.as_bytes()],
+        seeds = [b"vault", session_hash.as_ref()],
         bump
     )]
     pub vault: AccountInfo<'info>,
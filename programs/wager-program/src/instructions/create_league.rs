@@ -0,0 +1,103 @@
+use crate::errors::WagerError;
+use crate::events::LeagueCreated;
+use crate::state::*;
+use crate::TOKEN_ID;
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Token, TokenAccount};
+
+pub fn create_league_handler(
+    ctx: Context<CreateLeague>,
+    league_id: String,
+    entry_fee: u64,
+    max_teams: u8,
+    prize_percentages: [u8; 3],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    validate_competition_id(&league_id)?;
+
+    // Entry fees are held to the same limits as session bets
+    validate_stake(ctx.accounts.mint.key(), entry_fee)?;
+
+    require!(
+        (MIN_TEAMS..=MAX_LEAGUE_TEAMS).contains(&max_teams),
+        WagerError::InvalidTeamCount
+    );
+    validate_payout_percentages(&prize_percentages, max_teams)?;
+
+    let league = &mut ctx.accounts.league;
+
+    league.league_id = league_id;
+    league.authority = ctx.accounts.game_server.key();
+    league.entry_fee = entry_fee;
+    league.max_teams = max_teams;
+    league.prize_percentages = prize_percentages;
+    league.teams = Vec::with_capacity(max_teams as usize);
+    league.standings = Vec::with_capacity(max_teams as usize);
+    league.linked_fixtures = 0;
+    league.status = LeagueStatus::Registration;
+    league.created_at = clock.unix_timestamp;
+    league.bump = ctx.bumps.league;
+    league.vault_bump = ctx.bumps.vault;
+
+    emit!(LeagueCreated {
+        league: league.key(),
+        authority: league.authority,
+        entry_fee,
+        max_teams,
+    });
+
+    // Log all the accounts
+    msg!("League: {}", league.key());
+    msg!("Vault: {}", ctx.accounts.vault.key());
+    msg!(
+        "Vault token account: {}",
+        ctx.accounts.vault_token_account.key()
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(league_id: String, entry_fee: u64, max_teams: u8)]
+pub struct CreateLeague<'info> {
+    #[account(mut)]
+    pub game_server: Signer<'info>,
+
+    #[account(
+        init,
+        payer = game_server,
+        space = League::space(max_teams),
+        seeds = [b"league", league_id.as_bytes()],
+        bump
+    )]
+    pub league: Account<'info, League>,
+
+    /// CHECK: This is safe as it's just used to store SOL
+    #[account(
+        init,
+        payer = game_server,
+        space = 0,
+        seeds = [b"league_vault", league_id.as_bytes()],
+        bump
+    )]
+    pub vault: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = game_server,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = TOKEN_ID @ WagerError::InvalidMint
+    )]
+    pub mint: Account<'info, anchor_spl::token::Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
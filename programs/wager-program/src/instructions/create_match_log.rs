@@ -0,0 +1,67 @@
+use crate::{errors::WagerError, events::MatchLogCreated, state::*};
+use anchor_lang::prelude::*;
+
+pub fn create_match_log_handler(
+    ctx: Context<CreateMatchLog>,
+    _session_hash: [u8; 32],
+) -> Result<()> {
+    let game_session = &mut ctx.accounts.game_session;
+
+    // The log has to be in place before the match is decided
+    require!(
+        matches!(
+            game_session.status,
+            GameStatus::WaitingForPlayers | GameStatus::InProgress
+        ) && game_session.match_log.is_none(),
+        WagerError::InvalidGameState
+    );
+
+    let mut match_log = ctx.accounts.match_log.load_init()?;
+    match_log.session = game_session.key();
+    match_log.bump = ctx.bumps.match_log;
+
+    // Kills and spawn purchases have to be logged from now on
+    game_session.match_log = Some(ctx.accounts.match_log.key());
+    ctx.accounts.match_state.load_mut()?.match_log = 1;
+
+    emit!(MatchLogCreated {
+        session: game_session.key(),
+        match_log: ctx.accounts.match_log.key(),
+    });
+
+    msg!("Match log: {}", ctx.accounts.match_log.key());
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(session_hash: [u8; 32])]
+pub struct CreateMatchLog<'info> {
+    #[account(mut)]
+    pub game_server: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_session", session_hash.as_ref()],
+        bump = game_session.bump,
+        constraint = game_session.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(
+        mut,
+        seeds = [b"match_state", session_hash.as_ref()],
+        bump = match_state.load()?.bump,
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
+    #[account(
+        init,
+        payer = game_server,
+        space = MatchLog::LEN,
+        seeds = [b"match_log", session_hash.as_ref()],
+        bump
+    )]
+    pub match_log: AccountLoader<'info, MatchLog>,
+
+    pub system_program: Program<'info, System>,
+}
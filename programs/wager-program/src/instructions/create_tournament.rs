@@ -0,0 +1,104 @@
+use crate::errors::WagerError;
+use crate::events::TournamentCreated;
+use crate::state::*;
+use crate::TOKEN_ID;
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Token, TokenAccount};
+
+pub fn create_tournament_handler(
+    ctx: Context<CreateTournament>,
+    tournament_id: String,
+    entry_fee: u64,
+    max_players: u8,
+    prize_percentages: [u8; 3],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    validate_competition_id(&tournament_id)?;
+
+    // Entry fees are held to the same limits as session bets
+    validate_stake(ctx.accounts.mint.key(), entry_fee)?;
+
+    // Single elimination needs every round to pair up evenly
+    require!(
+        (MIN_TOURNAMENT_PLAYERS..=MAX_TOURNAMENT_PLAYERS).contains(&max_players)
+            && max_players.is_power_of_two(),
+        WagerError::InvalidTournamentSize
+    );
+    validate_payout_percentages(&prize_percentages, max_players)?;
+
+    let tournament = &mut ctx.accounts.tournament;
+
+    tournament.tournament_id = tournament_id;
+    tournament.authority = ctx.accounts.game_server.key();
+    tournament.entry_fee = entry_fee;
+    tournament.max_players = max_players;
+    tournament.prize_percentages = prize_percentages;
+    tournament.players = Vec::with_capacity(max_players as usize);
+    tournament.matches = vec![BracketMatch::default(); max_players as usize - 1];
+    tournament.status = TournamentStatus::Registration;
+    tournament.created_at = clock.unix_timestamp;
+    tournament.bump = ctx.bumps.tournament;
+    tournament.vault_bump = ctx.bumps.vault;
+
+    emit!(TournamentCreated {
+        tournament: tournament.key(),
+        authority: tournament.authority,
+        entry_fee,
+        max_players,
+    });
+
+    // Log all the accounts
+    msg!("Tournament: {}", tournament.key());
+    msg!("Vault: {}", ctx.accounts.vault.key());
+    msg!(
+        "Vault token account: {}",
+        ctx.accounts.vault_token_account.key()
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(tournament_id: String, entry_fee: u64, max_players: u8)]
+pub struct CreateTournament<'info> {
+    #[account(mut)]
+    pub game_server: Signer<'info>,
+
+    #[account(
+        init,
+        payer = game_server,
+        space = Tournament::space(max_players),
+        seeds = [b"tournament", tournament_id.as_bytes()],
+        bump
+    )]
+    pub tournament: Account<'info, Tournament>,
+
+    /// CHECK: This is safe as it's just used to store SOL
+    #[account(
+        init,
+        payer = game_server,
+        space = 0,
+        seeds = [b"tournament_vault", tournament_id.as_bytes()],
+        bump
+    )]
+    pub vault: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = game_server,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = TOKEN_ID @ WagerError::InvalidMint
+    )]
+    pub mint: Account<'info, anchor_spl::token::Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
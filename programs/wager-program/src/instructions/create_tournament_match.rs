@@ -0,0 +1,160 @@
+use crate::errors::WagerError;
+use crate::events::SessionCreated;
+use crate::state::*;
+use crate::TOKEN_ID;
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Token, TokenAccount};
+
+/// Tournament matches are always played one against one
+const TOURNAMENT_TEAM_SIZE: u8 = 1;
+const TOURNAMENT_TEAM_COUNT: u8 = 2;
+
+pub fn create_tournament_match_handler(
+    ctx: Context<CreateTournamentMatch>,
+    _tournament_id: String,
+    match_index: u8,
+    session_hash: [u8; 32],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let tournament = &mut ctx.accounts.tournament;
+
+    require!(
+        tournament.status == TournamentStatus::InProgress,
+        WagerError::InvalidGameState
+    );
+
+    // Both players must have advanced and the match can only be spawned once
+    let bracket_match = tournament.get_match(match_index)?;
+    require!(
+        bracket_match.player_a != Pubkey::default()
+            && bracket_match.player_b != Pubkey::default()
+            && bracket_match.game_session == Pubkey::default(),
+        WagerError::InvalidBracketMatch
+    );
+    let (player_a, player_b) = (bracket_match.player_a, bracket_match.player_b);
+
+    let game_session = &mut ctx.accounts.game_session;
+    tournament.matches[match_index as usize].game_session = game_session.key();
+
+    // Entry fees are already escrowed by the tournament, so the match itself has no stake
+    let mut teams =
+        vec![Team::new(TOURNAMENT_TEAM_SIZE as usize, 0); TOURNAMENT_TEAM_COUNT as usize];
+    for (team, player) in teams.iter_mut().zip([player_a, player_b]) {
+        team.players[0] = player;
+    }
+
+    game_session.version = GAME_SESSION_VERSION;
+    game_session.session_hash = session_hash;
+    game_session.authority = ctx.accounts.game_server.key();
+    game_session.session_bet = 0;
+    game_session.game_mode = GameMode::WinnerTakesAll;
+    game_session.team_size = TOURNAMENT_TEAM_SIZE;
+    game_session.teams = teams;
+    game_session.created_at = clock.unix_timestamp;
+    game_session.bump = ctx.bumps.game_session;
+    game_session.vault_bump = ctx.bumps.vault;
+    game_session.k_factor = DEFAULT_K_FACTOR;
+    game_session.tournament_match = Some(TournamentMatchLink {
+        tournament: tournament.key(),
+        match_index,
+    });
+
+    // Both players are seated straight away, so the match starts immediately
+    let mut match_state = ctx.accounts.match_state.load_init()?;
+    match_state.init(
+        game_session.key(),
+        game_session.authority,
+        &GameMode::WinnerTakesAll,
+        TOURNAMENT_TEAM_COUNT,
+        ctx.bumps.match_state,
+    );
+    for (team, player) in [player_a, player_b].into_iter().enumerate() {
+        match_state.seat_player(MatchState::team_seat(team as u8, 0), player);
+    }
+    match_state.set_status(GameStatus::InProgress);
+
+    emit!(SessionCreated {
+        session: game_session.key(),
+        session_hash,
+        authority: game_session.authority,
+        game_mode: GameMode::WinnerTakesAll,
+        bet_amount: 0,
+        team_size: TOURNAMENT_TEAM_SIZE,
+        team_count: TOURNAMENT_TEAM_COUNT,
+    });
+    let session = game_session.key();
+    game_session.set_status(session, GameStatus::InProgress);
+
+    // Log all the accounts
+    msg!("Game session: {}", game_session.key());
+    msg!("Vault: {}", ctx.accounts.vault.key());
+    msg!(
+        "Vault token account: {}",
+        ctx.accounts.vault_token_account.key()
+    );
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(tournament_id: String, match_index: u8, session_hash: [u8; 32])]
+pub struct CreateTournamentMatch<'info> {
+    #[account(mut)]
+    pub game_server: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"tournament", tournament_id.as_bytes()],
+        bump = tournament.bump,
+        constraint = tournament.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
+    )]
+    pub tournament: Account<'info, Tournament>,
+
+    #[account(
+        init,
+        payer = game_server,
+        space = GameSession::LEN,
+        seeds = [b"game_session", session_hash.as_ref()],
+        bump
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    /// Live combat state of the session, kept zero-copy for the hot path
+    #[account(
+        init,
+        payer = game_server,
+        space = MatchState::LEN,
+        seeds = [b"match_state", session_hash.as_ref()],
+        bump
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
+    /// CHECK: This is safe as it's just used to store SOL
+    #[account(
+        init,
+        payer = game_server,
+        space = 0,
+        seeds = [b"vault", session_hash.as_ref()],
+        bump
+    )]
+    pub vault: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = game_server,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = TOKEN_ID @ WagerError::InvalidMint
+    )]
+    pub mint: Account<'info, anchor_spl::token::Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
@@ -0,0 +1,155 @@
+use crate::{errors::WagerError, events::PrizePaid, state::*, TOKEN_ID};
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Token, TokenAccount};
+
+pub fn distribute_tournament_prizes_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DistributeTournamentPrizes<'info>>,
+    tournament_id: String,
+) -> Result<()> {
+    let tournament = &ctx.accounts.tournament;
+    msg!(
+        "Starting prize distribution for tournament: {}",
+        tournament_id
+    );
+
+    require!(
+        tournament.status == TournamentStatus::InProgress,
+        WagerError::InvalidGameState
+    );
+
+    let final_match = tournament.get_final();
+    let champion = final_match.winner;
+    let runner_up = final_match
+        .get_loser()
+        .ok_or(WagerError::TournamentNotFinished)?;
+    let semifinal_losers = tournament.get_semifinal_losers();
+
+    // Calculate the prize pool (sum of every entry fee)
+    let prize_pool = tournament
+        .entry_fee
+        .checked_mul(tournament.players.len() as u64)
+        .ok_or(WagerError::TotalPotCalculationError)?;
+    msg!("Prize pool calculated: {}", prize_pool);
+
+    // Work out each prize, semifinalists splitting third place and the
+    // champion taking the rounding dust so the vault empties
+    let [_, runner_up_percentage, semifinal_percentage] = tournament.prize_percentages;
+    let runner_up_prize = prize_pool
+        .checked_mul(runner_up_percentage as u64)
+        .ok_or(WagerError::WinningsCalculationError)?
+        / 100;
+    let semifinal_prize = if semifinal_losers.is_empty() {
+        0
+    } else {
+        prize_pool
+            .checked_mul(semifinal_percentage as u64)
+            .ok_or(WagerError::WinningsCalculationError)?
+            / 100
+            / semifinal_losers.len() as u64
+    };
+    let champion_prize = prize_pool
+        .checked_sub(runner_up_prize + semifinal_prize * semifinal_losers.len() as u64)
+        .ok_or(WagerError::WinningsCalculationError)?;
+
+    // Remaining accounts hold (player, token account) pairs for the champion,
+    // the runner-up and then the semifinalists in bracket order
+    let mut prizes = vec![
+        (champion, champion_prize, 1),
+        (runner_up, runner_up_prize, 2),
+    ];
+    prizes.extend(semifinal_losers.iter().map(|p| (*p, semifinal_prize, 3)));
+    prizes.retain(|(_, prize, _)| *prize > 0);
+
+    require!(
+        ctx.remaining_accounts.len() >= 2 * prizes.len(),
+        WagerError::InvalidRemainingAccounts
+    );
+
+    for (i, (expected_winner, prize, place)) in prizes.iter().enumerate() {
+        // Get winner and winner token account
+        let winner = &ctx.remaining_accounts[i * 2];
+        let winner_token_account_info = &ctx.remaining_accounts[i * 2 + 1];
+        let winner_token_account = Account::<TokenAccount>::try_from(winner_token_account_info)?;
+
+        require!(winner.key() == *expected_winner, WagerError::InvalidWinner);
+
+        // Verify winner constraints
+        require!(
+            winner_token_account.owner == winner.key(),
+            WagerError::InvalidWinnerTokenAccount
+        );
+
+        // Verify token account mint
+        require!(
+            winner_token_account.mint == TOKEN_ID,
+            WagerError::InvalidTokenMint
+        );
+
+        msg!("Prize for player {}: {}", expected_winner, prize);
+
+        // Transfer tokens from the tournament vault to the winner
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: winner_token_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                &[&[
+                    b"tournament_vault",
+                    tournament_id.as_bytes(),
+                    &[ctx.accounts.tournament.vault_bump],
+                ]],
+            ),
+            *prize,
+        )?;
+        emit!(PrizePaid {
+            competition: ctx.accounts.tournament.key(),
+            player: *expected_winner,
+            place: *place,
+            amount: *prize,
+        });
+    }
+
+    // Mark tournament as completed
+    let tournament = &mut ctx.accounts.tournament;
+    tournament.status = TournamentStatus::Completed;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(tournament_id: String)]
+pub struct DistributeTournamentPrizes<'info> {
+    /// The game server authority that created the tournament
+    pub game_server: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"tournament", tournament_id.as_bytes()],
+        bump = tournament.bump,
+        constraint = tournament.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
+    )]
+    pub tournament: Account<'info, Tournament>,
+
+    /// CHECK: Vault PDA that holds the entry fees
+    #[account(
+        mut,
+        seeds = [b"tournament_vault", tournament_id.as_bytes()],
+        bump = tournament.vault_bump,
+    )]
+    pub vault: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = TOKEN_ID,
+        associated_token::authority = vault
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
@@ -1,55 +1,80 @@
-use crate::{errors::WagerError, state::*, TOKEN_ID};
+use crate::{errors::WagerError, events::WinningsPaid, state::*, utils::*, TOKEN_ID};
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{Token, TokenAccount};
 
 pub fn distribute_pay_spawn_earnings<'info>(
     ctx: Context<'_, '_, 'info, 'info, DistributeWinnings<'info>>,
-    session_id: String,
+    session_hash: [u8; 32],
 ) -> Result<()> {
+    // Complete the session before anything is paid so it can't be settled twice
+    let session = ctx.accounts.game_session.key();
+    ctx.accounts.game_session.start_settlement(session)?;
+
     let game_session = &ctx.accounts.game_session;
-    msg!("Starting distribution for session: {}", session_id);
+    msg!("Starting distribution for session: {}", session);
 
-    let players = game_session.get_all_players();
+    let match_state = ctx.accounts.match_state.load()?;
+    let players = game_session.get_joined_players();
     msg!("Number of players: {}", players.len());
     msg!(
         "Number of remaining accounts: {}",
         ctx.remaining_accounts.len()
     );
 
+    // Every joined player's profile follows the (player, token account) pairs
+    let (payout_accounts, mut profiles) =
+        split_player_profiles(ctx.remaining_accounts, &game_session.get_joined_players())?;
+
     // We need at least one player and their token account
     require!(
-        !ctx.remaining_accounts.is_empty(),
+        !payout_accounts.is_empty(),
         WagerError::InvalidRemainingAccounts
     );
 
     // Make sure remaining accounts are in pairs
     require!(
-        ctx.remaining_accounts.len() % 2 == 0,
+        payout_accounts.len() % 2 == 0,
         WagerError::InvalidRemainingAccounts
     );
 
+    // Every player is paid for their kills and spawns left, which the vault has
+    // to cover in full so nobody is left unpaid
+    let mut player_earnings = Vec::with_capacity(players.len());
     for player in players {
+        let kills_and_spawns = match_state.get_kills_and_spawns(player)?;
+        player_earnings.push((
+            player,
+            kills_and_spawns as u64 * game_session.session_bet / 10,
+        ));
+    }
+    let total_earnings = player_earnings
+        .iter()
+        .try_fold(0u64, |total, (_, earnings)| total.checked_add(*earnings))
+        .ok_or(WagerError::WinningsCalculationError)?;
+    require!(
+        total_earnings <= ctx.accounts.vault_token_account.amount,
+        WagerError::IncompleteDistribution
+    );
+
+    for (player, earnings) in player_earnings {
         // Skip players with no kills/spawns
-        let kills_and_spawns = game_session.get_kills_and_spawns(player)?;
-        if kills_and_spawns == 0 {
+        if earnings == 0 {
             continue;
         }
 
-        let earnings = kills_and_spawns as u64 * game_session.session_bet / 10;
         msg!("Earnings for player {}: {}", player, earnings);
 
         // Find the player's account and token account in remaining_accounts
-        let player_index = ctx
-            .remaining_accounts
+        let player_index = payout_accounts
             .iter()
             .step_by(2) // Skip token accounts to only look at player accounts
             .position(|acc| acc.key() == player)
-            .ok_or(WagerError::InvalidPlayer)?;
+            .ok_or(WagerError::PlayerAccountNotFound)?;
 
         // Get player and token account from remaining accounts
-        let player_account = &ctx.remaining_accounts[player_index * 2];
-        let player_token_account_info = &ctx.remaining_accounts[player_index * 2 + 1];
+        let player_account = &payout_accounts[player_index * 2];
+        let player_token_account_info = &payout_accounts[player_index * 2 + 1];
         let player_token_account = Account::<TokenAccount>::try_from(player_token_account_info)?;
 
         // Verify player token account constraints
@@ -80,29 +105,40 @@ pub fn distribute_pay_spawn_earnings<'info>(
                     },
                     &[&[
                         b"vault",
-                        session_id.as_bytes(),
+                        session_hash.as_ref(),
                         &[ctx.accounts.game_session.vault_bump],
                     ]],
                 ),
                 earnings,
             )?;
+            emit!(WinningsPaid {
+                session: ctx.accounts.game_session.key(),
+                player,
+                amount: earnings,
+            });
         }
-    }
 
-    // Mark session as completed
-    let game_session = &mut ctx.accounts.game_session;
-    game_session.status = GameStatus::Completed;
+        // Pay-to-spawn games have no winner, only earnings
+        if let Some(profile) = profiles.iter_mut().find(|p| p.player == player) {
+            profile.total_won = profile.total_won.saturating_add(earnings);
+        }
+    }
+    save_player_profiles(&profiles)?;
 
     Ok(())
 }
 
 pub fn distribute_all_winnings_handler<'info>(
     ctx: Context<'_, '_, 'info, 'info, DistributeWinnings<'info>>,
-    session_id: String,
+    session_hash: [u8; 32],
     winning_team: u8,
 ) -> Result<()> {
+    // Complete the session before anything is paid so it can't be settled twice
+    let session = ctx.accounts.game_session.key();
+    ctx.accounts.game_session.start_settlement(session)?;
+
     let game_session = &ctx.accounts.game_session;
-    msg!("Starting distribution for session: {}", session_id);
+    msg!("Starting distribution for session: {}", session);
 
     // Verify authority
     require!(
@@ -112,33 +148,65 @@ pub fn distribute_all_winnings_handler<'info>(
 
     // Validate winning team selection
     require!(
-        winning_team == 0 || winning_team == 1,
+        (winning_team as usize) < game_session.teams.len(),
         WagerError::InvalidWinningTeam
     );
 
-    let players_per_team = game_session.game_mode.players_per_team();
+    // An eliminated team cannot be declared the winner
+    require!(
+        !ctx.accounts
+            .match_state
+            .load()?
+            .is_team_eliminated(winning_team),
+        WagerError::InvalidWinningTeam
+    );
+
+    // A series only pays out to the team that won the majority of games
+    if let Some(series) = &game_session.series {
+        let series_winner = series.get_winner().ok_or(WagerError::SeriesNotDecided)?;
+        require!(
+            series_winner == winning_team,
+            WagerError::InvalidWinningTeam
+        );
+    }
 
     // Get the winning team
-    let winning_players = if winning_team == 0 {
-        &game_session.team_a.players[0..players_per_team]
-    } else {
-        &game_session.team_b.players[0..players_per_team]
-    };
+    let winning = game_session.get_team(winning_team)?;
+    let winning_players = &winning.players;
+    let players_per_team = winning_players.len();
 
     for player in winning_players {
         msg!("Winning player: {}", player);
     }
 
+    // Calculate total pot (sum of every team's bets)
+    let total_pot = game_session
+        .teams
+        .iter()
+        .try_fold(0u64, |pot, team| pot.checked_add(team.total_bet))
+        .ok_or(WagerError::TotalPotCalculationError)?;
+    msg!("Total pot calculated: {}", total_pot);
+
+    // Sessions without a stake, like tournament matches, have nothing to pay out
+    let payout_count = if total_pot == 0 { 0 } else { players_per_team };
+
+    // Every joined player's profile follows the (winner, token account) pairs
+    let (payout_accounts, mut profiles) =
+        split_player_profiles(ctx.remaining_accounts, &game_session.get_joined_players())?;
+
     // Get winner account and token account from remaining accounts
     require!(
-        ctx.remaining_accounts.len() >= 2 * players_per_team,
+        payout_accounts.len() >= 2 * payout_count,
         WagerError::InvalidRemainingAccounts
     );
 
-    for i in 0..players_per_team {
+    let mut paid_winners: Vec<(Pubkey, u64)> = Vec::with_capacity(payout_count);
+    let mut distributed: u64 = 0;
+
+    for i in 0..payout_count {
         // Get winner and winner token account
-        let winner = &ctx.remaining_accounts[i * 2];
-        let winner_token_account_info = &ctx.remaining_accounts[i * 2 + 1];
+        let winner = &payout_accounts[i * 2];
+        let winner_token_account_info = &payout_accounts[i * 2 + 1];
         let winner_token_account = Account::<TokenAccount>::try_from(winner_token_account_info)?;
 
         // Verify winner constraints
@@ -153,13 +221,11 @@ pub fn distribute_all_winnings_handler<'info>(
             WagerError::InvalidTokenMint
         );
 
-        // Verify winner is actually in the winning team
+        // Verify winner is actually in the winning team and isn't paid twice
         let winner_pubkey = winner.key();
         require!(
-            winning_players
-                .iter()
-                .take(players_per_team)
-                .any(|&p| p == winner_pubkey),
+            winning_players.contains(&winner_pubkey)
+                && !paid_winners.iter().any(|(paid, _)| *paid == winner_pubkey),
             WagerError::InvalidWinner
         );
 
@@ -167,12 +233,244 @@ pub fn distribute_all_winnings_handler<'info>(
         let vault_balance = ctx.accounts.vault_token_account.amount;
         msg!("Vault balance before transfer: {}", vault_balance);
 
-        // Calculate total pot (sum of both teams' bets)
-        let total_pot = game_session.session_bet * players_per_team as u64 * 2;
-        msg!("Total pot calculated: {}", total_pot);
-
-        let winning_amount = game_session.session_bet * 2;
+        // Winners share the pot in proportion to their stake, the last one
+        // taking the rounding dust so the vault is emptied
+        let winning_amount = if i == payout_count - 1 {
+            total_pot
+                .checked_sub(distributed)
+                .ok_or(WagerError::WinningsCalculationError)?
+        } else {
+            let share = (total_pot as u128)
+                .checked_mul(winning.player_bet as u128)
+                .ok_or(WagerError::WinningsCalculationError)?
+                .checked_div(winning.total_bet as u128)
+                .ok_or(WagerError::NoWinnersFound)?;
+            u64::try_from(share).map_err(|_| WagerError::WinningsCalculationError)?
+        };
+        distributed = distributed
+            .checked_add(winning_amount)
+            .ok_or(WagerError::WinningsCalculationError)?;
         msg!("Winning amount calculated: {}", winning_amount);
+        paid_winners.push((winner_pubkey, winning_amount));
+
+        // Transfer tokens from vault to winner
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: winner_token_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                &[&[
+                    b"vault",
+                    session_hash.as_ref(),
+                    &[ctx.accounts.game_session.vault_bump],
+                ]],
+            ),
+            winning_amount,
+        )?;
+        emit!(WinningsPaid {
+            session: ctx.accounts.game_session.key(),
+            player: winner_pubkey,
+            amount: winning_amount,
+        });
+    }
+
+    // Rate the winners against the average of everyone they beat, and each
+    // losing team against the winners
+    let ratings: Vec<(Pubkey, u16)> = profiles.iter().map(|p| (p.player, p.rating)).collect();
+    let team_rating = |team: &Team| {
+        average_rating(
+            ratings
+                .iter()
+                .filter(|(player, _)| team.players.contains(player))
+                .map(|(_, rating)| *rating),
+        )
+    };
+    let winner_rating = team_rating(winning).unwrap_or(DEFAULT_RATING);
+    let loser_rating = average_rating(
+        ratings
+            .iter()
+            .filter(|(player, _)| !winning_players.contains(player))
+            .map(|(_, rating)| *rating),
+    )
+    .unwrap_or(DEFAULT_RATING);
+    // Practice games are unranked
+    let k_factor = if game_session.is_practice() {
+        0
+    } else {
+        game_session.k_factor
+    };
+    let rating_changes: Vec<i32> = game_session
+        .teams
+        .iter()
+        .enumerate()
+        .map(|(index, team)| {
+            if index == winning_team as usize {
+                rating_change(winner_rating, loser_rating, true, k_factor)
+            } else {
+                let team_rating = team_rating(team).unwrap_or(DEFAULT_RATING);
+                rating_change(team_rating, winner_rating, false, k_factor)
+            }
+        })
+        .collect();
+
+    // Record the result on every player's lifetime profile
+    let is_tournament_match = game_session.tournament_match.is_some();
+    for profile in profiles.iter_mut() {
+        if let Some(team) = game_session
+            .teams
+            .iter()
+            .position(|team| team.players.contains(&profile.player))
+        {
+            profile.apply_rating_change(rating_changes[team]);
+        }
+
+        let amount_won = paid_winners
+            .iter()
+            .find(|(winner, _)| *winner == profile.player)
+            .map_or(0, |(_, amount)| *amount);
+        // Bracket players are seated by the tournament rather than join_user
+        if is_tournament_match {
+            profile.record_join(0);
+        }
+        let won = winning_players.contains(&profile.player);
+        profile.record_result(won, amount_won);
+    }
+    save_player_profiles(&profiles)?;
+
+    // Tournament matches move their winner on to the next round of the bracket
+    if let Some(link) = ctx.accounts.game_session.tournament_match {
+        let winner = ctx.accounts.game_session.get_team(winning_team)?.players[0];
+        let tournament = ctx
+            .accounts
+            .tournament
+            .as_mut()
+            .ok_or(WagerError::InvalidTournament)?;
+        require_keys_eq!(
+            tournament.key(),
+            link.tournament,
+            WagerError::InvalidTournament
+        );
+        tournament.record_match_winner(link.match_index, winner)?;
+    }
+
+    // League fixtures update the standings
+    if let Some(link) = ctx.accounts.game_session.league_match {
+        let league = ctx
+            .accounts
+            .league
+            .as_mut()
+            .ok_or(WagerError::InvalidLeague)?;
+        require_keys_eq!(league.key(), link.league, WagerError::InvalidLeague);
+        let winner = if winning_team == 0 {
+            link.home
+        } else {
+            link.away
+        };
+        league.record_result(link.home, link.away, Some(winner))?;
+    }
+
+    Ok(())
+}
+
+pub fn distribute_free_for_all_winnings<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DistributeWinnings<'info>>,
+    session_hash: [u8; 32],
+) -> Result<()> {
+    // Complete the session before anything is paid so it can't be settled twice
+    let session = ctx.accounts.game_session.key();
+    ctx.accounts.game_session.start_settlement(session)?;
+
+    let game_session = &ctx.accounts.game_session;
+    msg!("Starting distribution for session: {}", session);
+
+    let payout_percentages = match game_session.game_mode {
+        GameMode::FreeForAll {
+            payout_percentages, ..
+        } => payout_percentages,
+        _ => return Err(error!(WagerError::InvalidGameMode)),
+    };
+    let paid_places = payout_percentages.iter().filter(|p| **p > 0).count();
+    let match_state = ctx.accounts.match_state.load()?;
+
+    // Remaining accounts hold (player, token account) pairs ordered by finishing place,
+    // followed by every joined player's profile
+    let (payout_accounts, mut profiles) =
+        split_player_profiles(ctx.remaining_accounts, &game_session.get_joined_players())?;
+    require!(
+        payout_accounts.len() >= 2 * paid_places,
+        WagerError::InvalidRemainingAccounts
+    );
+
+    // Calculate total pot (sum of every player's bet)
+    let total_pot = game_session
+        .session_bet
+        .checked_mul(game_session.free_for_all_players.len() as u64)
+        .ok_or(WagerError::TotalPotCalculationError)?;
+    msg!("Total pot calculated: {}", total_pot);
+
+    // Work out each place's share, giving rounding dust to first place so the vault empties
+    let mut place_amounts = [0u64; 3];
+    for (place, percentage) in payout_percentages.iter().enumerate().skip(1) {
+        place_amounts[place] = total_pot
+            .checked_mul(*percentage as u64)
+            .ok_or(WagerError::WinningsCalculationError)?
+            / 100;
+    }
+    place_amounts[0] = total_pot
+        .checked_sub(place_amounts[1] + place_amounts[2])
+        .ok_or(WagerError::WinningsCalculationError)?;
+
+    let mut placed_players: Vec<(Pubkey, u64)> = Vec::with_capacity(paid_places);
+    for (place, percentage) in payout_percentages.iter().enumerate() {
+        if *percentage == 0 {
+            continue;
+        }
+
+        let pair_index = placed_players.len();
+        let winner = &payout_accounts[pair_index * 2];
+        let winner_token_account_info = &payout_accounts[pair_index * 2 + 1];
+        let winner_token_account = Account::<TokenAccount>::try_from(winner_token_account_info)?;
+
+        // Verify winner constraints
+        require!(
+            winner_token_account.owner == winner.key(),
+            WagerError::InvalidWinnerTokenAccount
+        );
+
+        // Verify token account mint
+        require!(
+            winner_token_account.mint == TOKEN_ID,
+            WagerError::InvalidTokenMint
+        );
+
+        // Verify winner played in the session and isn't placed twice
+        let winner_pubkey = winner.key();
+        game_session.get_free_for_all_index(winner_pubkey)?;
+        require!(
+            !placed_players
+                .iter()
+                .any(|(placed, _)| *placed == winner_pubkey),
+            WagerError::InvalidWinner
+        );
+
+        // The last player standing has to take first place
+        if place == 0 && match_state.is_status(GameStatus::Ended) {
+            require!(
+                match_state.get_free_for_all_survivor() == Some(winner_pubkey),
+                WagerError::InvalidWinner
+            );
+        }
+        let winning_amount = place_amounts[place];
+        placed_players.push((winner_pubkey, winning_amount));
+        msg!(
+            "Place {} winner {}: {}",
+            place + 1,
+            winner_pubkey,
+            winning_amount
+        );
 
         // Transfer tokens from vault to winner
         anchor_spl::token::transfer(
@@ -185,39 +483,56 @@ pub fn distribute_all_winnings_handler<'info>(
                 },
                 &[&[
                     b"vault",
-                    session_id.as_bytes(),
+                    session_hash.as_ref(),
                     &[ctx.accounts.game_session.vault_bump],
                 ]],
             ),
             winning_amount,
         )?;
+        emit!(WinningsPaid {
+            session: ctx.accounts.game_session.key(),
+            player: winner_pubkey,
+            amount: winning_amount,
+        });
     }
 
-    // Mark session as completed
-    let game_session = &mut ctx.accounts.game_session;
-    game_session.status = GameStatus::Completed;
+    // Only first place counts as a win on the players' lifetime profiles
+    for profile in profiles.iter_mut() {
+        let placing = placed_players
+            .iter()
+            .position(|(placed, _)| *placed == profile.player);
+        let amount_won = placing.map_or(0, |place| placed_players[place].1);
+        profile.record_result(placing == Some(0), amount_won);
+    }
+    save_player_profiles(&profiles)?;
 
     Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(session_id: String)]
+#[instruction(session_hash: [u8; 32])]
 pub struct DistributeWinnings<'info> {
     /// The game server authority that created the session
     pub game_server: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"game_session", session_id.as_bytes()],
+        seeds = [b"game_session", session_hash.as_ref()],
         bump = game_session.bump,
         constraint = game_session.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
     )]
     pub game_session: Account<'info, GameSession>,
 
+    #[account(
+        seeds = [b"match_state", session_hash.as_ref()],
+        bump = match_state.load()?.bump,
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
     /// CHECK: Vault PDA that holds the funds
     #[account(
         mut,
-        seeds = [b"vault", session_id.as_bytes()],
+        seeds = [b"vault", session_hash.as_ref()],
         bump = game_session.vault_bump,
     )]
     pub vault: AccountInfo<'info>,
@@ -229,6 +544,14 @@ pub struct DistributeWinnings<'info> {
     )]
     pub vault_token_account: Account<'info, TokenAccount>,
 
+    /// Tournament the session's bracket match belongs to, if any
+    #[account(mut)]
+    pub tournament: Option<Account<'info, Tournament>>,
+
+    /// League the session's fixture belongs to, if any
+    #[account(mut)]
+    pub league: Option<Account<'info, League>>,
+
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
@@ -0,0 +1,85 @@
+use crate::{errors::WagerError, events::PlayerJoined, state::*};
+use anchor_lang::prelude::*;
+
+use super::JoinUser;
+
+pub fn join_free_for_all_handler(
+    ctx: Context<JoinUser>,
+    _session_hash: [u8; 32],
+    credentials: JoinCredentials,
+) -> Result<()> {
+    let game_session = &mut ctx.accounts.game_session;
+
+    // Validate game status
+    require!(
+        game_session.status == GameStatus::WaitingForPlayers,
+        WagerError::InvalidGameState
+    );
+
+    // Team games are joined through join_user
+    require!(game_session.is_free_for_all(), WagerError::InvalidGameMode);
+
+    // A wallet can only hold one seat and can't face wallets it is linked to
+    game_session.check_can_join(ctx.accounts.user.key(), None)?;
+
+    // Invite-only sessions only admit allowlisted wallets
+    game_session.check_allowlisted(ctx.accounts.user.key(), None, &credentials)?;
+
+    // Password protected lobbies need the join co-signed by the invite key
+    game_session.check_invite(ctx.accounts.invite.as_ref().map(|invite| invite.key()))?;
+
+    // Gated sessions need a token account holding the gating token
+    game_session.check_token_gate(
+        ctx.accounts.user.key(),
+        ctx.accounts.gate_token_account.as_deref(),
+    )?;
+
+    let session_bet = game_session.session_bet;
+
+    // Rated sessions only admit players inside their rating band
+    let player_profile = &mut ctx.accounts.player_profile;
+    player_profile.init_if_new(ctx.accounts.user.key(), ctx.bumps.player_profile);
+    game_session.check_rating(player_profile.rating)?;
+
+    // The player has to be able to cover the stake
+    require!(
+        ctx.accounts.user_token_account.amount >= session_bet,
+        WagerError::InsufficientFunds
+    );
+
+    // Transfer SPL tokens from user to vault using user's signature
+    anchor_spl::token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        session_bet,
+    )?;
+
+    // Take the next free slot, starting the game once the lobby is full
+    let player = ctx.accounts.user.key();
+    let seat = game_session.add_free_for_all_player(player)?;
+
+    let mut match_state = ctx.accounts.match_state.load_mut()?;
+    match_state.seat_player(seat, player);
+    emit!(PlayerJoined {
+        session: game_session.key(),
+        player,
+        team: None,
+        amount: session_bet,
+    });
+    if game_session.is_free_for_all_full() {
+        let session = game_session.key();
+        game_session.set_status(session, GameStatus::InProgress);
+        match_state.set_status(GameStatus::InProgress);
+    }
+
+    // Track the game on the player's lifetime profile
+    ctx.accounts.player_profile.record_join(session_bet);
+
+    Ok(())
+}
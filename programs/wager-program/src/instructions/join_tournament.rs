@@ -0,0 +1,109 @@
+use crate::{errors::WagerError, events::TournamentJoined, state::*, TOKEN_ID};
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Token, TokenAccount};
+
+pub fn join_tournament_handler(ctx: Context<JoinTournament>, _tournament_id: String) -> Result<()> {
+    let tournament = &mut ctx.accounts.tournament;
+
+    // Validate tournament status
+    require!(
+        tournament.status == TournamentStatus::Registration,
+        WagerError::InvalidGameState
+    );
+
+    let player = ctx.accounts.user.key();
+    require!(
+        !tournament.players.contains(&player),
+        WagerError::InvalidPlayer
+    );
+    require!(
+        tournament.players.len() < tournament.max_players as usize,
+        WagerError::SessionIsFull
+    );
+
+    // Transfer the entry fee from user to the tournament vault
+    anchor_spl::token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.user_token_account.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        tournament.entry_fee,
+    )?;
+
+    tournament.players.push(player);
+    emit!(TournamentJoined {
+        tournament: tournament.key(),
+        player,
+        amount: tournament.entry_fee,
+    });
+
+    // Bracket matches are settled against the player's profile, so make sure it exists
+    let player_profile = &mut ctx.accounts.player_profile;
+    player_profile.init_if_new(player, ctx.bumps.player_profile);
+    player_profile.total_wagered = player_profile
+        .total_wagered
+        .saturating_add(tournament.entry_fee);
+
+    // Seed the first round once the bracket is full
+    if tournament.players.len() == tournament.max_players as usize {
+        tournament.seed_bracket();
+        tournament.status = TournamentStatus::InProgress;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(tournament_id: String)]
+pub struct JoinTournament<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"tournament", tournament_id.as_bytes()],
+        bump = tournament.bump,
+    )]
+    pub tournament: Account<'info, Tournament>,
+
+    /// Lifetime stats of the joining player, created on their first game
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = PlayerProfile::LEN,
+        seeds = [b"player_profile", user.key().as_ref()],
+        bump,
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key(),
+        constraint = user_token_account.mint == TOKEN_ID
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Vault PDA that holds the entry fees
+    #[account(
+        mut,
+        seeds = [b"tournament_vault", tournament_id.as_bytes()],
+        bump = tournament.vault_bump,
+    )]
+    pub vault: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = TOKEN_ID,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
@@ -1,9 +1,14 @@
-use crate::{errors::WagerError, state::*, TOKEN_ID};
+use crate::{errors::WagerError, events::PlayerJoined, state::*, TOKEN_ID};
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{Token, TokenAccount};
 
-pub fn join_user_handler(ctx: Context<JoinUser>, _session_id: String, team: u8) -> Result<()> {
+pub fn join_user_handler(
+    ctx: Context<JoinUser>,
+    _session_hash: [u8; 32],
+    team: u8,
+    credentials: JoinCredentials,
+) -> Result<()> {
     let game_session = &mut ctx.accounts.game_session;
 
     // Validate game status
@@ -12,13 +17,46 @@ pub fn join_user_handler(ctx: Context<JoinUser>, _session_id: String, team: u8)
         WagerError::InvalidGameState
     );
 
-    // Validate team number (0 for team A, 1 for team B)
-    require!(team == 0 || team == 1, WagerError::InvalidTeamSelection);
+    // Free-for-all players join through join_free_for_all
+    require!(!game_session.is_free_for_all(), WagerError::InvalidGameMode);
+
+    // Validate team number against the session's teams
+    require!(
+        (team as usize) < game_session.teams.len(),
+        WagerError::InvalidTeamSelection
+    );
+
+    // A wallet can only hold one seat and can't face wallets it is linked to
+    game_session.check_can_join(ctx.accounts.user.key(), Some(team))?;
+
+    // Invite-only sessions and teams only admit allowlisted wallets
+    game_session.check_allowlisted(ctx.accounts.user.key(), Some(team), &credentials)?;
+
+    // Password protected lobbies need the join co-signed by the invite key
+    game_session.check_invite(ctx.accounts.invite.as_ref().map(|invite| invite.key()))?;
+
+    // Gated sessions need a token account holding the gating token
+    game_session.check_token_gate(
+        ctx.accounts.user.key(),
+        ctx.accounts.gate_token_account.as_deref(),
+    )?;
 
     // Check if team is full already
     let empty_index = game_session.get_player_empty_slot(team)?;
 
-    let session_bet = game_session.session_bet;
+    // Each team can stake a different amount per player
+    let player_bet = game_session.get_team(team)?.player_bet;
+
+    // Rated sessions only admit players inside their rating band
+    let player_profile = &mut ctx.accounts.player_profile;
+    player_profile.init_if_new(ctx.accounts.user.key(), ctx.bumps.player_profile);
+    game_session.check_rating(player_profile.rating)?;
+
+    // The player has to be able to cover the stake
+    require!(
+        ctx.accounts.user_token_account.amount >= player_bet,
+        WagerError::InsufficientFunds
+    );
 
     // Transfer SPL tokens from user to vault using user's signature
     anchor_spl::token::transfer(
@@ -30,32 +68,42 @@ pub fn join_user_handler(ctx: Context<JoinUser>, _session_id: String, team: u8)
                 authority: ctx.accounts.user.to_account_info(),
             },
         ),
-        session_bet,
+        player_bet,
     )?;
 
     let player = ctx.accounts.user.key();
 
     // Get reference to the selected team
-    let selected_team = if team == 0 {
-        &mut game_session.team_a
-    } else {
-        &mut game_session.team_b
-    };
+    let selected_team = game_session.get_team_mut(team)?;
 
     // Add player to the first available slot
     selected_team.players[empty_index] = player.key();
-    selected_team.player_spawns[empty_index] = 10;
-    selected_team.player_kills[empty_index] = 0;
+    selected_team.add_bet(player_bet)?;
+
+    let mut match_state = ctx.accounts.match_state.load_mut()?;
+    match_state.seat_player(MatchState::team_seat(team, empty_index), player);
+
+    // Track the game on the player's lifetime profile
+    ctx.accounts.player_profile.record_join(player_bet);
+
+    emit!(PlayerJoined {
+        session: game_session.key(),
+        player,
+        team: Some(team),
+        amount: player_bet,
+    });
 
     if game_session.check_all_filled()? {
-        game_session.status = GameStatus::InProgress;
+        let session = game_session.key();
+        game_session.set_status(session, GameStatus::InProgress);
+        match_state.set_status(GameStatus::InProgress);
     }
 
     Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(session_id: String)]
+#[instruction(session_hash: [u8; 32])]
 pub struct JoinUser<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
@@ -65,11 +113,28 @@ pub struct JoinUser<'info> {
 
     #[account(
         mut,
-        seeds = [b"game_session", session_id.as_bytes()],
+        seeds = [b"game_session", session_hash.as_ref()],
         bump = game_session.bump,
     )]
     pub game_session: Account<'info, GameSession>,
 
+    #[account(
+        mut,
+        seeds = [b"match_state", session_hash.as_ref()],
+        bump = match_state.load()?.bump,
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
+    /// Lifetime stats of the joining player, created on their first game
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = PlayerProfile::LEN,
+        seeds = [b"player_profile", user.key().as_ref()],
+        bump,
+    )]
+    pub player_profile: Account<'info, PlayerProfile>,
+
     #[account(
         mut,
         constraint = user_token_account.owner == user.key(),
@@ -77,10 +142,16 @@ pub struct JoinUser<'info> {
     )]
     pub user_token_account: Account<'info, TokenAccount>,
 
+    /// Joiner's account of the gating token, for token-gated sessions
+    pub gate_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Invite key co-signing the join, for password protected lobbies
+    pub invite: Option<Signer<'info>>,
+
     /// CHECK: Vault PDA that holds the funds
     #[account(
         mut,
-        seeds = [b"vault", session_id.as_bytes()],
+        seeds = [b"vault", session_hash.as_ref()],
         bump = game_session.vault_bump,
     )]
     pub vault: AccountInfo<'info>,
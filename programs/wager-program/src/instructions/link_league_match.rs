@@ -0,0 +1,87 @@
+use crate::{errors::WagerError, events::LeagueMatchLinked, state::*};
+use anchor_lang::prelude::*;
+
+pub fn link_league_match_handler(
+    ctx: Context<LinkLeagueMatch>,
+    _league_id: String,
+    _session_hash: [u8; 32],
+    home: u8,
+    away: u8,
+) -> Result<()> {
+    let league = &mut ctx.accounts.league;
+    let game_session = &mut ctx.accounts.game_session;
+
+    // Registration closes once the first fixture is scheduled
+    require!(
+        league.status != LeagueStatus::Completed,
+        WagerError::InvalidGameState
+    );
+
+    // Fixtures are two-team winner takes all games that haven't started yet
+    require!(
+        game_session.status == GameStatus::WaitingForPlayers
+            && game_session.game_mode == GameMode::WinnerTakesAll
+            && game_session.teams.len() == 2
+            && game_session.series.is_none(),
+        WagerError::InvalidGameMode
+    );
+    require!(
+        game_session.tournament_match.is_none() && game_session.league_match.is_none(),
+        WagerError::InvalidLeagueMatch
+    );
+
+    league.link_fixture(home, away)?;
+    league.status = LeagueStatus::InProgress;
+
+    // Each side of the fixture is fielded from its league team's roster
+    let rosters = [home, away].map(|team| league.teams[team as usize].roster.clone());
+    require!(
+        game_session
+            .teams
+            .iter()
+            .zip(&rosters)
+            .all(|(team, roster)| team.players.len() <= roster.len()),
+        WagerError::InvalidLeagueMatch
+    );
+    game_session.team_allowlists = rosters
+        .into_iter()
+        .map(|roster| Some(Allowlist::Wallets(roster)))
+        .collect();
+
+    game_session.league_match = Some(LeagueMatchLink {
+        league: league.key(),
+        home,
+        away,
+    });
+
+    emit!(LeagueMatchLinked {
+        league: league.key(),
+        session: game_session.key(),
+        home,
+        away,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(league_id: String, session_hash: [u8; 32])]
+pub struct LinkLeagueMatch<'info> {
+    pub game_server: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"league", league_id.as_bytes()],
+        bump = league.bump,
+        constraint = league.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
+    )]
+    pub league: Account<'info, League>,
+
+    #[account(
+        mut,
+        seeds = [b"game_session", session_hash.as_ref()],
+        bump = game_session.bump,
+        constraint = game_session.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
+    )]
+    pub game_session: Account<'info, GameSession>,
+}
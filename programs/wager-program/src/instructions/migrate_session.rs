@@ -0,0 +1,194 @@
+use crate::{errors::WagerError, events::SessionMigrated, state::*, utils::*, TOKEN_ID};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer};
+
+pub fn migrate_session_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, MigrateSession<'info>>,
+    session_id: String,
+    session_hash: [u8; 32],
+) -> Result<()> {
+    // The migrated session is addressed by the hash of its old ID
+    require!(
+        hash(session_id.as_bytes()).to_bytes() == session_hash,
+        WagerError::SessionHashMismatch
+    );
+
+    // Legacy sessions have the original fixed size, anything else has been migrated
+    let legacy = {
+        let data = ctx.accounts.legacy_session.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == GameSession::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        require!(
+            data.len() == GameSessionV0::LEN,
+            WagerError::SessionAlreadyMigrated
+        );
+        GameSessionV0::deserialize(&mut &data[8..])
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))?
+    };
+
+    // Only the session's game server can migrate it
+    require_keys_eq!(
+        legacy.authority,
+        ctx.accounts.game_server.key(),
+        WagerError::UnauthorizedDistribution
+    );
+
+    let game_session = &mut ctx.accounts.game_session;
+    game_session.set_inner(legacy.upgrade(session_hash, ctx.bumps.game_session, ctx.bumps.vault));
+
+    // Combat stats moved from the teams to the match state
+    let mut match_state = ctx.accounts.match_state.load_init()?;
+    match_state.init(
+        game_session.key(),
+        game_session.authority,
+        &game_session.game_mode,
+        game_session.teams.len() as u8,
+        ctx.bumps.match_state,
+    );
+    for (team, legacy_team) in legacy.teams().iter().enumerate() {
+        for slot in 0..game_session.team_size as usize {
+            let seat = MatchState::team_seat(team as u8, slot);
+            match_state.players[seat] = legacy_team.players[slot];
+            match_state.spawns[seat] = legacy_team.player_spawns[slot];
+            match_state.kills[seat] = legacy_team.player_kills[slot];
+            match_state.eliminated[seat] = (legacy_team.players[slot] != Pubkey::default()
+                && legacy_team.player_spawns[slot] == 0)
+                as u8;
+        }
+    }
+    match_state.set_status(legacy.status.clone());
+
+    // The original layout kept no profiles, so every seated player gets theirs here,
+    // passed as remaining accounts in joining order, with the legacy game on it
+    let players = legacy.get_joined_players();
+    require!(
+        ctx.remaining_accounts.len() == players.len(),
+        WagerError::InvalidRemainingAccounts
+    );
+    for (player, profile_info) in players.iter().zip(ctx.remaining_accounts) {
+        init_player_profile(
+            profile_info,
+            *player,
+            &ctx.accounts.game_server.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        let mut profile = Account::<PlayerProfile>::try_from(profile_info)?;
+        profile.record_join(legacy.session_bet);
+        profile.exit(&crate::ID)?;
+    }
+
+    // Move the escrow to the new vault, then close the legacy accounts
+    let vault_seeds: &[&[u8]] = &[b"vault", session_id.as_bytes(), &[legacy.vault_bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.legacy_vault_token_account.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.legacy_vault.to_account_info(),
+            },
+            &[vault_seeds],
+        ),
+        ctx.accounts.legacy_vault_token_account.amount,
+    )?;
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.legacy_vault_token_account.to_account_info(),
+            destination: ctx.accounts.game_server.to_account_info(),
+            authority: ctx.accounts.legacy_vault.to_account_info(),
+        },
+        &[vault_seeds],
+    ))?;
+
+    let game_server = ctx.accounts.game_server.to_account_info();
+    close_program_account(&ctx.accounts.legacy_session, &game_server)?;
+    close_program_account(&ctx.accounts.legacy_vault, &game_server)?;
+
+    emit!(SessionMigrated {
+        session: game_session.key(),
+        version: GAME_SESSION_VERSION,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(session_id: String, session_hash: [u8; 32])]
+pub struct MigrateSession<'info> {
+    #[account(mut)]
+    pub game_server: Signer<'info>,
+
+    /// CHECK: Deserialized by hand since it is on the original layout
+    #[account(
+        mut,
+        seeds = [b"game_session", session_id.as_bytes()],
+        bump,
+        owner = crate::ID,
+    )]
+    pub legacy_session: UncheckedAccount<'info>,
+
+    /// CHECK: Original vault PDA, only signs for its token account
+    #[account(
+        mut,
+        seeds = [b"vault", session_id.as_bytes()],
+        bump,
+        owner = crate::ID,
+    )]
+    pub legacy_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = legacy_vault,
+    )]
+    pub legacy_vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = game_server,
+        space = GameSession::LEN,
+        seeds = [b"game_session", session_hash.as_ref()],
+        bump
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(
+        init,
+        payer = game_server,
+        space = MatchState::LEN,
+        seeds = [b"match_state", session_hash.as_ref()],
+        bump
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
+    /// CHECK: This is safe as it's just used to store SOL
+    #[account(
+        init,
+        payer = game_server,
+        space = 0,
+        seeds = [b"vault", session_hash.as_ref()],
+        bump
+    )]
+    pub vault: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = game_server,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(address = TOKEN_ID @ WagerError::InvalidMint)]
+    pub mint: Account<'info, anchor_spl::token::Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
@@ -1,17 +1,68 @@
+pub mod close_league_season;
+pub use close_league_season::*;
+
+pub mod close_game_session;
+pub use close_game_session::*;
+
+pub mod commit_replay;
+pub use commit_replay::*;
+
 pub mod create_game_session;
 pub use create_game_session::*;
 
+pub mod create_league;
+pub use create_league::*;
+
+pub mod create_match_log;
+pub use create_match_log::*;
+
+pub mod create_tournament;
+pub use create_tournament::*;
+
+pub mod create_tournament_match;
+pub use create_tournament_match::*;
+
+pub mod distribute_tournament_prizes;
+pub use distribute_tournament_prizes::*;
+
 pub mod distribute_winnings;
 pub use distribute_winnings::*;
 
+pub mod join_free_for_all;
+pub use join_free_for_all::*;
+
+pub mod join_tournament;
+pub use join_tournament::*;
+
 pub mod join_user;
 pub use join_user::*;
 
+pub mod link_league_match;
+pub use link_league_match::*;
+
+pub mod migrate_session;
+pub use migrate_session::*;
+
 pub mod pay_to_spawn;
 pub use pay_to_spawn::*;
 
 pub mod record_kill;
 pub use record_kill::*;
 
+pub mod record_series_game;
+pub use record_series_game::*;
+
 pub mod refund_wager;
 pub use refund_wager::*;
+
+pub mod register_league_team;
+pub use register_league_team::*;
+
+pub mod settle_league_draw;
+pub use settle_league_draw::*;
+
+pub mod upgrade_session;
+pub use upgrade_session::*;
+
+pub mod verify_replay_event;
+pub use verify_replay_event::*;
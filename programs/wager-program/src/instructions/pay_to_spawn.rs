@@ -1,9 +1,13 @@
-use crate::{errors::WagerError, state::*, TOKEN_ID};
+use crate::{errors::WagerError, events::SpawnPurchased, state::*, TOKEN_ID};
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{Token, TokenAccount};
 
-pub fn pay_to_spawn_handler(ctx: Context<PayToSpawn>, _session_id: String, team: u8) -> Result<()> {
+pub fn pay_to_spawn_handler(
+    ctx: Context<PayToSpawn>,
+    _session_hash: [u8; 32],
+    team: u8,
+) -> Result<()> {
     let game_session = &mut ctx.accounts.game_session;
 
     // Check if game status is in progress and if it is a pay to spawn game
@@ -12,14 +16,25 @@ pub fn pay_to_spawn_handler(ctx: Context<PayToSpawn>, _session_id: String, team:
         WagerError::InvalidGameState
     );
 
-    // Validate team number (0 for team A, 1 for team B)
-    require!(team == 0 || team == 1, WagerError::InvalidTeamSelection);
+    // Validate team number against the session's teams
+    require!(
+        (team as usize) < game_session.teams.len(),
+        WagerError::InvalidTeamSelection
+    );
 
-    // Check if team is full already
-    let player_index = game_session.get_player_index(team, ctx.accounts.user.key())?;
+    // Only players seated in the team can buy spawns for it
+    game_session
+        .get_player_index(team, ctx.accounts.user.key())
+        .map_err(|_| error!(WagerError::UnauthorizedPayToSpawn))?;
 
     let session_bet = game_session.session_bet;
 
+    // The player has to be able to cover the spawns
+    require!(
+        ctx.accounts.user_token_account.amount >= session_bet,
+        WagerError::InsufficientFunds
+    );
+
     // Transfer SPL tokens from user to vault using user's signature
     anchor_spl::token::transfer(
         CpiContext::new(
@@ -33,13 +48,38 @@ pub fn pay_to_spawn_handler(ctx: Context<PayToSpawn>, _session_id: String, team:
         session_bet,
     )?;
 
-    game_session.add_spawns(team, player_index)?;
+    game_session.add_spawn_bet(team)?;
+    let mut match_state = ctx.accounts.match_state.load_mut()?;
+    match_state.add_spawns(team, ctx.accounts.user.key())?;
+
+    // Sessions with a match log keep a timeline of every spawn purchase
+    if match_state.match_log == 1 {
+        let match_log = ctx
+            .accounts
+            .match_log
+            .as_ref()
+            .ok_or(WagerError::MissingMatchLog)?;
+        match_log.load_mut()?.record(
+            MatchEventKind::Spawn,
+            ctx.accounts.user.key(),
+            team,
+            Pubkey::default(),
+            team,
+        )?;
+    }
+
+    emit!(SpawnPurchased {
+        session: game_session.key(),
+        player: ctx.accounts.user.key(),
+        team,
+        amount: session_bet,
+    });
 
     Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(_session_id: String)]
+#[instruction(_session_hash: [u8; 32])]
 pub struct PayToSpawn<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
@@ -49,11 +89,25 @@ pub struct PayToSpawn<'info> {
 
     #[account(
         mut,
-        seeds = [b"game_session", _session_id.as_bytes()],
+        seeds = [b"game_session", _session_hash.as_ref()],
         bump = game_session.bump,
     )]
     pub game_session: Account<'info, GameSession>,
 
+    #[account(
+        mut,
+        seeds = [b"match_state", _session_hash.as_ref()],
+        bump = match_state.load()?.bump,
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
+    #[account(
+        mut,
+        seeds = [b"match_log", _session_hash.as_ref()],
+        bump = match_log.load()?.bump,
+    )]
+    pub match_log: Option<AccountLoader<'info, MatchLog>>,
+
     #[account(
         mut,
         constraint = user_token_account.owner == user.key(),
@@ -64,7 +118,7 @@ pub struct PayToSpawn<'info> {
     /// CHECK: Vault PDA that holds the funds
     #[account(
         mut,
-        seeds = [b"vault", _session_id.as_bytes()],
+        seeds = [b"vault", _session_hash.as_ref()],
         bump = game_session.vault_bump,
     )]
     pub vault: AccountInfo<'info>,
@@ -1,29 +1,93 @@
-use crate::{errors::WagerError, state::*};
+use crate::{errors::WagerError, events::KillRecorded, state::*};
 use anchor_lang::prelude::*;
 
 pub fn record_kill_handler(
     ctx: Context<RecordKill>,
-    _session_id: String,
+    _session_hash: [u8; 32],
     killer_team: u8,
     killer: Pubkey,
     victim_team: u8,
     victim: Pubkey,
 ) -> Result<()> {
+    let mut match_state = ctx.accounts.match_state.load_mut()?;
+    match_state.add_kill(killer_team, killer, victim_team, victim)?;
+
+    // Sessions with a match log keep a timeline of every kill
+    if match_state.match_log == 1 {
+        let match_log = ctx
+            .accounts
+            .match_log
+            .as_ref()
+            .ok_or(WagerError::MissingMatchLog)?;
+        match_log.load_mut()?.record(
+            MatchEventKind::Kill,
+            killer,
+            killer_team,
+            victim,
+            victim_team,
+        )?;
+    }
+    emit!(KillRecorded {
+        session: match_state.session,
+        killer,
+        killer_team,
+        victim,
+        victim_team,
+    });
+
+    // The kill that eliminates the last opponent ends the match, and with it the session
     let game_session = &mut ctx.accounts.game_session;
-    game_session.add_kill(killer_team, killer, victim_team, victim)?;
+    if match_state.is_status(GameStatus::Ended) && game_session.status != GameStatus::Ended {
+        game_session.set_status(match_state.session, GameStatus::Ended);
+    }
+
+    // Carry the kill over to both players' lifetime profiles
+    let killer_profile = &mut ctx.accounts.killer_profile;
+    killer_profile.kills = killer_profile.kills.saturating_add(1);
+    let victim_profile = &mut ctx.accounts.victim_profile;
+    victim_profile.deaths = victim_profile.deaths.saturating_add(1);
     Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(_session_id: String)]
+#[instruction(_session_hash: [u8; 32], _killer_team: u8, killer: Pubkey, _victim_team: u8, victim: Pubkey)]
 pub struct RecordKill<'info> {
+    /// Kills are tallied in the zero-copy match state
+    #[account(
+        mut,
+        seeds = [b"match_state", _session_hash.as_ref()],
+        bump = match_state.load()?.bump,
+        constraint = match_state.load()?.authority == game_server.key() @ WagerError::UnauthorizedKill,
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
     #[account(
         mut,
-        seeds = [b"game_session", _session_id.as_bytes()],
+        seeds = [b"game_session", _session_hash.as_ref()],
         bump = game_session.bump,
-        constraint = game_session.authority == game_server.key() @ WagerError::UnauthorizedKill,
     )]
     pub game_session: Account<'info, GameSession>,
 
+    #[account(
+        mut,
+        seeds = [b"player_profile", killer.as_ref()],
+        bump = killer_profile.bump,
+    )]
+    pub killer_profile: Account<'info, PlayerProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"player_profile", victim.as_ref()],
+        bump = victim_profile.bump,
+    )]
+    pub victim_profile: Account<'info, PlayerProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"match_log", _session_hash.as_ref()],
+        bump = match_log.load()?.bump,
+    )]
+    pub match_log: Option<AccountLoader<'info, MatchLog>>,
+
     pub game_server: Signer<'info>,
 }
@@ -0,0 +1,44 @@
+use crate::{errors::WagerError, events::SeriesGameRecorded, state::*};
+use anchor_lang::prelude::*;
+
+pub fn record_series_game_handler(
+    ctx: Context<RecordSeriesGame>,
+    _session_hash: [u8; 32],
+    winning_team: u8,
+) -> Result<()> {
+    let game_session = &mut ctx.accounts.game_session;
+    let mut match_state = ctx.accounts.match_state.load_mut()?;
+    game_session.add_series_game(winning_team, &mut match_state)?;
+
+    let games_played = game_session
+        .series
+        .as_ref()
+        .map_or(0, |series| series.game_winners.len() as u8);
+    emit!(SeriesGameRecorded {
+        session: game_session.key(),
+        winning_team,
+        games_played,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(_session_hash: [u8; 32])]
+pub struct RecordSeriesGame<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_session", _session_hash.as_ref()],
+        bump = game_session.bump,
+        constraint = game_session.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
+    )]
+    pub game_session: Account<'info, GameSession>,
+
+    #[account(
+        mut,
+        seeds = [b"match_state", _session_hash.as_ref()],
+        bump = match_state.load()?.bump,
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
+    pub game_server: Signer<'info>,
+}
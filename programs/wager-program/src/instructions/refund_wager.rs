@@ -1,54 +1,108 @@
-use crate::{errors::WagerError, state::*, TOKEN_ID};
+use crate::{errors::WagerError, events::RefundIssued, state::*, utils::*, TOKEN_ID};
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{Token, TokenAccount};
 
 pub fn refund_wager_handler<'info>(
     ctx: Context<'_, '_, 'info, 'info, RefundWager<'info>>,
-    session_id: String,
+    session_hash: [u8; 32],
 ) -> Result<()> {
-    let game_session = &ctx.accounts.game_session;
-    msg!("Starting Refund for session: {}", session_id);
+    // Complete the session before anything is refunded so it can't be refunded twice
+    let game_session = &mut ctx.accounts.game_session;
+    require!(
+        game_session.status != GameStatus::Completed,
+        WagerError::InvalidGameState
+    );
+
+    // Bracket matches hold no stake and have to produce a winner for the bracket
+    // to advance, so they are settled through distribute_winnings
+    require!(
+        game_session.tournament_match.is_none(),
+        WagerError::InvalidBracketMatch
+    );
+    let session = game_session.key();
+    game_session.set_status(session, GameStatus::Completed);
+
+    // Every joined player's profile follows the (player, token account) pairs
+    let (payout_accounts, mut profiles) =
+        split_player_profiles(ctx.remaining_accounts, &game_session.get_joined_players())?;
+    return_stakes(ctx.accounts, payout_accounts, session_hash)?;
+
+    // A refunded game doesn't count towards the players' lifetime profiles
+    for profile in profiles.iter_mut() {
+        let stake = ctx.accounts.game_session.get_player_bet(profile.player)?;
+        profile.record_refund(stake);
+    }
+    save_player_profiles(&profiles)?;
+
+    // A refunded league fixture has no result and can be scheduled again
+    if let Some(link) = ctx.accounts.game_session.league_match {
+        let league = ctx
+            .accounts
+            .league
+            .as_mut()
+            .ok_or(WagerError::InvalidLeague)?;
+        require_keys_eq!(league.key(), link.league, WagerError::InvalidLeague);
+        league.unlink_fixture(link.home, link.away);
+    }
+
+    Ok(())
+}
+
+/// Pays every player of a completed session back their stake and spawn purchases,
+/// `remaining_accounts` holding a (player, token account) pair for each of them
+pub(crate) fn return_stakes<'info>(
+    accounts: &RefundWager<'info>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    session_hash: [u8; 32],
+) -> Result<()> {
+    let game_session = &accounts.game_session;
+    msg!("Starting Refund for session: {}", game_session.key());
 
     let players = game_session.get_all_players();
     msg!("Number of players: {}", players.len());
-    msg!(
-        "Number of remaining accounts: {}",
-        ctx.remaining_accounts.len()
-    );
+    msg!("Number of remaining accounts: {}", remaining_accounts.len());
 
     // We need at least one player and their token account
     require!(
-        !ctx.remaining_accounts.is_empty(),
+        !remaining_accounts.is_empty(),
         WagerError::InvalidRemainingAccounts
     );
 
     // Make sure remaining accounts are in pairs
     require!(
-        ctx.remaining_accounts.len() % 2 == 0,
+        remaining_accounts.len() % 2 == 0,
         WagerError::InvalidRemainingAccounts
     );
 
+    let match_state = accounts.match_state.load()?;
     for player in players {
         // Skip default player
         if player == Pubkey::default() {
             continue;
         }
 
-        let refund = game_session.session_bet;
+        // Players get back their stake along with every set of spawns they bought
+        let spawns_paid = game_session
+            .session_bet
+            .checked_mul(match_state.get_spawn_purchases(player) as u64)
+            .ok_or(WagerError::ArithmeticError)?;
+        let refund = game_session
+            .get_player_bet(player)?
+            .checked_add(spawns_paid)
+            .ok_or(WagerError::ArithmeticError)?;
         msg!("Earnings for player {}: {}", player, refund);
 
         // Find the player's account and token account in remaining_accounts
-        let player_index = ctx
-            .remaining_accounts
+        let player_index = remaining_accounts
             .iter()
             .step_by(2) // Skip token accounts to only look at player accounts
             .position(|acc| acc.key() == player)
             .ok_or(WagerError::InvalidPlayer)?;
 
         // Get player and token account from remaining accounts
-        let player_account = &ctx.remaining_accounts[player_index * 2];
-        let player_token_account_info = &ctx.remaining_accounts[player_index * 2 + 1];
+        let player_account = &remaining_accounts[player_index * 2];
+        let player_token_account_info = &remaining_accounts[player_index * 2 + 1];
         let player_token_account = Account::<TokenAccount>::try_from(player_token_account_info)?;
 
         // Verify player token account constraints
@@ -64,52 +118,60 @@ pub fn refund_wager_handler<'info>(
         );
 
         // Get vault balance before transfer
-        let vault_balance = ctx.accounts.vault_token_account.amount;
+        let vault_balance = accounts.vault_token_account.amount;
         msg!("Vault balance before transfer: {}", vault_balance);
 
         // Transfer tokens from vault to player
         anchor_spl::token::transfer(
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
+                accounts.token_program.to_account_info(),
                 anchor_spl::token::Transfer {
-                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    from: accounts.vault_token_account.to_account_info(),
                     to: player_token_account.to_account_info(),
-                    authority: ctx.accounts.vault.to_account_info(),
+                    authority: accounts.vault.to_account_info(),
                 },
                 &[&[
                     b"vault",
-                    session_id.as_bytes(),
-                    &[ctx.accounts.game_session.vault_bump],
+                    session_hash.as_ref(),
+                    &[accounts.game_session.vault_bump],
                 ]],
             ),
             refund,
         )?;
+        emit!(RefundIssued {
+            session: accounts.game_session.key(),
+            player,
+            amount: refund,
+        });
     }
 
-    // Mark session as completed
-    let game_session = &mut ctx.accounts.game_session;
-    game_session.status = GameStatus::Completed;
-
     Ok(())
 }
+
 #[derive(Accounts)]
-#[instruction(session_id: String)]
+#[instruction(session_hash: [u8; 32])]
 pub struct RefundWager<'info> {
     /// The game server authority that created the session
     pub game_server: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [b"game_session", session_id.as_bytes()],
+        seeds = [b"game_session", session_hash.as_ref()],
         bump = game_session.bump,
         constraint = game_session.authority == game_server.key() @ WagerError::UnauthorizedDistribution,
     )]
     pub game_session: Account<'info, GameSession>,
 
+    #[account(
+        seeds = [b"match_state", session_hash.as_ref()],
+        bump = match_state.load()?.bump,
+    )]
+    pub match_state: AccountLoader<'info, MatchState>,
+
     /// CHECK: Vault PDA that holds the funds
     #[account(
         mut,
-        seeds = [b"vault", session_id.as_bytes()],
+        seeds = [b"vault", session_hash.as_ref()],
         bump = game_session.vault_bump,
     )]
     pub vault: AccountInfo<'info>,
@@ -121,6 +183,10 @@ pub struct RefundWager<'info> {
     )]
     pub vault_token_account: Account<'info, TokenAccount>,
 
+    /// League the session's fixture belongs to, if any
+    #[account(mut)]
+    pub league: Option<Account<'info, League>>,
+
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
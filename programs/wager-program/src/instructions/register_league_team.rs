@@ -0,0 +1,108 @@
+use crate::{errors::WagerError, events::LeagueTeamRegistered, state::*, TOKEN_ID};
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{Token, TokenAccount};
+
+pub fn register_league_team_handler(
+    ctx: Context<RegisterLeagueTeam>,
+    _league_id: String,
+    roster: Vec<Pubkey>,
+) -> Result<()> {
+    let league = &mut ctx.accounts.league;
+
+    // Validate league status
+    require!(
+        league.status == LeagueStatus::Registration,
+        WagerError::InvalidGameState
+    );
+
+    let captain = ctx.accounts.captain.key();
+    require!(
+        !league.teams.iter().any(|team| team.captain == captain),
+        WagerError::InvalidPlayer
+    );
+    require!(
+        league.teams.len() < league.max_teams as usize,
+        WagerError::SessionIsFull
+    );
+
+    // A wallet can only play for one team of the league
+    LeagueTeam::validate_roster(&roster)?;
+    require!(
+        !league
+            .teams
+            .iter()
+            .any(|team| team.roster.iter().any(|player| roster.contains(player))),
+        WagerError::PlayerAlreadyJoined
+    );
+
+    // Transfer the entry fee from the captain to the league vault
+    anchor_spl::token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.captain_token_account.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.captain.to_account_info(),
+            },
+        ),
+        league.entry_fee,
+    )?;
+
+    let roster_size = roster.len() as u8;
+    league.teams.push(LeagueTeam {
+        captain,
+        roster,
+        ..Default::default()
+    });
+    emit!(LeagueTeamRegistered {
+        league: league.key(),
+        captain,
+        team: (league.teams.len() - 1) as u8,
+        roster_size,
+        amount: league.entry_fee,
+    });
+    league.update_standings();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(league_id: String)]
+pub struct RegisterLeagueTeam<'info> {
+    #[account(mut)]
+    pub captain: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"league", league_id.as_bytes()],
+        bump = league.bump,
+    )]
+    pub league: Account<'info, League>,
+
+    #[account(
+        mut,
+        constraint = captain_token_account.owner == captain.key(),
+        constraint = captain_token_account.mint == TOKEN_ID
+    )]
+    pub captain_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Vault PDA that holds the entry fees
+    #[account(
+        mut,
+        seeds = [b"league_vault", league_id.as_bytes()],
+        bump = league.vault_bump,
+    )]
+    pub vault: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = TOKEN_ID,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
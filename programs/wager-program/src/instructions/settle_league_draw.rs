@@ -0,0 +1,40 @@
+use crate::{errors::WagerError, events::LeagueMatchDrawn, state::*};
+use anchor_lang::prelude::*;
+
+use super::{return_stakes, RefundWager};
+
+pub fn settle_league_draw_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RefundWager<'info>>,
+    session_hash: [u8; 32],
+) -> Result<()> {
+    // Only a league fixture that kicked off and is still being played can be drawn
+    let game_session = &mut ctx.accounts.game_session;
+    let link = game_session
+        .league_match
+        .ok_or(WagerError::InvalidLeagueMatch)?;
+    require!(
+        game_session.status == GameStatus::InProgress,
+        WagerError::GameNotInProgress
+    );
+    let session = game_session.key();
+    game_session.set_status(session, GameStatus::Completed);
+
+    // Nobody wins a draw, so every stake goes back to its player
+    return_stakes(ctx.accounts, ctx.remaining_accounts, session_hash)?;
+
+    let league = ctx
+        .accounts
+        .league
+        .as_mut()
+        .ok_or(WagerError::InvalidLeague)?;
+    require_keys_eq!(league.key(), link.league, WagerError::InvalidLeague);
+    league.record_result(link.home, link.away, None)?;
+
+    emit!(LeagueMatchDrawn {
+        league: league.key(),
+        session,
+        home: link.home,
+        away: link.away,
+    });
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use crate::{errors::WagerError, events::SessionMigrated, state::*};
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+
+pub fn upgrade_session_handler(
+    ctx: Context<UpgradeSession>,
+    _session_hash: [u8; 32],
+) -> Result<()> {
+    let session_info = ctx.accounts.game_session.to_account_info();
+
+    // Only sessions still at the version 1 size need to grow
+    {
+        let data = session_info.try_borrow_data()?;
+        require!(
+            data.len() > 8 && data[..8] == GameSession::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        require!(
+            data[8] < GAME_SESSION_VERSION && data.len() == GAME_SESSION_V1_LEN,
+            WagerError::SessionAlreadyMigrated
+        );
+    }
+
+    // Top up the rent for the larger account, then grow it with zeroed bytes
+    let rent = Rent::get()?.minimum_balance(GameSession::LEN);
+    let top_up = rent.saturating_sub(session_info.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.game_server.to_account_info(),
+                    to: session_info.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    session_info.realloc(GameSession::LEN, true)?;
+
+    // The replay commitment takes the place of the first reserved byte, which is
+    // zero, so version 1 data reads back with no replay committed
+    let mut data = session_info.try_borrow_mut_data()?;
+    let mut game_session = GameSession::try_deserialize(&mut &data[..])?;
+    require_keys_eq!(
+        game_session.authority,
+        ctx.accounts.game_server.key(),
+        WagerError::UnauthorizedDistribution
+    );
+    game_session.version = GAME_SESSION_VERSION;
+    game_session.try_serialize(&mut &mut data[..])?;
+
+    emit!(SessionMigrated {
+        session: session_info.key(),
+        version: GAME_SESSION_VERSION,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(session_hash: [u8; 32])]
+pub struct UpgradeSession<'info> {
+    #[account(mut)]
+    pub game_server: Signer<'info>,
+
+    /// CHECK: Loaded by hand once it has grown to the current layout
+    #[account(
+        mut,
+        seeds = [b"game_session", session_hash.as_ref()],
+        bump,
+        owner = crate::ID,
+    )]
+    pub game_session: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,37 @@
+use crate::{errors::WagerError, events::ReplayEventVerified, state::*};
+use anchor_lang::prelude::*;
+
+pub fn verify_replay_event_handler(
+    ctx: Context<VerifyReplayEvent>,
+    _session_hash: [u8; 32],
+    event: ReplayEvent,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    let game_session = &ctx.accounts.game_session;
+    let replay = game_session
+        .replay
+        .as_ref()
+        .ok_or(WagerError::ReplayNotCommitted)?;
+
+    // Anyone can prove an event was in the log the game server settled on
+    require!(
+        replay.contains(&event, &proof),
+        WagerError::InvalidReplayProof
+    );
+
+    emit!(ReplayEventVerified {
+        session: game_session.key(),
+        event,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(session_hash: [u8; 32])]
+pub struct VerifyReplayEvent<'info> {
+    #[account(
+        seeds = [b"game_session", session_hash.as_ref()],
+        bump = game_session.bump,
+    )]
+    pub game_session: Account<'info, GameSession>,
+}
@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod state;
 pub mod utils;
@@ -17,49 +18,203 @@ pub mod wager_program {
 
     pub fn create_game_session(
         ctx: Context<CreateGameSession>,
-        session_id: String,
+        session_hash: [u8; 32],
         bet_amount: u64,
         game_mode: state::GameMode,
+        team_size: u8,
+        team_count: u8,
+        options: state::SessionOptions,
     ) -> Result<()> {
-        create_game_session_handler(ctx, session_id, bet_amount, game_mode)
+        create_game_session_handler(
+            ctx,
+            session_hash,
+            bet_amount,
+            game_mode,
+            team_size,
+            team_count,
+            options,
+        )
     }
 
-    pub fn join_user(ctx: Context<JoinUser>, session_id: String, team: u8) -> Result<()> {
-        join_user_handler(ctx, session_id, team)
+    pub fn join_user(
+        ctx: Context<JoinUser>,
+        session_hash: [u8; 32],
+        team: u8,
+        credentials: state::JoinCredentials,
+    ) -> Result<()> {
+        join_user_handler(ctx, session_hash, team, credentials)
+    }
+
+    pub fn join_free_for_all(
+        ctx: Context<JoinUser>,
+        session_hash: [u8; 32],
+        credentials: state::JoinCredentials,
+    ) -> Result<()> {
+        join_free_for_all_handler(ctx, session_hash, credentials)
     }
 
     pub fn distribute_winnings<'info>(
         ctx: Context<'_, '_, 'info, 'info, DistributeWinnings<'info>>,
-        session_id: String,
+        session_hash: [u8; 32],
         winning_team: u8,
     ) -> Result<()> {
         //if winner takes all, distribute all winnings else distribute winnings for the winners
+        //free-for-all games pay out by finishing place and ignore the winning team
         if ctx.accounts.game_session.is_pay_to_spawn() {
-            distribute_pay_spawn_earnings(ctx, session_id)
+            distribute_pay_spawn_earnings(ctx, session_hash)
+        } else if ctx.accounts.game_session.is_free_for_all() {
+            distribute_free_for_all_winnings(ctx, session_hash)
         } else {
-            distribute_all_winnings_handler(ctx, session_id, winning_team)
+            distribute_all_winnings_handler(ctx, session_hash, winning_team)
         }
     }
 
-    pub fn pay_to_spawn(ctx: Context<PayToSpawn>, session_id: String, team: u8) -> Result<()> {
-        pay_to_spawn_handler(ctx, session_id, team)
+    pub fn pay_to_spawn(ctx: Context<PayToSpawn>, session_hash: [u8; 32], team: u8) -> Result<()> {
+        pay_to_spawn_handler(ctx, session_hash, team)
     }
 
     pub fn record_kill(
         ctx: Context<RecordKill>,
-        session_id: String,
+        session_hash: [u8; 32],
         killer_team: u8,
         killer: Pubkey,
         victim_team: u8,
         victim: Pubkey,
     ) -> Result<()> {
-        record_kill_handler(ctx, session_id, killer_team, killer, victim_team, victim)
+        record_kill_handler(ctx, session_hash, killer_team, killer, victim_team, victim)
+    }
+
+    pub fn record_series_game(
+        ctx: Context<RecordSeriesGame>,
+        session_hash: [u8; 32],
+        winning_team: u8,
+    ) -> Result<()> {
+        record_series_game_handler(ctx, session_hash, winning_team)
+    }
+
+    pub fn create_match_log(ctx: Context<CreateMatchLog>, session_hash: [u8; 32]) -> Result<()> {
+        create_match_log_handler(ctx, session_hash)
+    }
+
+    pub fn create_tournament(
+        ctx: Context<CreateTournament>,
+        tournament_id: String,
+        entry_fee: u64,
+        max_players: u8,
+        prize_percentages: [u8; 3],
+    ) -> Result<()> {
+        create_tournament_handler(
+            ctx,
+            tournament_id,
+            entry_fee,
+            max_players,
+            prize_percentages,
+        )
+    }
+
+    pub fn join_tournament(ctx: Context<JoinTournament>, tournament_id: String) -> Result<()> {
+        join_tournament_handler(ctx, tournament_id)
+    }
+
+    pub fn create_tournament_match(
+        ctx: Context<CreateTournamentMatch>,
+        tournament_id: String,
+        match_index: u8,
+        session_hash: [u8; 32],
+    ) -> Result<()> {
+        create_tournament_match_handler(ctx, tournament_id, match_index, session_hash)
+    }
+
+    pub fn distribute_tournament_prizes<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeTournamentPrizes<'info>>,
+        tournament_id: String,
+    ) -> Result<()> {
+        distribute_tournament_prizes_handler(ctx, tournament_id)
+    }
+
+    pub fn create_league(
+        ctx: Context<CreateLeague>,
+        league_id: String,
+        entry_fee: u64,
+        max_teams: u8,
+        prize_percentages: [u8; 3],
+    ) -> Result<()> {
+        create_league_handler(ctx, league_id, entry_fee, max_teams, prize_percentages)
+    }
+
+    pub fn register_league_team(
+        ctx: Context<RegisterLeagueTeam>,
+        league_id: String,
+        roster: Vec<Pubkey>,
+    ) -> Result<()> {
+        register_league_team_handler(ctx, league_id, roster)
+    }
+
+    pub fn link_league_match(
+        ctx: Context<LinkLeagueMatch>,
+        league_id: String,
+        session_hash: [u8; 32],
+        home: u8,
+        away: u8,
+    ) -> Result<()> {
+        link_league_match_handler(ctx, league_id, session_hash, home, away)
+    }
+
+    pub fn close_league_season<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseLeagueSeason<'info>>,
+        league_id: String,
+    ) -> Result<()> {
+        close_league_season_handler(ctx, league_id)
+    }
+
+    pub fn migrate_session<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigrateSession<'info>>,
+        session_id: String,
+        session_hash: [u8; 32],
+    ) -> Result<()> {
+        migrate_session_handler(ctx, session_id, session_hash)
+    }
+
+    pub fn upgrade_session(ctx: Context<UpgradeSession>, session_hash: [u8; 32]) -> Result<()> {
+        upgrade_session_handler(ctx, session_hash)
     }
 
     pub fn refund_wager<'info>(
         ctx: Context<'_, '_, 'info, 'info, RefundWager<'info>>,
-        session_id: String,
+        session_hash: [u8; 32],
+    ) -> Result<()> {
+        refund_wager_handler(ctx, session_hash)
+    }
+
+    pub fn settle_league_draw<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RefundWager<'info>>,
+        session_hash: [u8; 32],
+    ) -> Result<()> {
+        settle_league_draw_handler(ctx, session_hash)
+    }
+
+    pub fn commit_replay(
+        ctx: Context<CommitReplay>,
+        session_hash: [u8; 32],
+        root: [u8; 32],
+        event_count: u32,
+    ) -> Result<()> {
+        commit_replay_handler(ctx, session_hash, root, event_count)
+    }
+
+    pub fn verify_replay_event(
+        ctx: Context<VerifyReplayEvent>,
+        session_hash: [u8; 32],
+        event: state::ReplayEvent,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        verify_replay_event_handler(ctx, session_hash, event, proof)
+    }
+
+    pub fn close_game_session(
+        ctx: Context<CloseGameSession>,
+        session_hash: [u8; 32],
     ) -> Result<()> {
-        refund_wager_handler(ctx, session_id)
+        close_game_session_handler(ctx, session_hash)
     }
 }
@@ -1,154 +1,843 @@
 //! State accounts for the betting program
 use crate::errors::WagerError;
+use crate::events::StatusChanged;
+use crate::utils::verify_merkle_proof;
+use crate::TOKEN_ID;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token::TokenAccount;
 
-/// Game mode defining the team sizes
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+/// Game mode defining how the pot is won
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
 pub enum GameMode {
-    WinnerTakesAllOneVsOne,     // 1v1 game mode
-    WinnerTakesAllThreeVsThree, // 3v3 game mode
-    WinnerTakesAllFiveVsFive,   // 5v5 game mode
-    PayToSpawnOneVsOne,         // 1v1 game mode
-    PayToSpawnThreeVsThree,     // 3v3 game mode
-    PayToSpawnFiveVsFive,       // 5v5 game mode
+    WinnerTakesAll, // Winning team splits the pot
+    PayToSpawn,     // Players buy spawns and earn from kills and spawns left
+    FreeForAll {
+        max_players: u8,             // Number of individual players (2-16)
+        payout_percentages: [u8; 3], // Share of the pot for 1st, 2nd and 3rd place
+    },
+    Practice, // Zero-stake team game that pays nothing out
 }
 
+/// Largest number of players in a team
+pub const MAX_TEAM_SIZE: u8 = 10;
+
+/// Longest human-readable session ID
+pub const MAX_SESSION_ID_LEN: usize = 32;
+
+/// Smallest and largest bet per player for each supported mint
+pub const BET_LIMITS: [(Pubkey, u64, u64); 1] = [(TOKEN_ID, 1_000_000, 1_000_000_000_000)];
+
+/// Number of spawns each player starts a game with
+pub const STARTING_SPAWNS: u16 = 10;
+
+/// Longest best-of-N series a session can be played as
+pub const MAX_SERIES_GAMES: u8 = 7;
+
+/// Smallest number of teams in a team game
+pub const MIN_TEAMS: u8 = 2;
+
+/// Largest number of teams in a team game
+pub const MAX_TEAMS: u8 = 4;
+
+/// Largest number of linked wallet groups in a session
+pub const MAX_LINKED_WALLET_GROUPS: usize = 4;
+
+/// Largest number of wallets in a linked wallet group
+pub const MAX_LINKED_GROUP_SIZE: usize = 8;
+
+/// Largest number of wallets in an inline allowlist
+pub const MAX_ALLOWLIST_WALLETS: usize = 16;
+
+/// Smallest number of players in a free-for-all game
+pub const MIN_FREE_FOR_ALL_PLAYERS: u8 = 2;
+
+/// Largest number of players in a free-for-all game
+pub const MAX_FREE_FOR_ALL_PLAYERS: u8 = 16;
+
 impl GameMode {
-    /// Returns the required number of players per team
-    pub fn players_per_team(&self) -> usize {
+    /// Returns the number of individual player slots for free-for-all games
+    pub fn free_for_all_slots(&self) -> usize {
         match self {
-            Self::WinnerTakesAllOneVsOne => 1,
-            Self::WinnerTakesAllThreeVsThree => 3,
-            Self::WinnerTakesAllFiveVsFive => 5,
-            Self::PayToSpawnOneVsOne => 1,
-            Self::PayToSpawnThreeVsThree => 3,
-            Self::PayToSpawnFiveVsFive => 5,
+            Self::FreeForAll { max_players, .. } => *max_players as usize,
+            _ => 0,
+        }
+    }
+
+    /// Checks the bet against the mint's limits. Practice games are the only zero-stake games
+    pub fn validate_bet(&self, mint: Pubkey, bet_amount: u64) -> Result<()> {
+        if *self == Self::Practice {
+            require!(bet_amount == 0, WagerError::InvalidBetAmount);
+            return Ok(());
         }
+        validate_stake(mint, bet_amount)
     }
+
+    /// Checks the team layout and, for free-for-all games, the player count and payout table
+    pub fn validate(&self, team_size: u8, team_count: u8) -> Result<()> {
+        if let Self::FreeForAll {
+            max_players,
+            payout_percentages,
+        } = self
+        {
+            // Free-for-all players don't belong to a team
+            require!(
+                team_size == 0 && team_count == 0,
+                WagerError::InvalidPlayerCount
+            );
+
+            require!(
+                (MIN_FREE_FOR_ALL_PLAYERS..=MAX_FREE_FOR_ALL_PLAYERS).contains(max_players),
+                WagerError::InvalidPlayerCount
+            );
+
+            validate_payout_percentages(payout_percentages, *max_players)?;
+        } else {
+            require!(
+                (1..=MAX_TEAM_SIZE).contains(&team_size),
+                WagerError::InvalidPlayerCount
+            );
+            require!(
+                (MIN_TEAMS..=MAX_TEAMS).contains(&team_count),
+                WagerError::InvalidTeamCount
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Checks a stake, a bet or an entry fee, against the mint's limits
+pub fn validate_stake(mint: Pubkey, amount: u64) -> Result<()> {
+    let (_, min_bet, max_bet) = BET_LIMITS
+        .iter()
+        .find(|(limit_mint, _, _)| *limit_mint == mint)
+        .ok_or(WagerError::InvalidMint)?;
+    require!(
+        (*min_bet..=*max_bet).contains(&amount),
+        WagerError::InvalidBetAmount
+    );
+    Ok(())
+}
+
+/// Checks a session ID is 1 to 32 ASCII letters, digits, dashes or underscores
+pub fn validate_session_id(session_id: &str) -> Result<()> {
+    require!(
+        (1..=MAX_SESSION_ID_LEN).contains(&session_id.len())
+            && session_id
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'),
+        WagerError::InvalidSessionId
+    );
+    Ok(())
+}
+
+/// Checks that a 1st/2nd/3rd place payout table adds up to 100 and can be filled by `player_count` players
+pub fn validate_payout_percentages(payout_percentages: &[u8; 3], player_count: u8) -> Result<()> {
+    let total: u16 = payout_percentages.iter().map(|p| *p as u16).sum();
+    require!(total == 100, WagerError::InvalidPayoutPercentages);
+
+    // Better places never earn less than worse ones
+    require!(
+        payout_percentages.windows(2).all(|w| w[0] >= w[1]),
+        WagerError::InvalidPayoutPercentages
+    );
+
+    // Every paid place needs a player to fill it
+    let paid_places = payout_percentages.iter().filter(|p| **p > 0).count();
+    require!(
+        paid_places <= player_count as usize,
+        WagerError::InvalidPayoutPercentages
+    );
+    Ok(())
 }
 
 /// Status of a game session
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Default, InitSpace)]
 pub enum GameStatus {
+    #[default]
     WaitingForPlayers, // Waiting for players to join
-    InProgress,        // Game is active with all players joined
-    Completed,         // Game has finished and rewards distributed
-}
-
-impl Default for GameStatus {
-    fn default() -> Self {
-        Self::WaitingForPlayers
-    }
+    InProgress, // Game is active with all players joined
+    Completed,  // Game has finished and rewards distributed
+    Ended,      // A whole team was eliminated, awaiting distribution
 }
 
 /// Represents a team in the game
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
 pub struct Team {
-    pub players: [Pubkey; 5],    // Array of player public keys
-    pub total_bet: u64,          // Total amount bet by team (in lamports)
-    pub player_spawns: [u16; 5], // Number of spawns remaining for each player
-    pub player_kills: [u16; 5],  // Number of kills for each player
+    #[max_len(MAX_TEAM_SIZE)]
+    pub players: Vec<Pubkey>, // Player public keys, one slot per team member
+    pub total_bet: u64,  // Total amount bet by team (in lamports)
+    pub player_bet: u64, // Stake each player of the team pays to join
 }
 
 impl Team {
+    /// Creates a team with `team_size` empty player slots, each staking `player_bet`
+    pub fn new(team_size: usize, player_bet: u64) -> Self {
+        Self {
+            players: vec![Pubkey::default(); team_size],
+            total_bet: 0,
+            player_bet,
+        }
+    }
+
     /// Finds the first empty slot in the team, if available
-    pub fn get_empty_slot(&self, player_count: usize) -> Result<usize> {
+    pub fn get_empty_slot(&self) -> Result<usize> {
         self.players
             .iter()
-            .enumerate()
-            .find(|(i, player)| **player == Pubkey::default() && *i < player_count)
-            .map(|(i, _)| i)
+            .position(|player| *player == Pubkey::default())
             .ok_or_else(|| error!(WagerError::TeamIsFull))
     }
+
+    /// Records a deposit made by one of the team's players
+    pub fn add_bet(&mut self, amount: u64) -> Result<()> {
+        self.total_bet = self
+            .total_bet
+            .checked_add(amount)
+            .ok_or(error!(WagerError::ArithmeticError))?;
+        Ok(())
+    }
+}
+
+/// Size and stake of a single team in an asymmetric match
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct TeamConfig {
+    pub size: u8,        // Number of players in the team
+    pub player_bet: u64, // Stake each player of the team pays to join
 }
 
+/// Optional settings chosen when a game session is created
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct SessionOptions {
+    pub team_configs: Vec<TeamConfig>, // Per-team sizes and stakes, empty for symmetric teams
+    pub best_of: u8,                   // Number of games in a series, 0 or 1 for a single game
+    pub k_factor: u8,                  // Rating K-factor, 0 for the default
+    pub rating_band: Option<RatingBand>, // Ratings joiners must fall within, if any
+    pub linked_wallets: Vec<Vec<Pubkey>>, // Groups of wallets that may not face each other
+    pub allowlist: Option<Allowlist>,  // Wallets allowed to join the session, if invite-only
+    pub team_allowlists: Vec<Option<Allowlist>>, // Wallets allowed on each team, empty for none
+    pub invite_key: Option<Pubkey>,    // Key derived from the lobby's invite code, if any
+    pub token_gate: Option<TokenGate>, // Token joiners must hold, if gated
+    pub session_id: Option<String>,    // Human-readable identifier stored as metadata
+}
+
+/// Token or NFT collection a joiner must hold to enter a gated session
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub struct TokenGate {
+    pub mint: Pubkey,     // Mint of the gating token
+    pub min_balance: u64, // Smallest balance the joiner must hold
+}
+
+/// Wallets allowed to join an invite-only session or team
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum Allowlist {
+    Wallets(#[max_len(MAX_ALLOWLIST_WALLETS)] Vec<Pubkey>), // Inline list for small lobbies
+    MerkleRoot([u8; 32]), // Root of a Merkle tree of wallet hashes for large lobbies
+}
+
+impl Allowlist {
+    /// Checks an inline list fits the space reserved for it on the session
+    pub fn validate(&self) -> Result<()> {
+        if let Allowlist::Wallets(wallets) = self {
+            require!(
+                wallets.len() <= MAX_ALLOWLIST_WALLETS,
+                WagerError::AllowlistTooLarge
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks `player` is on the list, using `merkle_proof` for Merkle root lists
+    pub fn contains(&self, player: Pubkey, merkle_proof: &[[u8; 32]]) -> bool {
+        match self {
+            Allowlist::Wallets(wallets) => wallets.contains(&player),
+            Allowlist::MerkleRoot(root) => {
+                let leaf = hashv(&[player.as_ref()]).to_bytes();
+                verify_merkle_proof(leaf, merkle_proof, *root)
+            }
+        }
+    }
+}
+
+/// Proofs a joiner presents to get into a restricted session
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct JoinCredentials {
+    pub merkle_proof: Vec<[u8; 32]>, // Proof of membership in a Merkle root allowlist
+}
+
+/// Inclusive range of ratings allowed to join a session
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub struct RatingBand {
+    pub min_rating: u16, // Lowest rating allowed to join
+    pub max_rating: u16, // Highest rating allowed to join
+}
+
+/// Best-of-N series played out of a single escrow
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct Series {
+    pub best_of: u8, // Number of games in the series
+    #[max_len(MAX_TEAMS)]
+    pub team_wins: Vec<u8>, // Games won by each team
+    #[max_len(MAX_SERIES_GAMES)]
+    pub game_winners: Vec<u8>, // Winning team of each game played, in order
+}
+
+impl Series {
+    /// Returns the team that has won the majority of games, if any
+    pub fn get_winner(&self) -> Option<u8> {
+        self.team_wins
+            .iter()
+            .position(|wins| *wins > self.best_of / 2)
+            .map(|team| team as u8)
+    }
+}
+
+/// Links a game session to the tournament bracket match it is played for
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub struct TournamentMatchLink {
+    pub tournament: Pubkey, // Tournament account
+    pub match_index: u8,    // Index of the match in the bracket
+}
+
+/// Links a game session to the league fixture it is played for
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub struct LeagueMatchLink {
+    pub league: Pubkey, // League account
+    pub home: u8,       // League team playing as team 0
+    pub away: u8,       // League team playing as team 1
+}
+
+/// Current layout version of the game session account. Version 2 added the replay commitment
+pub const GAME_SESSION_VERSION: u8 = 2;
+
+/// Account space of version 1 sessions, allocated before the replay commitment was added
+pub const GAME_SESSION_V1_LEN: usize = GameSession::LEN - (1 + ReplayCommitment::INIT_SPACE);
+
+/// Bytes kept free at the end of a game session for future fields
+pub const GAME_SESSION_RESERVED: usize = 31;
+
 /// Represents a game session between teams with its own pool
 #[account]
+#[derive(InitSpace)]
 pub struct GameSession {
-    pub session_id: String,  // Unique identifier for the game
-    pub authority: Pubkey,   // Creator of the game session
-    pub session_bet: u64,    // Required bet amount per player
-    pub game_mode: GameMode, // Game configuration (1v1, 2v2, 5v5)
-    pub team_a: Team,        // First team
-    pub team_b: Team,        // Second team
-    pub status: GameStatus,  // Current game state
-    pub created_at: i64,     // Creation timestamp
-    pub bump: u8,            // PDA bump
-    pub vault_bump: u8,      // Add this field for vault PDA bump
+    pub version: u8,            // Layout version, see GAME_SESSION_VERSION
+    pub session_hash: [u8; 32], // Hash the session's PDAs are derived from
+    #[max_len(MAX_SESSION_ID_LEN)]
+    pub session_id: Option<String>, // Optional human-readable identifier
+    pub authority: Pubkey,      // Creator of the game session
+    pub session_bet: u64,       // Required bet amount per player
+    pub game_mode: GameMode,    // Game configuration (winner takes all, pay to spawn, free for all)
+    pub team_size: u8,          // Number of players in the largest team
+    #[max_len(MAX_TEAMS)]
+    pub teams: Vec<Team>, // Competing teams, addressed by index
+    pub status: GameStatus,     // Current game state
+    pub created_at: i64,        // Creation timestamp
+    pub bump: u8,               // PDA bump
+    pub vault_bump: u8,         // Add this field for vault PDA bump
+    pub vault_token_bump: u8,
+    #[max_len(MAX_FREE_FOR_ALL_PLAYERS)]
+    pub free_for_all_players: Vec<Pubkey>, // Players of a free-for-all game, in joining order
+    pub series: Option<Series>, // Best-of-N series state, if the session is a series
+    pub tournament_match: Option<TournamentMatchLink>, // Bracket match this session settles
+    pub league_match: Option<LeagueMatchLink>, // League fixture this session settles
+    pub k_factor: u8,           // Rating K-factor applied on settlement
+    pub rating_band: Option<RatingBand>, // Ratings joiners must fall within, if any
+    #[max_len(MAX_LINKED_WALLET_GROUPS, MAX_LINKED_GROUP_SIZE)]
+    pub linked_wallets: Vec<Vec<Pubkey>>, // Groups of wallets that may not face each other
+    pub allowlist: Option<Allowlist>, // Wallets allowed to join the session, if invite-only
+    #[max_len(MAX_TEAMS)]
+    pub team_allowlists: Vec<Option<Allowlist>>, // Wallets allowed on each team, empty for none
+    pub invite_key: Option<Pubkey>, // Key derived from the lobby's invite code, if any
+    pub token_gate: Option<TokenGate>, // Token joiners must hold, if gated
+    pub match_log: Option<Pubkey>, // Match log recording the session's timeline, if any
+    pub replay: Option<ReplayCommitment>, // Merkle commitment to the full event log, once settled
+    pub reserved: [u8; GAME_SESSION_RESERVED], // Room for future fields without a realloc
+}
+
+/// Game mode of the original session layout, where the mode fixed the team size
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum GameModeV0 {
+    WinnerTakesAllOneVsOne,     // 1v1 game mode
+    WinnerTakesAllThreeVsThree, // 3v3 game mode
+    WinnerTakesAllFiveVsFive,   // 5v5 game mode
+    PayToSpawnOneVsOne,         // 1v1 game mode
+    PayToSpawnThreeVsThree,     // 3v3 game mode
+    PayToSpawnFiveVsFive,       // 5v5 game mode
+}
+
+impl GameModeV0 {
+    /// Returns the current game mode and team size of a legacy mode
+    pub fn upgrade(&self) -> (GameMode, u8) {
+        match self {
+            Self::WinnerTakesAllOneVsOne => (GameMode::WinnerTakesAll, 1),
+            Self::WinnerTakesAllThreeVsThree => (GameMode::WinnerTakesAll, 3),
+            Self::WinnerTakesAllFiveVsFive => (GameMode::WinnerTakesAll, 5),
+            Self::PayToSpawnOneVsOne => (GameMode::PayToSpawn, 1),
+            Self::PayToSpawnThreeVsThree => (GameMode::PayToSpawn, 3),
+            Self::PayToSpawnFiveVsFive => (GameMode::PayToSpawn, 5),
+        }
+    }
+}
+
+/// Team of the original session layout, which kept combat stats inline
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TeamV0 {
+    pub players: [Pubkey; 5],    // Array of player public keys
+    pub total_bet: u64,          // Total amount bet by team (in lamports)
+    pub player_spawns: [u16; 5], // Number of spawns remaining for each player
+    pub player_kills: [u16; 5],  // Number of kills for each player
+}
+
+/// Game session layout from before versioning, addressed by its session ID.
+/// Kept so sessions created on it can be migrated
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GameSessionV0 {
+    pub session_id: String,    // Unique identifier for the game
+    pub authority: Pubkey,     // Creator of the game session
+    pub session_bet: u64,      // Required bet amount per player
+    pub game_mode: GameModeV0, // Game configuration (team size and format)
+    pub team_a: TeamV0,        // First team
+    pub team_b: TeamV0,        // Second team
+    pub status: GameStatus,    // Current game state
+    pub created_at: i64,       // Creation timestamp
+    pub bump: u8,              // PDA bump
+    pub vault_bump: u8,        // Vault PDA bump
     pub vault_token_bump: u8,
 }
 
+impl GameSessionV0 {
+    /// Account space the original program allocated for every session
+    pub const LEN: usize =
+        8 + 4 + 10 + 32 + 8 + 1 + (2 * (32 * 5 + 16 * 5 + 16 * 5 + 8)) + 1 + 8 + 1 + 1 + 1;
+
+    /// Returns the legacy teams in index order
+    pub fn teams(&self) -> [&TeamV0; 2] {
+        [&self.team_a, &self.team_b]
+    }
+
+    /// Returns every seated player in team order, matching the upgraded session's
+    /// `get_joined_players`
+    pub fn get_joined_players(&self) -> Vec<Pubkey> {
+        let (_, team_size) = self.game_mode.upgrade();
+        self.teams()
+            .iter()
+            .flat_map(|team| team.players[..team_size as usize].iter().copied())
+            .filter(|player| *player != Pubkey::default())
+            .collect()
+    }
+
+    /// Converts the session to the current layout under its new address
+    pub fn upgrade(&self, session_hash: [u8; 32], bump: u8, vault_bump: u8) -> GameSession {
+        let (game_mode, team_size) = self.game_mode.upgrade();
+        let teams = self
+            .teams()
+            .iter()
+            .map(|legacy| Team {
+                players: legacy.players[..team_size as usize].to_vec(),
+                total_bet: legacy.total_bet,
+                player_bet: self.session_bet,
+            })
+            .collect();
+
+        GameSession {
+            version: GAME_SESSION_VERSION,
+            session_hash,
+            session_id: Some(self.session_id.clone()),
+            authority: self.authority,
+            session_bet: self.session_bet,
+            game_mode,
+            team_size,
+            teams,
+            status: self.status.clone(),
+            created_at: self.created_at,
+            bump,
+            vault_bump,
+            vault_token_bump: 0,
+            free_for_all_players: Vec::new(),
+            series: None,
+            tournament_match: None,
+            league_match: None,
+            k_factor: DEFAULT_K_FACTOR,
+            rating_band: None,
+            linked_wallets: Vec::new(),
+            allowlist: None,
+            team_allowlists: Vec::new(),
+            invite_key: None,
+            token_gate: None,
+            match_log: None,
+            replay: None,
+            reserved: [0; GAME_SESSION_RESERVED],
+        }
+    }
+}
+
 impl GameSession {
+    /// Account space of a session, sized for the largest layout it can hold
+    pub const LEN: usize = 8 + GameSession::INIT_SPACE;
+
+    /// Gets a reference to the specified team
+    pub fn get_team(&self, team: u8) -> Result<&Team> {
+        self.teams
+            .get(team as usize)
+            .ok_or(error!(WagerError::InvalidTeam))
+    }
+
+    /// Gets a mutable reference to the specified team
+    pub fn get_team_mut(&mut self, team: u8) -> Result<&mut Team> {
+        self.teams
+            .get_mut(team as usize)
+            .ok_or(error!(WagerError::InvalidTeam))
+    }
+
     /// Gets an empty slot for a player in the specified team
     pub fn get_player_empty_slot(&self, team: u8) -> Result<usize> {
-        let player_count = self.game_mode.players_per_team();
-        match team {
-            0 => self.team_a.get_empty_slot(player_count),
-            1 => self.team_b.get_empty_slot(player_count),
-            _ => Err(error!(WagerError::InvalidTeam)),
-        }
+        self.get_team(team)?.get_empty_slot()
     }
 
-    /// Checks if both teams are completely filled
+    /// Checks if every team is completely filled
     pub fn check_all_filled(&self) -> Result<bool> {
-        let player_count = self.game_mode.players_per_team();
+        Ok(self
+            .teams
+            .iter()
+            .all(|team| matches!(team.get_empty_slot(), Err(e) if is_team_full_error(&e))))
+    }
 
-        Ok(matches!(
-            (
-                self.team_a.get_empty_slot(player_count),
-                self.team_b.get_empty_slot(player_count)
-            ),
-            (Err(e1), Err(e2)) if is_team_full_error(&e1) && is_team_full_error(&e2)
-        ))
+    /// Checks every seat of the session has been taken
+    pub fn is_full(&self) -> Result<bool> {
+        if self.is_free_for_all() {
+            Ok(self.is_free_for_all_full())
+        } else {
+            self.check_all_filled()
+        }
+    }
+
+    /// Marks a full, started session as completed ahead of its payouts, so it
+    /// can only ever be settled once
+    pub fn start_settlement(&mut self, session: Pubkey) -> Result<()> {
+        require!(self.is_full()?, WagerError::NotAllPlayersJoined);
+        require!(
+            matches!(self.status, GameStatus::InProgress | GameStatus::Ended),
+            WagerError::InvalidGameState
+        );
+        self.set_status(session, GameStatus::Completed);
+        Ok(())
+    }
+
+    /// Checks a joining player's rating against the session's rating band
+    pub fn check_rating(&self, rating: u16) -> Result<()> {
+        if let Some(band) = self.rating_band {
+            require!(
+                (band.min_rating..=band.max_rating).contains(&rating),
+                WagerError::RatingOutOfBand
+            );
+        }
+        Ok(())
     }
 
     pub fn is_pay_to_spawn(&self) -> bool {
-        matches!(
-            self.game_mode,
-            GameMode::PayToSpawnOneVsOne
-                | GameMode::PayToSpawnThreeVsThree
-                | GameMode::PayToSpawnFiveVsFive
-        )
+        matches!(self.game_mode, GameMode::PayToSpawn)
+    }
+
+    pub fn is_free_for_all(&self) -> bool {
+        matches!(self.game_mode, GameMode::FreeForAll { .. })
+    }
+
+    pub fn is_practice(&self) -> bool {
+        matches!(self.game_mode, GameMode::Practice)
     }
 
     pub fn get_all_players(&self) -> Vec<Pubkey> {
-        let mut players = self.team_a.players.to_vec();
-        players.extend(self.team_b.players.to_vec());
+        let mut players: Vec<Pubkey> = self
+            .teams
+            .iter()
+            .flat_map(|team| team.players.iter().copied())
+            .collect();
+        players.extend(self.free_for_all_players.iter().copied());
         players
     }
 
+    /// Returns every player that has taken a seat, in team then free-for-all order
+    pub fn get_joined_players(&self) -> Vec<Pubkey> {
+        self.get_all_players()
+            .into_iter()
+            .filter(|player| *player != Pubkey::default())
+            .collect()
+    }
+
+    /// Gets the index of a player in a free-for-all game
+    pub fn get_free_for_all_index(&self, player: Pubkey) -> Result<usize> {
+        self.free_for_all_players
+            .iter()
+            .position(|p| *p == player)
+            .ok_or(error!(WagerError::PlayerNotFound))
+    }
+
+    /// Checks a wallet isn't already seated and won't face a wallet it is linked to.
+    /// `team` is None in free-for-all games, where every other player is an opponent
+    pub fn check_can_join(&self, player: Pubkey, team: Option<u8>) -> Result<()> {
+        require!(
+            !self.get_all_players().contains(&player),
+            WagerError::PlayerAlreadyJoined
+        );
+
+        for group in self.linked_wallets.iter().filter(|g| g.contains(&player)) {
+            let faces_linked_wallet = match team {
+                Some(team) => self
+                    .teams
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| *index != team as usize)
+                    .any(|(_, other)| other.players.iter().any(|p| group.contains(p))),
+                None => self.free_for_all_players.iter().any(|p| group.contains(p)),
+            };
+            require!(!faces_linked_wallet, WagerError::LinkedWalletOpponent);
+        }
+        Ok(())
+    }
+
+    /// Checks a wallet is on the session's allowlist and, for team games, the
+    /// allowlist of the team it is joining
+    pub fn check_allowlisted(
+        &self,
+        player: Pubkey,
+        team: Option<u8>,
+        credentials: &JoinCredentials,
+    ) -> Result<()> {
+        let team_allowlist = team.and_then(|team| self.team_allowlists.get(team as usize));
+        for allowlist in [
+            self.allowlist.as_ref(),
+            team_allowlist.and_then(Option::as_ref),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            require!(
+                allowlist.contains(player, &credentials.merkle_proof),
+                WagerError::NotOnAllowlist
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks a password protected lobby's join is co-signed by its invite key. Only
+    /// holders of the invite code can derive the key, and the code never goes on chain
+    pub fn check_invite(&self, invite: Option<Pubkey>) -> Result<()> {
+        if let Some(invite_key) = self.invite_key {
+            require!(invite == Some(invite_key), WagerError::InvalidInviteCode);
+        }
+        Ok(())
+    }
+
+    /// Checks a joiner presented a token account holding enough of the gating token
+    pub fn check_token_gate(
+        &self,
+        player: Pubkey,
+        gate_token_account: Option<&TokenAccount>,
+    ) -> Result<()> {
+        if let Some(gate) = self.token_gate {
+            let token_account = gate_token_account.ok_or(WagerError::TokenGateNotMet)?;
+            require!(
+                token_account.owner == player
+                    && token_account.mint == gate.mint
+                    && token_account.amount >= gate.min_balance,
+                WagerError::TokenGateNotMet
+            );
+        }
+        Ok(())
+    }
+
+    /// Adds a player to a free-for-all game, returning their seat in the match state
+    pub fn add_free_for_all_player(&mut self, player: Pubkey) -> Result<usize> {
+        require!(
+            self.free_for_all_players.len() < self.game_mode.free_for_all_slots(),
+            WagerError::SessionIsFull
+        );
+
+        self.free_for_all_players.push(player);
+        Ok(self.free_for_all_players.len() - 1)
+    }
+
+    /// Checks if every free-for-all slot has been taken
+    pub fn is_free_for_all_full(&self) -> bool {
+        self.free_for_all_players.len() == self.game_mode.free_for_all_slots()
+    }
+
     pub fn get_player_index(&self, team: u8, player: Pubkey) -> Result<usize> {
-        match team {
-            0 => self
-                .team_a
-                .players
-                .iter()
-                .position(|p| *p == player)
-                .ok_or(error!(WagerError::PlayerNotFound)),
-            1 => self
-                .team_b
-                .players
-                .iter()
-                .position(|p| *p == player)
-                .ok_or(error!(WagerError::PlayerNotFound)),
-            _ => return Err(error!(WagerError::InvalidTeam)),
-        }
-    }
-
-    /// Gets the kill and death difference for a player in a team
-    pub fn get_kills_and_spawns(&self, player_pubkey: Pubkey) -> Result<u16> {
-        // search in both teams and return the kill and death difference
-        let team_a_index = self.team_a.players.iter().position(|p| *p == player_pubkey);
-        let team_b_index = self.team_b.players.iter().position(|p| *p == player_pubkey);
-        if let Some(team_a_index) = team_a_index {
-            Ok(self.team_a.player_kills[team_a_index] as u16
-                + self.team_a.player_spawns[team_a_index] as u16)
-        } else if let Some(team_b_index) = team_b_index {
-            Ok(self.team_b.player_kills[team_b_index] as u16
-                + self.team_b.player_spawns[team_b_index] as u16)
+        self.get_team(team)?
+            .players
+            .iter()
+            .position(|p| *p == player)
+            .ok_or(error!(WagerError::PlayerNotFound))
+    }
+
+    /// Gets the amount a player staked to join the session
+    pub fn get_player_bet(&self, player: Pubkey) -> Result<u64> {
+        if self.is_free_for_all() {
+            self.get_free_for_all_index(player)?;
+            return Ok(self.session_bet);
+        }
+
+        self.teams
+            .iter()
+            .find(|team| team.players.contains(&player))
+            .map(|team| team.player_bet)
+            .ok_or(error!(WagerError::PlayerNotFound))
+    }
+
+    /// Records the winner of a series game, ending the session once a team holds
+    /// the majority and otherwise resetting the match state for the next game.
+    /// The game may have been ended by a team's elimination already
+    pub fn add_series_game(
+        &mut self,
+        winning_team: u8,
+        match_state: &mut MatchState,
+    ) -> Result<()> {
+        let decided = self
+            .series
+            .as_ref()
+            .is_some_and(|series| series.get_winner().is_some());
+        require!(
+            self.status == GameStatus::InProgress || (self.status == GameStatus::Ended && !decided),
+            WagerError::GameNotInProgress
+        );
+
+        // An eliminated team cannot be declared the winner
+        require!(
+            (winning_team as usize) < self.teams.len()
+                && !match_state.is_team_eliminated(winning_team),
+            WagerError::InvalidWinningTeam
+        );
+
+        let series = self.series.as_mut().ok_or(WagerError::InvalidGameMode)?;
+        require!(series.get_winner().is_none(), WagerError::InvalidGameState);
+
+        series.team_wins[winning_team as usize] += 1;
+        series.game_winners.push(winning_team);
+
+        if series.get_winner().is_some() {
+            self.set_status(match_state.session, GameStatus::Ended);
+            match_state.set_status(GameStatus::Ended);
         } else {
-            return Err(error!(WagerError::PlayerNotFound));
+            if self.status == GameStatus::Ended {
+                self.set_status(match_state.session, GameStatus::InProgress);
+            }
+            match_state.reset_stats();
+            match_state.set_status(GameStatus::InProgress);
         }
+        Ok(())
+    }
+
+    /// Moves the session to `status`, announcing the transition
+    pub fn set_status(&mut self, session: Pubkey, status: GameStatus) {
+        emit!(StatusChanged {
+            session,
+            from: self.status.clone(),
+            to: status.clone(),
+        });
+        self.status = status;
+    }
+
+    /// Adds the price of a respawn to a team's pot
+    pub fn add_spawn_bet(&mut self, team: u8) -> Result<()> {
+        let session_bet = self.session_bet;
+        self.get_team_mut(team)?.add_bet(session_bet)
+    }
+}
+
+/// Helper function to check if an error is TeamIsFull
+fn is_team_full_error(error: &Error) -> bool {
+    error.to_string().contains("TeamIsFull")
+}
+
+/// Current layout version of the match state account
+pub const MATCH_STATE_VERSION: u8 = 1;
+
+/// Seats in a match state, enough for the largest team game or free-for-all
+pub const MAX_SEATS: usize = MAX_TEAMS as usize * MAX_TEAM_SIZE as usize;
+
+/// Live combat state of a game session, kept in a fixed zero-copy layout so
+/// kills can be recorded without deserializing the whole session.
+/// Team players sit at `team * MAX_TEAM_SIZE + slot`, free-for-all players at their joining index
+#[account(zero_copy)]
+pub struct MatchState {
+    pub session: Pubkey,                  // Game session this state belongs to
+    pub authority: Pubkey,                // Game server allowed to record kills
+    pub players: [Pubkey; MAX_SEATS],     // Player sitting in each seat
+    pub spawns: [u16; MAX_SEATS],         // Spawns remaining for each seat
+    pub kills: [u16; MAX_SEATS],          // Kills made by each seat
+    pub eliminated: [u8; MAX_SEATS],      // 1 once a seat has run out of spawns
+    pub version: u8,                      // Layout version, see MATCH_STATE_VERSION
+    pub status: u8,                       // GameStatus of the match in play
+    pub team_count: u8,                   // Number of teams, 0 for free-for-all games
+    pub free_for_all: u8,                 // 1 for free-for-all games
+    pub pay_to_spawn: u8,                 // 1 for pay-to-spawn games
+    pub bump: u8,                         // PDA bump
+    pub match_log: u8,                    // 1 once a match log records this match
+    pub _padding: [u8; 1],                // Keeps the layout free of implicit padding
+    pub spawn_purchases: [u8; MAX_SEATS], // Sets of spawns each seat has paid for
+    pub _reserved: [u8; 24],              // Room for future fields without a realloc
+}
+
+impl MatchState {
+    pub const LEN: usize = 8 + std::mem::size_of::<MatchState>();
+
+    /// Sets up the match state of a newly created session
+    pub fn init(
+        &mut self,
+        session: Pubkey,
+        authority: Pubkey,
+        game_mode: &GameMode,
+        team_count: u8,
+        bump: u8,
+    ) {
+        self.session = session;
+        self.authority = authority;
+        self.version = MATCH_STATE_VERSION;
+        self.status = GameStatus::WaitingForPlayers as u8;
+        self.team_count = team_count;
+        self.free_for_all = matches!(game_mode, GameMode::FreeForAll { .. }) as u8;
+        self.pay_to_spawn = matches!(game_mode, GameMode::PayToSpawn) as u8;
+        self.bump = bump;
+    }
+
+    /// Returns the seat of a team player
+    pub fn team_seat(team: u8, slot: usize) -> usize {
+        team as usize * MAX_TEAM_SIZE as usize + slot
+    }
+
+    pub fn set_status(&mut self, status: GameStatus) {
+        self.status = status as u8;
+    }
+
+    pub fn is_status(&self, status: GameStatus) -> bool {
+        self.status == status as u8
+    }
+
+    /// Seats a player with a fresh set of spawns
+    pub fn seat_player(&mut self, seat: usize, player: Pubkey) {
+        self.players[seat] = player;
+        self.spawns[seat] = STARTING_SPAWNS;
+        self.kills[seat] = 0;
+        self.eliminated[seat] = 0;
+    }
+
+    /// Finds a player's seat. Teams are ignored in free-for-all games
+    pub fn find_seat(&self, team: u8, player: Pubkey) -> Result<usize> {
+        require_keys_neq!(player, Pubkey::default(), WagerError::PlayerNotFound);
+
+        let seats = if self.free_for_all == 1 {
+            0..MAX_SEATS
+        } else {
+            require!(team < self.team_count, WagerError::InvalidTeam);
+            Self::team_seat(team, 0)..Self::team_seat(team + 1, 0)
+        };
+        seats
+            .into_iter()
+            .find(|seat| self.players[*seat] == player)
+            .ok_or(error!(WagerError::PlayerNotFound))
+    }
+
+    fn is_seated(&self, seat: usize) -> bool {
+        self.players[seat] != Pubkey::default()
     }
 
     pub fn add_kill(
@@ -158,40 +847,825 @@ impl GameSession {
         victim_team: u8,
         victim: Pubkey,
     ) -> Result<()> {
-        let killer_player_index: usize = self.get_player_index(killer_team, killer)?;
-        let victim_player_index: usize = self.get_player_index(victim_team, victim)?;
-
         require!(
-            self.status == GameStatus::InProgress,
+            self.is_status(GameStatus::InProgress),
             WagerError::GameNotInProgress
         );
 
-        match killer_team {
-            0 => self.team_a.player_kills[killer_player_index] += 1,
-            1 => self.team_b.player_kills[killer_player_index] += 1,
-            _ => return Err(error!(WagerError::InvalidTeam)),
+        let killer_seat = self.find_seat(killer_team, killer)?;
+        let victim_seat = self.find_seat(victim_team, victim)?;
+
+        // Take the spawn first so kills on eliminated players are rejected
+        require!(
+            self.eliminated[victim_seat] == 0 && self.spawns[victim_seat] > 0,
+            WagerError::PlayerHasNoSpawns
+        );
+        self.spawns[victim_seat] -= 1;
+        if self.spawns[victim_seat] == 0 {
+            self.eliminated[victim_seat] = 1;
         }
 
-        match victim_team {
-            0 => self.team_a.player_spawns[victim_player_index] -= 1,
-            1 => self.team_b.player_spawns[victim_player_index] -= 1,
-            _ => return Err(error!(WagerError::InvalidTeam)),
+        self.kills[killer_seat] = self.kills[killer_seat]
+            .checked_add(1)
+            .ok_or(error!(WagerError::ArithmeticError))?;
+
+        // The match is over once a single team or player is left standing, unless
+        // players can still buy their way back in
+        let over = if self.free_for_all == 1 {
+            self.get_free_for_all_survivor().is_some()
+        } else {
+            self.pay_to_spawn == 0 && self.get_surviving_team().is_some()
+        };
+        if over {
+            self.set_status(GameStatus::Ended);
         }
 
         Ok(())
     }
 
-    pub fn add_spawns(&mut self, team: u8, player_index: usize) -> Result<()> {
-        match team {
-            0 => self.team_a.player_spawns[player_index] += 10u16,
-            1 => self.team_b.player_spawns[player_index] += 10u16,
-            _ => return Err(error!(WagerError::InvalidTeam)),
+    /// Gives a player another set of spawns after they paid for them
+    pub fn add_spawns(&mut self, team: u8, player: Pubkey) -> Result<()> {
+        let seat = self.find_seat(team, player)?;
+        self.spawns[seat] = self.spawns[seat]
+            .checked_add(STARTING_SPAWNS)
+            .ok_or(error!(WagerError::InvalidSpawns))?;
+        self.spawn_purchases[seat] = self.spawn_purchases[seat]
+            .checked_add(1)
+            .ok_or(error!(WagerError::InvalidSpawns))?;
+        self.eliminated[seat] = 0;
+        Ok(())
+    }
+
+    /// Gets the number of spawn sets a player has paid for, 0 if they are not seated
+    pub fn get_spawn_purchases(&self, player: Pubkey) -> u8 {
+        (0..MAX_SEATS)
+            .find(|seat| self.is_seated(*seat) && self.players[*seat] == player)
+            .map_or(0, |seat| self.spawn_purchases[seat])
+    }
+
+    /// Gets the kills plus spawns left of a player
+    pub fn get_kills_and_spawns(&self, player: Pubkey) -> Result<u16> {
+        (0..MAX_SEATS)
+            .find(|seat| self.is_seated(*seat) && self.players[*seat] == player)
+            .ok_or(error!(WagerError::PlayerNotFound))
+            .and_then(|seat| {
+                self.kills[seat]
+                    .checked_add(self.spawns[seat])
+                    .ok_or(error!(WagerError::ArithmeticError))
+            })
+    }
+
+    /// Checks if every seated player of the team has been eliminated
+    pub fn is_team_eliminated(&self, team: u8) -> bool {
+        let mut seated = (Self::team_seat(team, 0)..Self::team_seat(team + 1, 0))
+            .filter(|seat| self.is_seated(*seat))
+            .peekable();
+        seated.peek().is_some() && seated.all(|seat| self.eliminated[seat] == 1)
+    }
+
+    /// Returns the only team that still has players standing, if any
+    pub fn get_surviving_team(&self) -> Option<u8> {
+        let mut standing = (0..self.team_count).filter(|team| !self.is_team_eliminated(*team));
+        match (standing.next(), standing.next()) {
+            (Some(team), None) => Some(team),
+            _ => None,
+        }
+    }
+
+    /// Returns the only player still standing in a free-for-all game, if any
+    pub fn get_free_for_all_survivor(&self) -> Option<Pubkey> {
+        let mut standing =
+            (0..MAX_SEATS).filter(|seat| self.is_seated(*seat) && self.eliminated[*seat] == 0);
+        match (standing.next(), standing.next()) {
+            (Some(seat), None) => Some(self.players[seat]),
+            _ => None,
+        }
+    }
+
+    /// Restores spawns and clears kills of every seated player for a new game
+    pub fn reset_stats(&mut self) {
+        for seat in 0..MAX_SEATS {
+            if self.is_seated(seat) {
+                let player = self.players[seat];
+                self.seat_player(seat, player);
+            }
         }
+    }
+}
+
+/// Number of events a match log holds before it wraps around
+pub const MATCH_LOG_CAPACITY: usize = 64;
+
+/// Kind of event stored in a match log
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum MatchEventKind {
+    Kill,  // `player` killed `target`
+    Spawn, // `player` bought more spawns
+}
+
+/// A single entry of a match log
+#[zero_copy]
+pub struct MatchLogEntry {
+    pub slot: u64,         // Slot the event was recorded in
+    pub timestamp: i64,    // Unix timestamp the event was recorded at
+    pub player: Pubkey,    // Killer or spawn buyer
+    pub target: Pubkey,    // Victim of a kill, default for spawns
+    pub kind: u8,          // MatchEventKind of the event
+    pub team: u8,          // Team of `player`
+    pub target_team: u8,   // Team of `target`
+    pub _padding: [u8; 5], // Keeps the layout free of implicit padding
+}
+
+/// Ring buffer of the kills and spawn purchases of a game session, giving an
+/// auditable timeline for settling disputes. Once full the oldest events are overwritten
+#[account(zero_copy)]
+pub struct MatchLog {
+    pub session: Pubkey,   // Game session this log belongs to
+    pub total_events: u64, // Events ever recorded, including overwritten ones
+    pub entries: [MatchLogEntry; MATCH_LOG_CAPACITY], // Ring buffer of events
+    pub bump: u8,          // PDA bump
+    pub _padding: [u8; 7], // Keeps the layout free of implicit padding
+}
+
+impl MatchLog {
+    pub const LEN: usize = 8 + std::mem::size_of::<MatchLog>();
+
+    /// Appends an event stamped with the current slot and time, overwriting the oldest once full
+    pub fn record(
+        &mut self,
+        kind: MatchEventKind,
+        player: Pubkey,
+        team: u8,
+        target: Pubkey,
+        target_team: u8,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let index = (self.total_events % MATCH_LOG_CAPACITY as u64) as usize;
+        self.entries[index] = MatchLogEntry {
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+            player,
+            target,
+            kind: kind as u8,
+            team,
+            target_team,
+            _padding: [0; 5],
+        };
+        self.total_events += 1;
         Ok(())
     }
+
+    /// Returns the stored events from oldest to newest
+    pub fn events(&self) -> Vec<MatchLogEntry> {
+        let stored = self.total_events.min(MATCH_LOG_CAPACITY as u64);
+        (self.total_events - stored..self.total_events)
+            .map(|event| self.entries[(event % MATCH_LOG_CAPACITY as u64) as usize])
+            .collect()
+    }
 }
 
-/// Helper function to check if an error is TeamIsFull
-fn is_team_full_error(error: &Error) -> bool {
-    error.to_string().contains("TeamIsFull")
+/// Merkle root of a session's full event log, committed by the game server at settlement
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, InitSpace)]
+pub struct ReplayCommitment {
+    pub root: [u8; 32],   // Root of the tree of ReplayEvent leaves
+    pub event_count: u32, // Number of events in the log
+}
+
+impl ReplayCommitment {
+    /// Checks `event` is in the committed log using a Merkle proof
+    pub fn contains(&self, event: &ReplayEvent, proof: &[[u8; 32]]) -> bool {
+        event.index < self.event_count && verify_merkle_proof(event.leaf(), proof, self.root)
+    }
+}
+
+/// An event of the full log kept off-chain by the game server, used as a replay leaf
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub struct ReplayEvent {
+    pub index: u32,           // Position of the event in the log
+    pub kind: MatchEventKind, // Kill or spawn purchase
+    pub player: Pubkey,       // Killer or spawn buyer
+    pub team: u8,             // Team of `player`
+    pub target: Pubkey,       // Victim of a kill, default for spawns
+    pub target_team: u8,      // Team of `target`
+    pub slot: u64,            // Slot the event happened in
+    pub timestamp: i64,       // Unix timestamp the event happened at
+}
+
+impl ReplayEvent {
+    /// Returns the Merkle leaf of the event, the hash of its serialized form
+    pub fn leaf(&self) -> [u8; 32] {
+        hashv(&[&self.try_to_vec().unwrap_or_default()]).to_bytes()
+    }
+}
+
+/// Smallest number of players in a tournament
+pub const MIN_TOURNAMENT_PLAYERS: u8 = 2;
+
+/// Largest number of players in a tournament
+pub const MAX_TOURNAMENT_PLAYERS: u8 = 16;
+
+/// Longest tournament or league ID, which has to fit a PDA seed
+pub const MAX_COMPETITION_ID_LEN: usize = 32;
+
+/// Checks a tournament or league ID fits the space reserved for it
+pub fn validate_competition_id(id: &str) -> Result<()> {
+    require!(
+        (1..=MAX_COMPETITION_ID_LEN).contains(&id.len()),
+        WagerError::InvalidCompetitionId
+    );
+    Ok(())
+}
+
+/// Status of a tournament
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Default)]
+pub enum TournamentStatus {
+    #[default]
+    Registration, // Waiting for players to register
+    InProgress, // Bracket is being played
+    Completed,  // Final has been played and prizes distributed
+}
+
+/// A single match slot in a tournament bracket
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct BracketMatch {
+    pub player_a: Pubkey,     // Player from the upper feeder slot, plays as team 0
+    pub player_b: Pubkey,     // Player from the lower feeder slot, plays as team 1
+    pub game_session: Pubkey, // Game session the match is played in
+    pub winner: Pubkey,       // Winner of the match once settled
+}
+
+impl BracketMatch {
+    pub const LEN: usize = 32 * 4;
+
+    /// Returns the player that lost the match, once it has been settled
+    pub fn get_loser(&self) -> Option<Pubkey> {
+        if self.winner == Pubkey::default() {
+            None
+        } else if self.winner == self.player_a {
+            Some(self.player_b)
+        } else {
+            Some(self.player_a)
+        }
+    }
+}
+
+/// Single-elimination tournament with its own entry fee pool
+#[account]
+pub struct Tournament {
+    pub tournament_id: String,      // Unique identifier for the tournament
+    pub authority: Pubkey,          // Creator of the tournament
+    pub entry_fee: u64,             // Entry fee paid by every player
+    pub max_players: u8,            // Bracket size, a power of two
+    pub prize_percentages: [u8; 3], // Share of the pool for the champion, runner-up and semifinalists
+    pub players: Vec<Pubkey>,       // Registered players in seeding order
+    pub matches: Vec<BracketMatch>, // Bracket, first round first and the final last
+    pub status: TournamentStatus,   // Current tournament state
+    pub created_at: i64,            // Creation timestamp
+    pub bump: u8,                   // PDA bump
+    pub vault_bump: u8,             // Tournament vault PDA bump
+}
+
+impl Tournament {
+    /// Returns the account space needed for a bracket of `max_players`
+    pub fn space(max_players: u8) -> usize {
+        let max_players = max_players as usize;
+        8 // discriminator
+            + (4 + MAX_COMPETITION_ID_LEN) // tournament_id
+            + 32 // authority
+            + 8 // entry_fee
+            + 1 // max_players
+            + 3 // prize_percentages
+            + (4 + 32 * max_players) // players
+            + (4 + BracketMatch::LEN * max_players.saturating_sub(1)) // matches
+            + 1 // status
+            + 8 // created_at
+            + 1 // bump
+            + 1 // vault_bump
+    }
+
+    /// Returns the match the winner of `match_index` advances to, if it isn't the final
+    pub fn get_next_match(&self, match_index: usize) -> Option<usize> {
+        let next = self.max_players as usize / 2 + match_index / 2;
+        (next < self.matches.len()).then_some(next)
+    }
+
+    /// Places registered players into the first round of the bracket
+    pub fn seed_bracket(&mut self) {
+        for (i, pair) in self.players.chunks(2).enumerate() {
+            self.matches[i].player_a = pair[0];
+            self.matches[i].player_b = pair[1];
+        }
+    }
+
+    /// Gets a bracket match by index
+    pub fn get_match(&self, match_index: u8) -> Result<&BracketMatch> {
+        self.matches
+            .get(match_index as usize)
+            .ok_or(error!(WagerError::InvalidBracketMatch))
+    }
+
+    /// Settles a bracket match and moves its winner into the next round
+    pub fn record_match_winner(&mut self, match_index: u8, winner: Pubkey) -> Result<()> {
+        let bracket_match = self
+            .matches
+            .get_mut(match_index as usize)
+            .ok_or(error!(WagerError::InvalidBracketMatch))?;
+        require!(
+            bracket_match.winner == Pubkey::default()
+                && (winner == bracket_match.player_a || winner == bracket_match.player_b),
+            WagerError::InvalidBracketMatch
+        );
+        bracket_match.winner = winner;
+
+        if let Some(next) = self.get_next_match(match_index as usize) {
+            if match_index % 2 == 0 {
+                self.matches[next].player_a = winner;
+            } else {
+                self.matches[next].player_b = winner;
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the final match of the bracket
+    pub fn get_final(&self) -> &BracketMatch {
+        &self.matches[self.matches.len() - 1]
+    }
+
+    /// Gets the players knocked out in the semifinals
+    pub fn get_semifinal_losers(&self) -> Vec<Pubkey> {
+        if self.matches.len() < 3 {
+            return Vec::new();
+        }
+        self.matches[self.matches.len() - 3..self.matches.len() - 1]
+            .iter()
+            .filter_map(|m| m.get_loser())
+            .collect()
+    }
+}
+
+/// Largest number of teams in a league
+pub const MAX_LEAGUE_TEAMS: u8 = 16;
+
+/// Points awarded for a win in league standings
+pub const LEAGUE_WIN_POINTS: u16 = 3;
+
+/// Points awarded for a draw in league standings
+pub const LEAGUE_DRAW_POINTS: u16 = 1;
+
+/// Status of a league season
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Default)]
+pub enum LeagueStatus {
+    #[default]
+    Registration, // Waiting for teams to register
+    InProgress, // Fixtures are being played
+    Completed,  // Season has been closed and prizes distributed
+}
+
+/// Record of a team registered in a league
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct LeagueTeam {
+    pub captain: Pubkey, // Wallet that registered the team and receives its prizes
+    pub roster: Vec<Pubkey>, // Wallets allowed to play the team's fixtures
+    pub wins: u16,       // Matches won
+    pub losses: u16,     // Matches lost
+    pub draws: u16,      // Matches drawn
+    pub points: u16,     // League points
+}
+
+impl LeagueTeam {
+    pub const LEN: usize = 32 + (4 + 32 * MAX_TEAM_SIZE as usize) + 2 + 2 + 2 + 2;
+
+    /// Checks a roster can field a team without naming a wallet twice
+    pub fn validate_roster(roster: &[Pubkey]) -> Result<()> {
+        require!(
+            (1..=MAX_TEAM_SIZE as usize).contains(&roster.len()),
+            WagerError::InvalidPlayerCount
+        );
+        require!(
+            roster
+                .iter()
+                .enumerate()
+                .all(|(index, player)| !roster[..index].contains(player)),
+            WagerError::InvalidPlayer
+        );
+        Ok(())
+    }
+}
+
+/// Round-robin league season with its own prize pool
+#[account]
+pub struct League {
+    pub league_id: String,          // Unique identifier for the league
+    pub authority: Pubkey,          // Admin of the league
+    pub entry_fee: u64,             // Entry fee paid by every team
+    pub max_teams: u8,              // Largest number of teams that can register
+    pub prize_percentages: [u8; 3], // Share of the pool for the top three teams
+    pub teams: Vec<LeagueTeam>,     // Registered teams in registration order
+    pub standings: Vec<u8>,         // Team indexes ordered by rank
+    pub linked_fixtures: u128,      // Bit set of pairings that already have a game session
+    pub status: LeagueStatus,       // Current season state
+    pub created_at: i64,            // Creation timestamp
+    pub bump: u8,                   // PDA bump
+    pub vault_bump: u8,             // League vault PDA bump
+}
+
+impl League {
+    /// Returns the account space needed for a league of `max_teams`
+    pub fn space(max_teams: u8) -> usize {
+        let max_teams = max_teams as usize;
+        8 // discriminator
+            + (4 + MAX_COMPETITION_ID_LEN) // league_id
+            + 32 // authority
+            + 8 // entry_fee
+            + 1 // max_teams
+            + 3 // prize_percentages
+            + (4 + LeagueTeam::LEN * max_teams) // teams
+            + (4 + max_teams) // standings
+            + 16 // linked_fixtures
+            + 1 // status
+            + 8 // created_at
+            + 1 // bump
+            + 1 // vault_bump
+    }
+
+    /// Returns the bit tracking the fixture between two teams, in either order
+    fn fixture_bit(home: u8, away: u8) -> u128 {
+        let (low, high) = (home.min(away) as u32, home.max(away) as u32);
+        let n = MAX_LEAGUE_TEAMS as u32;
+        // Index into the upper triangle of the pairing matrix
+        1u128 << (low * (2 * n - low - 1) / 2 + (high - low - 1))
+    }
+
+    /// Reserves the fixture between two teams, which can only be played once
+    pub fn link_fixture(&mut self, home: u8, away: u8) -> Result<()> {
+        require!(
+            home != away
+                && (home as usize) < self.teams.len()
+                && (away as usize) < self.teams.len(),
+            WagerError::InvalidLeagueMatch
+        );
+
+        let bit = Self::fixture_bit(home, away);
+        require!(
+            self.linked_fixtures & bit == 0,
+            WagerError::InvalidLeagueMatch
+        );
+        self.linked_fixtures |= bit;
+        Ok(())
+    }
+
+    /// Frees a fixture that was called off before it was played
+    pub fn unlink_fixture(&mut self, home: u8, away: u8) {
+        self.linked_fixtures &= !Self::fixture_bit(home, away);
+    }
+
+    /// Records the result of a fixture, `winner` being `None` for a draw.
+    /// Standings are final once the season has been closed
+    pub fn record_result(&mut self, home: u8, away: u8, winner: Option<u8>) -> Result<()> {
+        require!(
+            self.status != LeagueStatus::Completed,
+            WagerError::InvalidGameState
+        );
+
+        match winner {
+            Some(winner) => {
+                let loser = if winner == home { away } else { home };
+                let winning_team = &mut self.teams[winner as usize];
+                winning_team.wins += 1;
+                winning_team.points += LEAGUE_WIN_POINTS;
+                self.teams[loser as usize].losses += 1;
+            }
+            None => {
+                for team in [home, away] {
+                    let drawing_team = &mut self.teams[team as usize];
+                    drawing_team.draws += 1;
+                    drawing_team.points += LEAGUE_DRAW_POINTS;
+                }
+            }
+        }
+
+        self.update_standings();
+        Ok(())
+    }
+
+    /// Ranks teams by points, then wins, keeping registration order for ties
+    pub fn update_standings(&mut self) {
+        let teams = &self.teams;
+        let mut standings: Vec<u8> = (0..teams.len() as u8).collect();
+        standings.sort_by(|a, b| {
+            let (a, b) = (&teams[*a as usize], &teams[*b as usize]);
+            b.points.cmp(&a.points).then(b.wins.cmp(&a.wins))
+        });
+        self.standings = standings;
+    }
+}
+
+/// Lifetime stats of a wallet across every game it has played
+#[account]
+#[derive(Default)]
+pub struct PlayerProfile {
+    pub player: Pubkey,     // Wallet the profile belongs to
+    pub games_played: u32,  // Games joined
+    pub wins: u32,          // Games won
+    pub losses: u32,        // Games lost
+    pub kills: u32,         // Kills recorded against other players
+    pub deaths: u32,        // Times killed by other players
+    pub total_wagered: u64, // Tokens staked in games and entry fees
+    pub total_won: u64,     // Tokens paid out from game pots
+    pub bump: u8,           // PDA bump
+    pub rating: u16,        // ELO skill rating
+}
+
+impl PlayerProfile {
+    pub const LEN: usize = 8 + 32 + 4 + 4 + 4 + 4 + 4 + 8 + 8 + 1 + 2;
+
+    /// Sets up a fresh profile the first time a wallet plays
+    pub fn init_if_new(&mut self, player: Pubkey, bump: u8) {
+        if self.player == Pubkey::default() {
+            self.player = player;
+            self.bump = bump;
+            self.rating = DEFAULT_RATING;
+        }
+    }
+
+    /// Moves the rating by `change` points without leaving the u16 range
+    pub fn apply_rating_change(&mut self, change: i32) {
+        self.rating = (self.rating as i32 + change).clamp(0, u16::MAX as i32) as u16;
+    }
+
+    /// Records joining a game with the given stake
+    pub fn record_join(&mut self, stake: u64) {
+        self.games_played = self.games_played.saturating_add(1);
+        self.total_wagered = self.total_wagered.saturating_add(stake);
+    }
+
+    /// Takes back a game recorded by `record_join` once its stake has been refunded
+    pub fn record_refund(&mut self, stake: u64) {
+        self.games_played = self.games_played.saturating_sub(1);
+        self.total_wagered = self.total_wagered.saturating_sub(stake);
+    }
+
+    /// Records the outcome of a settled game
+    pub fn record_result(&mut self, won: bool, amount_won: u64) {
+        if won {
+            self.wins = self.wins.saturating_add(1);
+        } else {
+            self.losses = self.losses.saturating_add(1);
+        }
+        self.total_won = self.total_won.saturating_add(amount_won);
+    }
+}
+
+/// Rating every player profile starts from
+pub const DEFAULT_RATING: u16 = 1200;
+
+/// K-factor used when a session doesn't set its own
+pub const DEFAULT_K_FACTOR: u8 = 32;
+
+/// Expected score of the higher rated side in basis points, for rating gaps in steps of 25
+const EXPECTED_SCORE_BPS: [u32; 33] = [
+    5000, 5359, 5715, 6063, 6401, 6725, 7034, 7325, 7597, 7850, 8083, 8296, 8490, 8666, 8823, 8965,
+    9091, 9203, 9302, 9390, 9468, 9536, 9595, 9648, 9693, 9733, 9768, 9799, 9825, 9848, 9868, 9886,
+    9901,
+];
+
+/// Returns the expected score in basis points of a side rated `rating` against `opponent_rating`
+pub fn expected_score_bps(rating: u16, opponent_rating: u16) -> u32 {
+    // Gaps past 800 points are treated as 800, interpolating between table steps
+    let gap = (rating as i32 - opponent_rating as i32)
+        .unsigned_abs()
+        .min(800);
+    let step = (gap / 25) as usize;
+    let lower = EXPECTED_SCORE_BPS[step];
+    let upper = EXPECTED_SCORE_BPS[(step + 1).min(EXPECTED_SCORE_BPS.len() - 1)];
+    let score = lower + (upper - lower) * (gap % 25) / 25;
+
+    if rating >= opponent_rating {
+        score
+    } else {
+        10_000 - score
+    }
+}
+
+/// Returns the ELO rating change of a side rated `rating` after playing `opponent_rating`
+pub fn rating_change(rating: u16, opponent_rating: u16, won: bool, k_factor: u8) -> i32 {
+    let expected = expected_score_bps(rating, opponent_rating) as i32;
+    let actual = if won { 10_000 } else { 0 };
+    k_factor as i32 * (actual - expected) / 10_000
+}
+
+/// Returns the average of `ratings`, or None if there are none
+pub fn average_rating(ratings: impl Iterator<Item = u16>) -> Option<u16> {
+    let (total, count) = ratings.fold((0u32, 0u32), |(total, count), rating| {
+        (total + rating as u32, count + 1)
+    });
+    (count > 0).then(|| (total / count) as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a session with every variable-length field at its bound
+    fn full_game_session() -> GameSession {
+        let wallets = |count: usize| vec![Pubkey::new_unique(); count];
+        let full_allowlist = || Some(Allowlist::Wallets(wallets(MAX_ALLOWLIST_WALLETS)));
+
+        GameSession {
+            version: GAME_SESSION_VERSION,
+            session_hash: [1; 32],
+            session_id: Some("x".repeat(MAX_SESSION_ID_LEN)),
+            authority: Pubkey::new_unique(),
+            session_bet: u64::MAX,
+            game_mode: GameMode::FreeForAll {
+                max_players: MAX_FREE_FOR_ALL_PLAYERS,
+                payout_percentages: [50, 30, 20],
+            },
+            team_size: MAX_TEAM_SIZE,
+            teams: vec![Team::new(MAX_TEAM_SIZE as usize, u64::MAX); MAX_TEAMS as usize],
+            status: GameStatus::InProgress,
+            created_at: i64::MAX,
+            bump: 255,
+            vault_bump: 255,
+            vault_token_bump: 255,
+            free_for_all_players: wallets(MAX_FREE_FOR_ALL_PLAYERS as usize),
+            series: Some(Series {
+                best_of: MAX_SERIES_GAMES,
+                team_wins: vec![0; MAX_TEAMS as usize],
+                game_winners: vec![0; MAX_SERIES_GAMES as usize],
+            }),
+            tournament_match: Some(TournamentMatchLink {
+                tournament: Pubkey::new_unique(),
+                match_index: 0,
+            }),
+            league_match: Some(LeagueMatchLink {
+                league: Pubkey::new_unique(),
+                home: 0,
+                away: 1,
+            }),
+            k_factor: DEFAULT_K_FACTOR,
+            rating_band: Some(RatingBand {
+                min_rating: 0,
+                max_rating: u16::MAX,
+            }),
+            linked_wallets: vec![wallets(MAX_LINKED_GROUP_SIZE); MAX_LINKED_WALLET_GROUPS],
+            allowlist: full_allowlist(),
+            team_allowlists: vec![full_allowlist(); MAX_TEAMS as usize],
+            invite_key: Some(Pubkey::new_unique()),
+            token_gate: Some(TokenGate {
+                mint: Pubkey::new_unique(),
+                min_balance: u64::MAX,
+            }),
+            match_log: Some(Pubkey::new_unique()),
+            replay: Some(ReplayCommitment {
+                root: [3; 32],
+                event_count: u32::MAX,
+            }),
+            reserved: [0; GAME_SESSION_RESERVED],
+        }
+    }
+
+    #[test]
+    fn full_game_session_fits_allocation() {
+        let mut data = Vec::new();
+        full_game_session().try_serialize(&mut data).unwrap();
+        assert!(
+            data.len() <= GameSession::LEN,
+            "serialized {} bytes into {} allocated",
+            data.len(),
+            GameSession::LEN
+        );
+    }
+
+    #[test]
+    fn free_for_all_mode_validates_player_count() {
+        let free_for_all = |max_players| GameMode::FreeForAll {
+            max_players,
+            payout_percentages: [100, 0, 0],
+        };
+
+        assert!(free_for_all(MIN_FREE_FOR_ALL_PLAYERS)
+            .validate(0, 0)
+            .is_ok());
+        assert!(free_for_all(MAX_FREE_FOR_ALL_PLAYERS)
+            .validate(0, 0)
+            .is_ok());
+        for (max_players, team_size, team_count) in [
+            (MIN_FREE_FOR_ALL_PLAYERS - 1, 0, 0),
+            (MAX_FREE_FOR_ALL_PLAYERS + 1, 0, 0),
+            (MIN_FREE_FOR_ALL_PLAYERS, 1, 0),
+            (MIN_FREE_FOR_ALL_PLAYERS, 0, 2),
+        ] {
+            assert_eq!(
+                free_for_all(max_players).validate(team_size, team_count),
+                Err(WagerError::InvalidPlayerCount.into())
+            );
+        }
+    }
+
+    #[test]
+    fn payout_percentages_rank_places_and_add_up() {
+        assert!(validate_payout_percentages(&[100, 0, 0], 2).is_ok());
+        assert!(validate_payout_percentages(&[50, 30, 20], 3).is_ok());
+        assert!(validate_payout_percentages(&[40, 40, 20], 3).is_ok());
+        for (payout_percentages, player_count) in [
+            ([50, 30, 10], 3), // Leaves part of the pot unpaid
+            ([30, 50, 20], 3), // Pays second place more than first
+            ([50, 30, 20], 2), // Pays more places than there are players
+            ([0, 0, 100], 16), // Pays only third place
+        ] {
+            assert_eq!(
+                validate_payout_percentages(&payout_percentages, player_count),
+                Err(WagerError::InvalidPayoutPercentages.into())
+            );
+        }
+    }
+
+    #[test]
+    fn expected_score_follows_rating_gap() {
+        assert_eq!(expected_score_bps(1200, 1200), 5000);
+        assert_eq!(expected_score_bps(1600, 1200), 9091);
+        // Gaps between table steps are interpolated, and capped at 800 points
+        assert_eq!(expected_score_bps(1212, 1200), 5172);
+        assert_eq!(expected_score_bps(2400, 1200), 9901);
+        for (rating, opponent_rating) in [(1200, 1212), (1000, 1600), (0, u16::MAX)] {
+            assert_eq!(
+                expected_score_bps(rating, opponent_rating)
+                    + expected_score_bps(opponent_rating, rating),
+                10_000
+            );
+        }
+    }
+
+    #[test]
+    fn rating_change_rewards_upsets() {
+        assert_eq!(rating_change(1200, 1200, true, DEFAULT_K_FACTOR), 16);
+        assert_eq!(rating_change(1200, 1200, false, DEFAULT_K_FACTOR), -16);
+        assert_eq!(rating_change(1600, 1200, true, DEFAULT_K_FACTOR), 2);
+        assert_eq!(rating_change(1200, 1600, true, DEFAULT_K_FACTOR), 29);
+        assert_eq!(rating_change(1200, 1200, true, 64), 32);
+        assert_eq!(rating_change(1200, 1600, true, 0), 0);
+    }
+
+    #[test]
+    fn average_rating_of_teams() {
+        assert_eq!(average_rating([1000, 1500].into_iter()), Some(1250));
+        assert_eq!(average_rating([u16::MAX; 4].into_iter()), Some(u16::MAX));
+        assert_eq!(average_rating(std::iter::empty()), None);
+    }
+
+    #[test]
+    fn stakes_are_bounded_per_mint() {
+        let (mint, min_bet, max_bet) = BET_LIMITS[0];
+        assert!(validate_stake(mint, min_bet).is_ok());
+        assert!(validate_stake(mint, max_bet).is_ok());
+        for amount in [0, min_bet - 1, max_bet + 1] {
+            assert_eq!(
+                validate_stake(mint, amount),
+                Err(WagerError::InvalidBetAmount.into())
+            );
+        }
+        assert_eq!(
+            validate_stake(Pubkey::new_unique(), min_bet),
+            Err(WagerError::InvalidMint.into())
+        );
+
+        // Practice games are the only ones without a stake
+        assert!(GameMode::Practice.validate_bet(mint, 0).is_ok());
+        assert_eq!(
+            GameMode::Practice.validate_bet(mint, min_bet),
+            Err(WagerError::InvalidBetAmount.into())
+        );
+        assert_eq!(
+            GameMode::WinnerTakesAll.validate_bet(mint, 0),
+            Err(WagerError::InvalidBetAmount.into())
+        );
+    }
+
+    #[test]
+    fn session_ids_are_short_and_url_safe() {
+        for session_id in ["a", "final_2024-b", &"x".repeat(MAX_SESSION_ID_LEN)] {
+            assert!(validate_session_id(session_id).is_ok(), "{session_id}");
+        }
+        for session_id in [
+            "",
+            &"x".repeat(MAX_SESSION_ID_LEN + 1),
+            "with space",
+            "slash/es",
+            "ümlaut",
+        ] {
+            assert_eq!(
+                validate_session_id(session_id),
+                Err(WagerError::InvalidSessionId.into()),
+                "{session_id}"
+            );
+        }
+    }
+
+    #[test]
+    fn game_session_can_be_created_by_cpi() {
+        // Accounts created through a CPI can't start larger than this
+        const { assert!(GameSession::LEN <= 10_240) };
+    }
 }
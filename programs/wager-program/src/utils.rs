@@ -1,4 +1,7 @@
+use crate::{errors::WagerError, state::PlayerProfile};
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount, Transfer};
 use anchor_spl::token::{self, TokenAccount, Transfer as SplTransfer};
 
 pub fn transfer_spl_tokens<'info>(
@@ -21,3 +24,144 @@ pub fn transfer_spl_tokens<'info>(
 
     Ok(())
 }
+
+/// Splits off the trailing player profile accounts from `remaining_accounts`, one per
+/// player in `players` order, returning the leading accounts alongside the loaded profiles
+pub fn split_player_profiles<'info>(
+    remaining_accounts: &'info [AccountInfo<'info>],
+    players: &[Pubkey],
+) -> Result<(
+    &'info [AccountInfo<'info>],
+    Vec<Account<'info, PlayerProfile>>,
+)> {
+    require!(
+        remaining_accounts.len() >= players.len(),
+        WagerError::InvalidRemainingAccounts
+    );
+    let (leading_accounts, profile_accounts) =
+        remaining_accounts.split_at(remaining_accounts.len() - players.len());
+
+    let mut profiles = Vec::with_capacity(players.len());
+    for (player, profile_account) in players.iter().zip(profile_accounts) {
+        // Profiles can only be created at their PDA, so a matching owner is enough
+        let profile = Account::<PlayerProfile>::try_from(profile_account)?;
+        require_keys_eq!(profile.player, *player, WagerError::InvalidPlayerProfile);
+        profiles.push(profile);
+    }
+
+    Ok((leading_accounts, profiles))
+}
+
+/// Creates the profile of `player` at its PDA unless it already exists, for players
+/// seated without going through a join instruction
+pub fn init_player_profile<'info>(
+    profile: &AccountInfo<'info>,
+    player: Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let (address, bump) =
+        Pubkey::find_program_address(&[b"player_profile", player.as_ref()], &crate::ID);
+    require_keys_eq!(profile.key(), address, WagerError::InvalidPlayerProfile);
+    if profile.owner == &crate::ID {
+        return Ok(());
+    }
+
+    let seeds: &[&[u8]] = &[b"player_profile", player.as_ref(), &[bump]];
+    let space = PlayerProfile::LEN as u64;
+    let rent = Rent::get()?.minimum_balance(PlayerProfile::LEN);
+    if profile.lamports() == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                CreateAccount {
+                    from: payer.clone(),
+                    to: profile.clone(),
+                },
+                &[seeds],
+            ),
+            rent,
+            space,
+            &crate::ID,
+        )?;
+    } else {
+        // Lamports sent to the address ahead of time would make create_account fail
+        let top_up = rent.saturating_sub(profile.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    Transfer {
+                        from: payer.clone(),
+                        to: profile.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                Allocate {
+                    account_to_allocate: profile.clone(),
+                },
+                &[seeds],
+            ),
+            space,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                Assign {
+                    account_to_assign: profile.clone(),
+                },
+                &[seeds],
+            ),
+            &crate::ID,
+        )?;
+    }
+
+    let mut new_profile = PlayerProfile::default();
+    new_profile.init_if_new(player, bump);
+    new_profile.try_serialize(&mut &mut profile.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+/// Writes updated player profiles back to their accounts
+pub fn save_player_profiles(profiles: &[Account<PlayerProfile>]) -> Result<()> {
+    for profile in profiles {
+        profile.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
+/// Closes an account owned by this program that can't be loaded as a typed account,
+/// returning its rent to `destination`
+pub fn close_program_account<'info>(
+    account: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    let lamports = account.lamports();
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(error!(WagerError::ArithmeticError))?;
+    **account.try_borrow_mut_lamports()? = 0;
+
+    account.assign(&anchor_lang::system_program::ID);
+    account.realloc(0, false)?;
+    Ok(())
+}
+
+/// Checks `leaf` is in the Merkle tree with `root`, hashing each pair of
+/// nodes in sorted order so proofs don't need to record sides
+pub fn verify_merkle_proof(leaf: [u8; 32], proof: &[[u8; 32]], root: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+    computed == root
+}
@@ -0,0 +1,173 @@
+//! Fixtures shared by the integration tests
+#![allow(dead_code)]
+
+pub mod svm;
+
+pub use svm::*;
+
+use anchor_lang::{prelude::*, solana_program::program_error::ProgramError};
+use wager_client::{accounts, instructions, pda};
+use wager_program::{errors::WagerError, state::*};
+
+/// Bet every player stakes unless a test says otherwise
+pub const BET: u64 = 10_000_000;
+
+/// Wager tokens every player starts with
+pub const PLAYER_TOKENS: u64 = 1_000_000_000;
+
+/// Returns the error a failed instruction reports for `error`
+pub fn wager_error(error: WagerError) -> ProgramError {
+    ProgramError::Custom(error.into())
+}
+
+/// Asserts an instruction failed with `error`
+#[track_caller]
+pub fn assert_wager_error(result: std::result::Result<(), ProgramError>, error: WagerError) {
+    assert_eq!(result, Err(wager_error(error)));
+}
+
+impl Svm {
+    /// Creates a session hashed from `session_id` as `game_server`, returning its hash
+    pub fn create_session(
+        &mut self,
+        game_server: &Pubkey,
+        session_id: &str,
+        game_mode: GameMode,
+        team_size: u8,
+        team_count: u8,
+        options: SessionOptions,
+    ) -> [u8; 32] {
+        let session_hash = pda::session_hash(session_id);
+        let bet = if game_mode == GameMode::Practice {
+            0
+        } else {
+            BET
+        };
+        self.process(&instructions::create_game_session(
+            game_server,
+            session_hash,
+            bet,
+            game_mode,
+            team_size,
+            team_count,
+            options,
+        ))
+        .unwrap();
+        session_hash
+    }
+
+    /// Creates a winner takes all session of `team_count` teams of `team_size`
+    pub fn create_team_session(
+        &mut self,
+        game_server: &Pubkey,
+        session_id: &str,
+        team_size: u8,
+        team_count: u8,
+    ) -> [u8; 32] {
+        self.create_session(
+            game_server,
+            session_id,
+            GameMode::WinnerTakesAll,
+            team_size,
+            team_count,
+            SessionOptions::default(),
+        )
+    }
+
+    pub fn join(
+        &mut self,
+        player: &Pubkey,
+        game_server: &Pubkey,
+        session_hash: [u8; 32],
+        team: u8,
+    ) -> std::result::Result<(), ProgramError> {
+        self.process(&instructions::join_user(
+            player,
+            game_server,
+            session_hash,
+            team,
+            JoinCredentials::default(),
+            None,
+            None,
+        ))
+    }
+
+    /// Creates a funded player for every seat and joins them, team by team
+    pub fn fill_teams(&mut self, game_server: &Pubkey, session_hash: [u8; 32]) -> Vec<Vec<Pubkey>> {
+        let teams: Vec<usize> = self
+            .session(session_hash)
+            .teams
+            .iter()
+            .map(|team| team.players.len())
+            .collect();
+        teams
+            .into_iter()
+            .enumerate()
+            .map(|(team, size)| {
+                (0..size)
+                    .map(|_| {
+                        let player = self.create_player(PLAYER_TOKENS);
+                        self.join(&player, game_server, session_hash, team as u8)
+                            .unwrap();
+                        player
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn record_kill(
+        &mut self,
+        game_server: &Pubkey,
+        session_hash: [u8; 32],
+        (killer_team, killer): (u8, Pubkey),
+        (victim_team, victim): (u8, Pubkey),
+    ) -> std::result::Result<(), ProgramError> {
+        let match_log = self.session(session_hash).match_log.is_some();
+        self.process(&instructions::record_kill(
+            game_server,
+            session_hash,
+            killer_team,
+            killer,
+            victim_team,
+            victim,
+            match_log,
+        ))
+    }
+
+    pub fn session(&self, session_hash: [u8; 32]) -> GameSession {
+        self.get(&pda::find_game_session(&session_hash).0)
+    }
+
+    pub fn match_state(&self, session_hash: [u8; 32]) -> MatchState {
+        let key = pda::find_match_state(&session_hash).0;
+        accounts::decode_match_state(&self.account(&key).unwrap().data).unwrap()
+    }
+
+    /// Rewrites a session's match state, for reaching states too slow to play out
+    pub fn update_match_state(
+        &mut self,
+        session_hash: [u8; 32],
+        update: impl FnOnce(&mut MatchState),
+    ) {
+        let key = pda::find_match_state(&session_hash).0;
+        let mut match_state = self.match_state(session_hash);
+        update(&mut match_state);
+        let mut account = self.account(&key).unwrap().clone();
+        account.data[8..8 + std::mem::size_of::<MatchState>()]
+            .copy_from_slice(bytemuck::bytes_of(&match_state));
+        self.set_account(key, account);
+    }
+
+    pub fn profile(&self, player: &Pubkey) -> PlayerProfile {
+        self.get(&pda::find_player_profile(player).0)
+    }
+
+    pub fn player_balance(&self, player: &Pubkey) -> u64 {
+        self.token_balance(&pda::player_token_account(player))
+    }
+
+    pub fn vault_balance(&self, session_hash: [u8; 32]) -> u64 {
+        self.token_balance(&pda::vault_token_account(&session_hash))
+    }
+}
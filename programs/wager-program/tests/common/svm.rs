@@ -0,0 +1,741 @@
+//! In-process runtime the integration tests run the wager program on. The
+//! program, the system program, SPL Token and the associated token program are
+//! executed natively against an in-memory account store, with cross-program
+//! invocations and sysvars served through solana-program's syscall stubs, so
+//! the suite needs neither a validator nor the BPF toolchain.
+//!
+//! Every instruction, including each cross-program invocation, is held to the
+//! runtime's account rules: only the owner may change an account's data or
+//! owner or debit its lamports, read-only accounts can't change at all,
+//! lamports are conserved and written accounts must stay rent exempt. Native
+//! code can't be metered, so the compute meter charges the runtime's cost of
+//! every syscall the harness serves, which is a lower bound on what the
+//! program uses on chain. Breaking any of these rules panics, failing the test
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        bpf_loader_upgradeable,
+        entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS},
+        instruction::Instruction,
+        program_option::COption,
+        program_pack::Pack,
+        program_stubs::{set_syscall_stubs, SyscallStubs},
+        system_instruction::{SystemError, SystemInstruction},
+        sysvar,
+    },
+    system_program, AccountDeserialize, AccountSerialize, Discriminator,
+};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address, spl_associated_token_account},
+    token::{self, spl_token},
+};
+use std::{cell::RefCell, collections::HashMap, sync::Once};
+use wager_program::{ID as PROGRAM_ID, TOKEN_ID};
+
+/// Lamports every wallet created by the tests starts with
+pub const WALLET_LAMPORTS: u64 = 100_000_000_000;
+
+/// Unix timestamp the runtime's clock reports
+pub const CLOCK_TIMESTAMP: i64 = 1_700_000_000;
+
+/// Slot the runtime's clock reports
+pub const CLOCK_SLOT: u64 = 42;
+
+/// Compute units an instruction may consume unless a test sets another limit
+pub const COMPUTE_UNIT_LIMIT: u64 = 200_000;
+
+/// Deepest the runtime lets cross-program invocations nest, counting the top-level program
+const MAX_INVOKE_STACK_HEIGHT: usize = 5;
+
+// Costs the runtime charges for the syscalls the harness serves
+const INVOKE_UNITS: u64 = 1_000;
+const CPI_BYTES_PER_UNIT: u64 = 250;
+const SYSCALL_BASE_COST: u64 = 100;
+const SYSVAR_BASE_COST: u64 = 100;
+const SYSTEM_PROGRAM_UNITS: u64 = 150;
+
+/// An account held by the runtime between instructions
+#[derive(Clone, Default)]
+pub struct StoredAccount {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+/// State of an account when the program now holding it was invoked
+#[derive(Clone)]
+struct AccountSnapshot {
+    key: Pubkey,
+    invoked_lamports: u64, // Balance when the program was invoked, kept through refreshes
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+    is_writable: bool,
+}
+
+impl AccountSnapshot {
+    fn take(info: &AccountInfo) -> Self {
+        Self {
+            key: *info.key,
+            invoked_lamports: info.lamports(),
+            lamports: info.lamports(),
+            data: info.data.borrow().to_vec(),
+            owner: *info.owner,
+            is_writable: info.is_writable,
+        }
+    }
+}
+
+/// State of the instruction being executed on the current thread
+#[derive(Default)]
+struct Invocation {
+    programs: Vec<Pubkey>,                  // Call stack, the running program last
+    snapshots: Vec<Vec<AccountSnapshot>>,   // Accounts of each program on the call stack
+    return_data: Option<(Pubkey, Vec<u8>)>, // Data set by the last program that returned some
+    events: Vec<Vec<u8>>,                   // Data logged with sol_log_data, like Anchor events
+    compute_units: u64,                     // Units consumed so far
+    compute_limit: u64,                     // Units the instruction may consume
+}
+
+/// Charges `units` to the running instruction's compute meter
+fn consume(units: u64) {
+    INVOCATION.with(|invocation| {
+        let mut invocation = invocation.borrow_mut();
+        invocation.compute_units += units;
+        assert!(
+            invocation.compute_units <= invocation.compute_limit,
+            "instruction exceeded its limit of {} compute units",
+            invocation.compute_limit
+        );
+    });
+}
+
+thread_local! {
+    static INVOCATION: RefCell<Invocation> = RefCell::new(Invocation::default());
+}
+
+/// Syscalls of the programs running in the runtime. The stubs are process-wide,
+/// so everything they touch lives in the calling thread's `Invocation`
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let caller = INVOCATION
+            .with(|invocation| invocation.borrow().programs.last().copied())
+            .ok_or(ProgramError::InvalidArgument)?;
+        consume(INVOKE_UNITS + instruction.data.len() as u64 / CPI_BYTES_PER_UNIT);
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+
+        let mut callee_accounts = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            let account = account_infos
+                .iter()
+                .find(|account| *account.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+            // The callee can't be handed privileges the caller doesn't hold
+            let is_signer = account.is_signer || signers.contains(account.key);
+            if meta.is_signer && !is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if meta.is_writable && !account.is_writable {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            let mut callee_account = account.clone();
+            callee_account.is_signer = meta.is_signer;
+            callee_account.is_writable = meta.is_writable;
+            callee_accounts.push(callee_account);
+            consume(account.data_len() as u64 / CPI_BYTES_PER_UNIT);
+        }
+
+        // The caller's own changes are checked before the callee builds on them
+        INVOCATION.with(|invocation| {
+            let invocation = invocation.borrow();
+            let snapshots = invocation.snapshots.last().expect("caller has no snapshot");
+            for account in account_infos {
+                if let Some(pre) = snapshots.iter().find(|pre| pre.key == *account.key) {
+                    verify_account(&caller, pre, account);
+                }
+            }
+        });
+
+        invoke(&instruction.program_id, &callee_accounts, &instruction.data)
+    }
+
+    fn sol_log(&self, message: &str) {
+        consume(SYSCALL_BASE_COST.max(message.len() as u64));
+        println!("{message}");
+    }
+
+    fn sol_remaining_compute_units(&self) -> u64 {
+        INVOCATION.with(|invocation| {
+            let invocation = invocation.borrow();
+            invocation.compute_limit - invocation.compute_units
+        })
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        consume(SYSVAR_BASE_COST + std::mem::size_of::<Clock>() as u64);
+        let clock = Clock {
+            slot: CLOCK_SLOT,
+            unix_timestamp: CLOCK_TIMESTAMP,
+            ..Clock::default()
+        };
+        unsafe { std::ptr::write(var_addr as *mut Clock, clock) };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        consume(SYSVAR_BASE_COST + std::mem::size_of::<Rent>() as u64);
+        unsafe { std::ptr::write(var_addr as *mut Rent, Rent::default()) };
+        SUCCESS
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        let return_data = INVOCATION.with(|invocation| invocation.borrow().return_data.clone());
+        let len = return_data.as_ref().map_or(0, |(_, data)| data.len()) + 32;
+        consume(SYSCALL_BASE_COST + len as u64 / CPI_BYTES_PER_UNIT);
+        return_data
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        consume(SYSCALL_BASE_COST + data.len() as u64 / CPI_BYTES_PER_UNIT);
+        INVOCATION.with(|invocation| {
+            let mut invocation = invocation.borrow_mut();
+            let program = invocation.programs.last().copied().unwrap_or_default();
+            invocation.return_data = (!data.is_empty()).then(|| (program, data.to_vec()));
+        });
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let bytes: usize = fields.iter().map(|field| field.len()).sum();
+        consume(SYSCALL_BASE_COST * (1 + fields.len() as u64) + bytes as u64);
+        INVOCATION.with(|invocation| {
+            let mut invocation = invocation.borrow_mut();
+            invocation
+                .events
+                .extend(fields.iter().map(|field| field.to_vec()));
+        });
+    }
+}
+
+/// Runs an instruction of one of the runtime's programs on already loaded accounts,
+/// checking the changes it made against the runtime's account rules
+fn invoke(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    // Each account is checked once, with the privileges of all its infos combined
+    let mut snapshots: Vec<AccountSnapshot> = Vec::new();
+    for account in accounts {
+        match snapshots.iter_mut().find(|pre| pre.key == *account.key) {
+            Some(pre) => pre.is_writable |= account.is_writable,
+            None => snapshots.push(AccountSnapshot::take(account)),
+        }
+    }
+    INVOCATION.with(|invocation| {
+        let mut invocation = invocation.borrow_mut();
+        invocation.programs.push(*program_id);
+        invocation.snapshots.push(snapshots);
+        assert!(
+            invocation.programs.len() <= MAX_INVOKE_STACK_HEIGHT,
+            "cross-program invocations nested deeper than the runtime allows"
+        );
+    });
+
+    let result = if *program_id == PROGRAM_ID {
+        // Anchor ties the slice to the accounts' lifetime; both outlive the call,
+        // which is all the program relies on
+        let accounts: &[AccountInfo] = unsafe { std::mem::transmute(accounts) };
+        wager_program::entry(program_id, accounts, data)
+    } else if *program_id == system_program::ID {
+        consume(SYSTEM_PROGRAM_UNITS);
+        process_system_instruction(accounts, data)
+    } else if *program_id == token::ID {
+        spl_token::processor::Processor::process(program_id, accounts, data)
+    } else if *program_id == associated_token::ID {
+        spl_associated_token_account::processor::process_instruction(program_id, accounts, data)
+    } else {
+        Err(ProgramError::IncorrectProgramId)
+    };
+
+    let snapshots = INVOCATION.with(|invocation| {
+        let mut invocation = invocation.borrow_mut();
+        invocation.programs.pop();
+        invocation.snapshots.pop().unwrap()
+    });
+    if result.is_ok() {
+        verify_instruction(program_id, &snapshots, accounts);
+
+        // The caller continues from the state the callee left its accounts in
+        INVOCATION.with(|invocation| {
+            if let Some(caller) = invocation.borrow_mut().snapshots.last_mut() {
+                for callee in &snapshots {
+                    let post = accounts
+                        .iter()
+                        .find(|info| *info.key == callee.key)
+                        .unwrap();
+                    if let Some(pre) = caller.iter_mut().find(|pre| pre.key == callee.key) {
+                        pre.lamports = pre.lamports + post.lamports() - callee.invoked_lamports;
+                        pre.data = post.data.borrow().to_vec();
+                        pre.owner = *post.owner;
+                    }
+                }
+            }
+        });
+    }
+    result
+}
+
+/// Checks the changes `program_id` made to its accounts and that it conserved lamports
+fn verify_instruction(
+    program_id: &Pubkey,
+    snapshots: &[AccountSnapshot],
+    accounts: &[AccountInfo],
+) {
+    let mut pre_lamports: u128 = 0;
+    let mut post_lamports: u128 = 0;
+    for pre in snapshots {
+        let post = accounts.iter().find(|info| *info.key == pre.key).unwrap();
+        verify_account(program_id, pre, post);
+        pre_lamports += pre.lamports as u128;
+        post_lamports += post.lamports() as u128;
+    }
+    assert_eq!(
+        pre_lamports, post_lamports,
+        "instruction of {program_id} created or destroyed lamports"
+    );
+}
+
+/// Checks a single account was only changed the way the runtime lets `program_id` change it
+fn verify_account(program_id: &Pubkey, pre: &AccountSnapshot, post: &AccountInfo) {
+    let key = pre.key;
+    let data = post.data.borrow();
+    let data_changed = pre.data[..] != data[..];
+    let owner_changed = pre.owner != *post.owner;
+    if !pre.is_writable {
+        assert!(
+            !data_changed && !owner_changed && pre.lamports == post.lamports(),
+            "{program_id} changed read-only account {key}"
+        );
+        return;
+    }
+    if owner_changed {
+        assert!(
+            pre.owner == *program_id && data.iter().all(|byte| *byte == 0),
+            "{program_id} reassigned account {key} it doesn't own or that still holds data"
+        );
+    }
+    if data_changed {
+        assert!(
+            pre.owner == *program_id,
+            "{program_id} wrote to the data of account {key} it doesn't own"
+        );
+    }
+    if post.lamports() < pre.lamports {
+        assert!(
+            pre.owner == *program_id,
+            "{program_id} debited account {key} it doesn't own"
+        );
+    }
+}
+
+/// The parts of the system program Anchor's account constraints rely on
+fn process_system_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let instruction: SystemInstruction =
+        bincode::deserialize(data).map_err(|_| ProgramError::InvalidInstructionData)?;
+    match (instruction, accounts) {
+        (
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            },
+            [from, to, ..],
+        ) => {
+            if to.lamports() > 0 {
+                return Err(ProgramError::Custom(
+                    SystemError::AccountAlreadyInUse as u32,
+                ));
+            }
+            transfer_lamports(from, to, lamports)?;
+            allocate(to, space)?;
+            assign(to, &owner)
+        }
+        (SystemInstruction::Transfer { lamports }, [from, to, ..]) => {
+            if !from.data_is_empty() {
+                return Err(ProgramError::InvalidArgument);
+            }
+            transfer_lamports(from, to, lamports)
+        }
+        (SystemInstruction::Allocate { space }, [account, ..]) => allocate(account, space),
+        (SystemInstruction::Assign { owner }, [account, ..]) => assign(account, &owner),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn transfer_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let remaining = from
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(ProgramError::Custom(
+            SystemError::ResultWithNegativeLamports as u32,
+        ))?;
+    **from.try_borrow_mut_lamports()? = remaining;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if !account.data_is_empty() || *account.owner != system_program::ID {
+        return Err(ProgramError::Custom(
+            SystemError::AccountAlreadyInUse as u32,
+        ));
+    }
+    account.realloc(space as usize, true)
+}
+
+fn assign(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    account.assign(owner);
+    Ok(())
+}
+
+/// Account key as the runtime serializes it, preceded by the length the
+/// account's data had when the instruction started
+#[repr(C)]
+struct SerializedKey {
+    original_data_len: u32,
+    key: Pubkey,
+}
+
+/// An account loaded for an instruction, laid out the way the runtime
+/// serializes it so `AccountInfo::realloc` can grow and shrink it in place
+struct LoadedAccount {
+    key: Box<SerializedKey>,
+    lamports: Box<u64>,
+    data: Vec<u64>, // Data length, the data, then room for it to grow
+    data_len: usize,
+    owner: Box<Pubkey>,
+    executable: bool,
+}
+
+impl LoadedAccount {
+    fn new(key: Pubkey, account: StoredAccount) -> Self {
+        let data_len = account.data.len();
+        let mut data = vec![0u64; 1 + (data_len + MAX_PERMITTED_DATA_INCREASE).div_ceil(8)];
+        data[0] = data_len as u64;
+        let mut loaded = Self {
+            key: Box::new(SerializedKey {
+                original_data_len: data_len as u32,
+                key,
+            }),
+            lamports: Box::new(account.lamports),
+            data,
+            data_len,
+            owner: Box::new(account.owner),
+            executable: account.executable,
+        };
+        loaded.data_mut().copy_from_slice(&account.data);
+        loaded
+    }
+
+    fn data_mut<'a>(&mut self) -> &'a mut [u8] {
+        // The data sits after its length, with the spare capacity behind it
+        // left for `AccountInfo::realloc`
+        unsafe {
+            std::slice::from_raw_parts_mut(
+                (self.data.as_mut_ptr() as *mut u8).add(8),
+                self.data_len,
+            )
+        }
+    }
+
+    fn info(&mut self, is_signer: bool, is_writable: bool) -> AccountInfo<'_> {
+        let data = self.data_mut();
+        AccountInfo::new(
+            &self.key.key,
+            is_signer,
+            is_writable,
+            &mut self.lamports,
+            data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
+
+/// In-memory ledger of accounts that instructions are processed against
+pub struct Svm {
+    accounts: HashMap<Pubkey, StoredAccount>,
+    compute_limit: u64,
+}
+
+impl Svm {
+    /// Creates a ledger holding the programs, the rent sysvar and the wager token's mint
+    pub fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let mut svm = Self {
+            accounts: HashMap::new(),
+            compute_limit: COMPUTE_UNIT_LIMIT,
+        };
+        for program in [
+            PROGRAM_ID,
+            system_program::ID,
+            token::ID,
+            associated_token::ID,
+        ] {
+            svm.set_account(
+                program,
+                StoredAccount {
+                    lamports: 1,
+                    data: Vec::new(),
+                    owner: bpf_loader_upgradeable::ID,
+                    executable: true,
+                },
+            );
+        }
+        svm.set_account(
+            sysvar::rent::ID,
+            StoredAccount {
+                lamports: 1,
+                data: bincode::serialize(&Rent::default()).unwrap(),
+                owner: sysvar::ID,
+                executable: false,
+            },
+        );
+        svm.create_mint(TOKEN_ID);
+        svm
+    }
+
+    /// Sets the compute units each following instruction may consume
+    pub fn set_compute_limit(&mut self, compute_limit: u64) {
+        self.compute_limit = compute_limit;
+    }
+
+    /// Returns the compute units the last processed instruction was charged
+    pub fn compute_units(&self) -> u64 {
+        INVOCATION.with(|invocation| invocation.borrow().compute_units)
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&StoredAccount> {
+        self.accounts.get(key)
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: StoredAccount) {
+        self.accounts.insert(key, account);
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.account(key).map_or(0, |account| account.lamports)
+    }
+
+    pub fn airdrop(&mut self, key: &Pubkey, lamports: u64) {
+        self.accounts.entry(*key).or_default().lamports += lamports;
+    }
+
+    /// Creates a wallet funded with WALLET_LAMPORTS
+    pub fn create_wallet(&mut self) -> Pubkey {
+        let wallet = Pubkey::new_unique();
+        self.airdrop(&wallet, WALLET_LAMPORTS);
+        wallet
+    }
+
+    /// Stores a rent-exempt account owned by `owner`
+    pub fn set_data(&mut self, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        let lamports = Rent::default().minimum_balance(data.len());
+        self.set_account(
+            key,
+            StoredAccount {
+                lamports,
+                data,
+                owner,
+                executable: false,
+            },
+        );
+    }
+
+    /// Stores an initialized mint
+    pub fn create_mint(&mut self, mint: Pubkey) {
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: COption::Some(Pubkey::new_unique()),
+            supply: u64::MAX,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        self.set_data(mint, token::ID, data);
+    }
+
+    /// Stores an initialized token account holding `amount` of `mint`
+    pub fn create_token_account(
+        &mut self,
+        address: Pubkey,
+        mint: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+    ) {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        self.set_data(address, token::ID, data);
+    }
+
+    /// Creates a wallet whose associated token account holds `amount` of the wager token
+    pub fn create_player(&mut self, amount: u64) -> Pubkey {
+        let player = self.create_wallet();
+        let token_account = get_associated_token_address(&player, &TOKEN_ID);
+        self.create_token_account(token_account, TOKEN_ID, player, amount);
+        player
+    }
+
+    pub fn token_balance(&self, token_account: &Pubkey) -> u64 {
+        self.account(token_account)
+            .and_then(|account| spl_token::state::Account::unpack(&account.data).ok())
+            .map_or(0, |account| account.amount)
+    }
+
+    /// Deserializes an Anchor account, panicking if it is missing or malformed
+    pub fn get<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self
+            .account(key)
+            .unwrap_or_else(|| panic!("account {key} does not exist"));
+        T::try_deserialize(&mut &account.data[..])
+            .unwrap_or_else(|err| panic!("account {key} failed to deserialize: {err}"))
+    }
+
+    /// Rewrites an Anchor account in place, for reaching states instructions can't
+    pub fn update<T: AccountDeserialize + AccountSerialize>(
+        &mut self,
+        key: &Pubkey,
+        update: impl FnOnce(&mut T),
+    ) {
+        let mut value = self.get::<T>(key);
+        update(&mut value);
+        let account = self.accounts.get_mut(key).unwrap();
+        value.try_serialize(&mut &mut account.data[..]).unwrap();
+    }
+
+    /// Processes an instruction, keeping its changes only if it succeeds
+    pub fn process(&mut self, instruction: &Instruction) -> std::result::Result<(), ProgramError> {
+        INVOCATION.with(|invocation| {
+            *invocation.borrow_mut() = Invocation {
+                compute_limit: self.compute_limit,
+                ..Invocation::default()
+            }
+        });
+
+        // Each account is loaded once, with the privileges of all its metas combined
+        let mut keys: Vec<(Pubkey, bool, bool)> = Vec::new();
+        for meta in &instruction.accounts {
+            match keys.iter_mut().find(|(key, _, _)| *key == meta.pubkey) {
+                Some((_, is_signer, is_writable)) => {
+                    *is_signer |= meta.is_signer;
+                    *is_writable |= meta.is_writable;
+                }
+                None => keys.push((meta.pubkey, meta.is_signer, meta.is_writable)),
+            }
+        }
+        let mut loaded: Vec<LoadedAccount> = keys
+            .iter()
+            .map(|(key, _, _)| {
+                LoadedAccount::new(*key, self.account(key).cloned().unwrap_or_default())
+            })
+            .collect();
+        let infos: Vec<AccountInfo> = loaded
+            .iter_mut()
+            .zip(&keys)
+            .map(|(account, (_, is_signer, is_writable))| account.info(*is_signer, *is_writable))
+            .collect();
+        let accounts: Vec<AccountInfo> = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let index = keys.iter().position(|(key, _, _)| *key == meta.pubkey);
+                infos[index.unwrap()].clone()
+            })
+            .collect();
+
+        let result = invoke(&instruction.program_id, &accounts, &instruction.data);
+        if result.is_ok() {
+            // Accounts left without lamports are garbage collected, like closed accounts
+            for info in infos.iter().filter(|info| info.is_writable) {
+                let pre = self.account(info.key);
+                let rent = Rent::default();
+                let was_rent_paying = pre.is_some_and(|pre| {
+                    pre.lamports > 0 && !rent.is_exempt(pre.lamports, pre.data.len())
+                });
+                // Only accounts that already paid rent may stay unchanged below the exempt balance
+                assert!(
+                    info.lamports() == 0
+                        || rent.is_exempt(info.lamports(), info.data_len())
+                        || (was_rent_paying
+                            && pre.is_some_and(|pre| {
+                                pre.data.len() == info.data_len() && info.lamports() <= pre.lamports
+                            })),
+                    "instruction left account {} below rent exemption",
+                    info.key
+                );
+
+                let account = StoredAccount {
+                    lamports: info.lamports(),
+                    data: info.data.borrow().to_vec(),
+                    owner: *info.owner,
+                    executable: info.executable,
+                };
+                if account.lamports == 0 {
+                    self.accounts.remove(info.key);
+                } else {
+                    self.accounts.insert(*info.key, account);
+                }
+            }
+        }
+        result
+    }
+
+    /// Returns the events of type `T` emitted by the last processed instruction
+    pub fn events<T: Discriminator + AnchorDeserialize>(&self) -> Vec<T> {
+        INVOCATION.with(|invocation| {
+            invocation
+                .borrow()
+                .events
+                .iter()
+                .filter(|data| data.starts_with(&T::DISCRIMINATOR))
+                .map(|data| T::deserialize(&mut &data[8..]).unwrap())
+                .collect()
+        })
+    }
+}
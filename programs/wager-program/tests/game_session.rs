@@ -0,0 +1,1246 @@
+mod common;
+
+use anchor_lang::{
+    prelude::*, solana_program::hash::hashv, AnchorSerialize, Discriminator, InstructionData,
+};
+use anchor_spl::associated_token::get_associated_token_address;
+use common::*;
+use solana_sdk::signer::Signer;
+use wager_client::{instructions, invite::invite_keypair, pda};
+use wager_program::{
+    errors::WagerError,
+    events::{PlayerJoined, SessionClosed, SessionCreated, SessionMigrated, StatusChanged},
+    instruction,
+    state::*,
+    TOKEN_ID,
+};
+
+/// Stores a 1v1 session on the original layout, with both players joined and
+/// `stats` spawns and kills each, and its escrow holding both bets
+fn create_legacy_session(
+    svm: &mut Svm,
+    game_server: &Pubkey,
+    session_id: &str,
+    players: [Pubkey; 2],
+    stats: [(u16, u16); 2],
+) -> GameSessionV0 {
+    let (legacy_session, bump) = pda::find_legacy_session(session_id);
+    let (legacy_vault, vault_bump) = pda::find_legacy_vault(session_id);
+    let team = |player: Pubkey, (spawns, kills): (u16, u16)| TeamV0 {
+        players: [
+            player,
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+            Pubkey::default(),
+        ],
+        total_bet: BET,
+        player_spawns: [spawns, 0, 0, 0, 0],
+        player_kills: [kills, 0, 0, 0, 0],
+    };
+    let legacy = GameSessionV0 {
+        session_id: session_id.to_string(),
+        authority: *game_server,
+        session_bet: BET,
+        game_mode: GameModeV0::WinnerTakesAllOneVsOne,
+        team_a: team(players[0], stats[0]),
+        team_b: team(players[1], stats[1]),
+        status: GameStatus::InProgress,
+        created_at: CLOCK_TIMESTAMP,
+        bump,
+        vault_bump,
+        vault_token_bump: 0,
+    };
+
+    // The original program allocated a fixed size, leaving the tail zeroed
+    let mut data = GameSession::DISCRIMINATOR.to_vec();
+    data.extend(legacy.try_to_vec().unwrap());
+    data.resize(GameSessionV0::LEN, 0);
+    svm.set_data(legacy_session, wager_program::ID, data);
+    svm.set_data(legacy_vault, wager_program::ID, Vec::new());
+    svm.create_token_account(
+        pda::legacy_vault_token_account(session_id),
+        TOKEN_ID,
+        legacy_vault,
+        2 * BET,
+    );
+    legacy
+}
+
+#[test]
+fn create_game_session_initializes_session_and_vault() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "create", 2, 3);
+
+    let session = svm.session(session_hash);
+    assert_eq!(session.version, GAME_SESSION_VERSION);
+    assert_eq!(session.session_hash, session_hash);
+    assert_eq!(session.authority, game_server);
+    assert_eq!(session.session_bet, BET);
+    assert_eq!(session.teams.len(), 3);
+    assert!(session.teams.iter().all(|team| team.players.len() == 2));
+    assert!(session.status == GameStatus::WaitingForPlayers);
+    assert_eq!(session.created_at, CLOCK_TIMESTAMP);
+    assert_eq!(session.k_factor, DEFAULT_K_FACTOR);
+
+    let match_state = svm.match_state(session_hash);
+    assert_eq!(match_state.session, pda::find_game_session(&session_hash).0);
+    assert_eq!(match_state.authority, game_server);
+    assert_eq!(match_state.team_count, 3);
+    assert!(match_state.is_status(GameStatus::WaitingForPlayers));
+
+    // The vault's token account is created for the wager token
+    let vault_token_account =
+        svm.get::<anchor_spl::token::TokenAccount>(&pda::vault_token_account(&session_hash));
+    assert_eq!(vault_token_account.owner, pda::find_vault(&session_hash).0);
+    assert_eq!(vault_token_account.mint, TOKEN_ID);
+    assert_eq!(vault_token_account.amount, 0);
+
+    let created = svm.events::<SessionCreated>();
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].session_hash, session_hash);
+    assert_eq!(created[0].team_count, 3);
+}
+
+#[test]
+fn create_game_session_takes_any_team_size() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+
+    // Sizes between the old fixed variants work like any other
+    let session_hash = svm.create_team_session(&game_server, "four-a-side", 4, 2);
+    let session = svm.session(session_hash);
+    assert_eq!(session.team_size, 4);
+    assert!(session.teams.iter().all(|team| team.players.len() == 4));
+    let teams = svm.fill_teams(&game_server, session_hash);
+    assert!(teams.iter().all(|team| team.len() == 4));
+    assert!(svm.session(session_hash).status == GameStatus::InProgress);
+    assert_wager_error(
+        svm.process(&instructions::create_game_session(
+            &game_server,
+            pda::session_hash("oversized"),
+            BET,
+            GameMode::WinnerTakesAll,
+            MAX_TEAM_SIZE + 1,
+            2,
+            SessionOptions::default(),
+        )),
+        WagerError::InvalidPlayerCount,
+    );
+
+    // The largest session fits its account and can be filled
+    let largest = svm.create_team_session(&game_server, "largest", MAX_TEAM_SIZE, MAX_TEAMS);
+    svm.fill_teams(&game_server, largest);
+    assert!(svm.session(largest).status == GameStatus::InProgress);
+    let match_state = svm.match_state(largest);
+    assert!(match_state
+        .players
+        .iter()
+        .all(|player| *player != Pubkey::default()));
+}
+
+#[test]
+fn create_game_session_rejects_invalid_configurations() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let free_for_all = |payout_percentages| GameMode::FreeForAll {
+        max_players: 4,
+        payout_percentages,
+    };
+
+    let cases = [
+        (
+            GameMode::WinnerTakesAll,
+            0,
+            2,
+            BET,
+            SessionOptions::default(),
+            WagerError::InvalidPlayerCount,
+        ),
+        (
+            free_for_all([100, 0, 0]),
+            1,
+            2,
+            BET,
+            SessionOptions::default(),
+            WagerError::InvalidPlayerCount,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            MIN_TEAMS - 1,
+            BET,
+            SessionOptions::default(),
+            WagerError::InvalidTeamCount,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            MAX_TEAMS + 1,
+            BET,
+            SessionOptions::default(),
+            WagerError::InvalidTeamCount,
+        ),
+        (
+            free_for_all([50, 60, 0]),
+            0,
+            0,
+            BET,
+            SessionOptions::default(),
+            WagerError::InvalidPayoutPercentages,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            2,
+            1,
+            SessionOptions::default(),
+            WagerError::InvalidBetAmount,
+        ),
+        (
+            GameMode::Practice,
+            1,
+            2,
+            BET,
+            SessionOptions::default(),
+            WagerError::InvalidBetAmount,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            2,
+            BET,
+            SessionOptions {
+                session_id: Some("not a valid id".to_string()),
+                ..Default::default()
+            },
+            WagerError::InvalidSessionId,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            2,
+            BET,
+            SessionOptions {
+                session_id: Some("squatted".to_string()),
+                ..Default::default()
+            },
+            WagerError::SessionHashMismatch,
+        ),
+        (
+            GameMode::PayToSpawn,
+            2,
+            2,
+            BET,
+            SessionOptions {
+                team_configs: vec![
+                    TeamConfig {
+                        size: 1,
+                        player_bet: BET,
+                    };
+                    2
+                ],
+                ..Default::default()
+            },
+            WagerError::InvalidGameMode,
+        ),
+        (
+            GameMode::PayToSpawn,
+            1,
+            2,
+            BET,
+            SessionOptions {
+                best_of: 3,
+                ..Default::default()
+            },
+            WagerError::InvalidGameMode,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            2,
+            BET,
+            SessionOptions {
+                best_of: 4,
+                ..Default::default()
+            },
+            WagerError::InvalidSeriesLength,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            2,
+            BET,
+            SessionOptions {
+                rating_band: Some(RatingBand {
+                    min_rating: 1500,
+                    max_rating: 1400,
+                }),
+                ..Default::default()
+            },
+            WagerError::InvalidRatingBand,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            2,
+            BET,
+            SessionOptions {
+                linked_wallets: vec![vec![Pubkey::new_unique()]; MAX_LINKED_WALLET_GROUPS + 1],
+                ..Default::default()
+            },
+            WagerError::TooManyLinkedWallets,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            2,
+            BET,
+            SessionOptions {
+                linked_wallets: vec![vec![Pubkey::new_unique(); MAX_LINKED_GROUP_SIZE + 1]],
+                ..Default::default()
+            },
+            WagerError::TooManyLinkedWallets,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            2,
+            BET,
+            SessionOptions {
+                team_allowlists: vec![None; 3],
+                ..Default::default()
+            },
+            WagerError::InvalidTeamCount,
+        ),
+        (
+            GameMode::WinnerTakesAll,
+            1,
+            2,
+            BET,
+            SessionOptions {
+                allowlist: Some(Allowlist::Wallets(vec![
+                    Pubkey::new_unique();
+                    MAX_ALLOWLIST_WALLETS + 1
+                ])),
+                ..Default::default()
+            },
+            WagerError::AllowlistTooLarge,
+        ),
+    ];
+
+    for (game_mode, team_size, team_count, bet, options, error) in cases {
+        let result = svm.process(&instructions::create_game_session(
+            &game_server,
+            pda::session_hash("invalid"),
+            bet,
+            game_mode,
+            team_size,
+            team_count,
+            options,
+        ));
+        assert_wager_error(result, error);
+    }
+
+    // Nothing is created by a rejected session
+    assert!(svm
+        .account(&pda::find_game_session(&pda::session_hash("invalid")).0)
+        .is_none());
+}
+
+#[test]
+fn asymmetric_sessions_size_and_stake_each_team() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let config = |size, player_bet| TeamConfig { size, player_bet };
+    let create = |svm: &mut Svm, session_id: &str, team_configs: Vec<TeamConfig>| {
+        svm.process(&instructions::create_game_session(
+            &game_server,
+            pda::session_hash(session_id),
+            BET,
+            GameMode::WinnerTakesAll,
+            2,
+            2,
+            SessionOptions {
+                team_configs,
+                ..Default::default()
+            },
+        ))
+    };
+
+    // Each team has its own size and stake, bounded like the session's
+    let cases = [
+        (vec![config(1, BET)], WagerError::InvalidTeamCount),
+        (
+            vec![config(0, BET), config(2, BET)],
+            WagerError::InvalidPlayerCount,
+        ),
+        (
+            vec![config(3, BET), config(2, BET)],
+            WagerError::InvalidPlayerCount,
+        ),
+        (
+            vec![config(1, 1), config(2, BET)],
+            WagerError::InvalidBetAmount,
+        ),
+    ];
+    for (team_configs, error) in cases {
+        assert_wager_error(create(&mut svm, "invalid", team_configs), error);
+    }
+
+    create(
+        &mut svm,
+        "handicap",
+        vec![config(1, 3 * BET), config(2, BET)],
+    )
+    .unwrap();
+    let session_hash = pda::session_hash("handicap");
+    let session = svm.session(session_hash);
+    assert_eq!(session.teams[0].players.len(), 1);
+    assert_eq!(session.teams[1].players.len(), 2);
+
+    // Joiners pay their team's stake
+    let pro = svm.create_player(PLAYER_TOKENS);
+    let amateur = svm.create_player(PLAYER_TOKENS);
+    svm.join(&pro, &game_server, session_hash, 0).unwrap();
+    svm.join(&amateur, &game_server, session_hash, 1).unwrap();
+    assert_eq!(svm.player_balance(&pro), PLAYER_TOKENS - 3 * BET);
+    assert_eq!(svm.player_balance(&amateur), PLAYER_TOKENS - BET);
+    assert_eq!(svm.profile(&pro).total_wagered, 3 * BET);
+    let session = svm.session(session_hash);
+    assert_eq!(session.teams[0].total_bet, 3 * BET);
+    assert_eq!(session.teams[1].total_bet, BET);
+
+    let late = svm.create_player(PLAYER_TOKENS);
+    assert_wager_error(
+        svm.join(&late, &game_server, session_hash, 0),
+        WagerError::TeamIsFull,
+    );
+}
+
+#[test]
+fn create_game_session_accepts_stakes_and_ids_within_bounds() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let (_, min_bet, max_bet) = BET_LIMITS[0];
+    let create = |session_id: &str, bet, game_mode| {
+        instructions::create_game_session(
+            &game_server,
+            pda::session_hash(session_id),
+            bet,
+            game_mode,
+            1,
+            2,
+            SessionOptions {
+                session_id: Some(session_id.to_string()),
+                ..Default::default()
+            },
+        )
+    };
+
+    svm.process(&create("min-bet", min_bet, GameMode::WinnerTakesAll))
+        .unwrap();
+    svm.process(&create("max-bet", max_bet, GameMode::WinnerTakesAll))
+        .unwrap();
+    assert_wager_error(
+        svm.process(&create(
+            "over-max-bet",
+            max_bet + 1,
+            GameMode::WinnerTakesAll,
+        )),
+        WagerError::InvalidBetAmount,
+    );
+
+    // The longest session ID is stored in full
+    let longest_id = "x".repeat(MAX_SESSION_ID_LEN);
+    svm.process(&create(&longest_id, BET, GameMode::WinnerTakesAll))
+        .unwrap();
+    let session = svm.session(pda::session_hash(&longest_id));
+    assert_eq!(session.session_id, Some(longest_id.clone()));
+    assert_wager_error(
+        svm.process(&create(
+            &format!("{longest_id}x"),
+            BET,
+            GameMode::WinnerTakesAll,
+        )),
+        WagerError::InvalidSessionId,
+    );
+
+    // Practice games cost nothing to join
+    svm.process(&create("practice", 0, GameMode::Practice))
+        .unwrap();
+    let practice = pda::session_hash("practice");
+    let player = svm.create_player(0);
+    svm.join(&player, &game_server, practice, 0).unwrap();
+    assert_eq!(svm.profile(&player).total_wagered, 0);
+    assert_eq!(svm.vault_balance(practice), 0);
+}
+
+#[test]
+fn create_game_session_requires_wager_mint() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let other_mint = Pubkey::new_unique();
+    svm.create_mint(other_mint);
+
+    let session_hash = pda::session_hash("other-mint");
+    let vault = pda::find_vault(&session_hash).0;
+    let mut ix = instructions::create_game_session(
+        &game_server,
+        session_hash,
+        BET,
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        SessionOptions::default(),
+    );
+    for meta in ix.accounts.iter_mut() {
+        if meta.pubkey == TOKEN_ID {
+            meta.pubkey = other_mint;
+        } else if meta.pubkey == pda::vault_token_account(&session_hash) {
+            meta.pubkey = get_associated_token_address(&vault, &other_mint);
+        }
+    }
+    assert_wager_error(svm.process(&ix), WagerError::InvalidMint);
+}
+
+#[test]
+fn join_user_seats_players_and_starts_full_session() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "join", 1, 2);
+    let first = svm.create_player(PLAYER_TOKENS);
+    let second = svm.create_player(PLAYER_TOKENS);
+
+    svm.join(&first, &game_server, session_hash, 1).unwrap();
+    assert_eq!(svm.player_balance(&first), PLAYER_TOKENS - BET);
+    assert_eq!(svm.vault_balance(session_hash), BET);
+
+    let joined = svm.events::<PlayerJoined>();
+    assert_eq!(joined.len(), 1);
+    assert_eq!(joined[0].player, first);
+    assert_eq!(joined[0].team, Some(1));
+    assert_eq!(joined[0].amount, BET);
+
+    let session = svm.session(session_hash);
+    assert_eq!(session.teams[1].players, vec![first]);
+    assert_eq!(session.teams[1].total_bet, BET);
+    assert!(session.status == GameStatus::WaitingForPlayers);
+
+    let match_state = svm.match_state(session_hash);
+    let seat = MatchState::team_seat(1, 0);
+    assert_eq!(match_state.players[seat], first);
+    assert_eq!(match_state.spawns[seat], STARTING_SPAWNS);
+
+    // The first game creates the player's lifetime profile
+    let profile = svm.profile(&first);
+    assert_eq!(profile.player, first);
+    assert_eq!(profile.games_played, 1);
+    assert_eq!(profile.total_wagered, BET);
+    assert_eq!(profile.rating, DEFAULT_RATING);
+
+    // Filling the last seat starts the game
+    svm.join(&second, &game_server, session_hash, 0).unwrap();
+    assert!(svm.session(session_hash).status == GameStatus::InProgress);
+    assert!(svm
+        .match_state(session_hash)
+        .is_status(GameStatus::InProgress));
+    let changed = svm.events::<StatusChanged>();
+    assert_eq!(changed.len(), 1);
+    assert!(changed[0].from == GameStatus::WaitingForPlayers);
+    assert!(changed[0].to == GameStatus::InProgress);
+    assert_eq!(svm.vault_balance(session_hash), 2 * BET);
+}
+
+#[test]
+fn join_user_rejects_invalid_joins() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "invalid-joins", 1, 2);
+    let first = svm.create_player(PLAYER_TOKENS);
+    let second = svm.create_player(PLAYER_TOKENS);
+    let third = svm.create_player(PLAYER_TOKENS);
+
+    assert_wager_error(
+        svm.join(&first, &game_server, session_hash, 2),
+        WagerError::InvalidTeamSelection,
+    );
+
+    // Players have to be able to cover the stake
+    let broke = svm.create_player(BET - 1);
+    assert_wager_error(
+        svm.join(&broke, &game_server, session_hash, 0),
+        WagerError::InsufficientFunds,
+    );
+    assert_eq!(svm.player_balance(&broke), BET - 1);
+
+    svm.join(&first, &game_server, session_hash, 0).unwrap();
+    assert_wager_error(
+        svm.join(&first, &game_server, session_hash, 1),
+        WagerError::PlayerAlreadyJoined,
+    );
+    assert_wager_error(
+        svm.join(&second, &game_server, session_hash, 0),
+        WagerError::TeamIsFull,
+    );
+
+    svm.join(&second, &game_server, session_hash, 1).unwrap();
+    assert_wager_error(
+        svm.join(&third, &game_server, session_hash, 1),
+        WagerError::InvalidGameState,
+    );
+
+    // Free-for-all lobbies are joined through join_free_for_all
+    let free_for_all = svm.create_session(
+        &game_server,
+        "free-for-all",
+        GameMode::FreeForAll {
+            max_players: 2,
+            payout_percentages: [100, 0, 0],
+        },
+        0,
+        0,
+        SessionOptions::default(),
+    );
+    assert_wager_error(
+        svm.join(&third, &game_server, free_for_all, 0),
+        WagerError::InvalidGameMode,
+    );
+
+    // A rejected join takes nothing from the player
+    assert_eq!(svm.player_balance(&third), PLAYER_TOKENS);
+}
+
+#[test]
+fn join_user_enforces_allowlists() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let listed = svm.create_player(PLAYER_TOKENS);
+    let unlisted = svm.create_player(PLAYER_TOKENS);
+    let merkle_listed = svm.create_player(PLAYER_TOKENS);
+    let merkle_sibling = Pubkey::new_unique();
+
+    // The team allowlist applies on top of the session-wide one
+    let leaf = |player: &Pubkey| hashv(&[player.as_ref()]).to_bytes();
+    let (low, high) = {
+        let (a, b) = (leaf(&merkle_listed), leaf(&merkle_sibling));
+        (a.min(b), a.max(b))
+    };
+    let root = hashv(&[&low, &high]).to_bytes();
+    let session_hash = svm.create_session(
+        &game_server,
+        "allowlist",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        SessionOptions {
+            allowlist: Some(Allowlist::Wallets(vec![listed, merkle_listed])),
+            team_allowlists: vec![None, Some(Allowlist::MerkleRoot(root))],
+            ..Default::default()
+        },
+    );
+
+    assert_wager_error(
+        svm.join(&unlisted, &game_server, session_hash, 0),
+        WagerError::NotOnAllowlist,
+    );
+    assert_wager_error(
+        svm.join(&listed, &game_server, session_hash, 1),
+        WagerError::NotOnAllowlist,
+    );
+    svm.join(&listed, &game_server, session_hash, 0).unwrap();
+
+    let join_with_proof = |proof: Vec<[u8; 32]>| {
+        instructions::join_user(
+            &merkle_listed,
+            &game_server,
+            session_hash,
+            1,
+            JoinCredentials {
+                merkle_proof: proof,
+            },
+            None,
+            None,
+        )
+    };
+    assert_wager_error(
+        svm.process(&join_with_proof(vec![[7; 32]])),
+        WagerError::NotOnAllowlist,
+    );
+    svm.process(&join_with_proof(vec![leaf(&merkle_sibling)]))
+        .unwrap();
+    assert!(svm.session(session_hash).status == GameStatus::InProgress);
+}
+
+#[test]
+fn join_user_enforces_lobby_restrictions() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let player = svm.create_player(PLAYER_TOKENS);
+    let teammate = svm.create_player(PLAYER_TOKENS);
+
+    // Password protected lobbies need the join co-signed by the invite code's key
+    let invite = invite_keypair("open sesame").pubkey();
+    let invite_only = svm.create_session(
+        &game_server,
+        "invite-code",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        SessionOptions {
+            invite_key: Some(invite),
+            ..Default::default()
+        },
+    );
+    let join_with_invite = |invite: Option<Pubkey>| {
+        instructions::join_user(
+            &player,
+            &game_server,
+            invite_only,
+            0,
+            JoinCredentials::default(),
+            None,
+            invite,
+        )
+    };
+    assert_wager_error(
+        svm.process(&join_with_invite(None)),
+        WagerError::InvalidInviteCode,
+    );
+    assert_wager_error(
+        svm.process(&join_with_invite(Some(
+            invite_keypair("let me in").pubkey(),
+        ))),
+        WagerError::InvalidInviteCode,
+    );
+
+    // The code itself never reaches the chain
+    let join = join_with_invite(Some(invite));
+    assert!(!join
+        .data
+        .windows(b"open sesame".len())
+        .any(|window| window == b"open sesame"));
+    let invite_meta = join
+        .accounts
+        .iter()
+        .find(|meta| meta.pubkey == invite)
+        .unwrap();
+    assert!(invite_meta.is_signer);
+    svm.process(&join).unwrap();
+
+    // Gated lobbies need a token account holding enough of the gating token
+    let gate_mint = Pubkey::new_unique();
+    svm.create_mint(gate_mint);
+    let gated = svm.create_session(
+        &game_server,
+        "token-gate",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        SessionOptions {
+            token_gate: Some(TokenGate {
+                mint: gate_mint,
+                min_balance: 5,
+            }),
+            ..Default::default()
+        },
+    );
+    let gate_account = get_associated_token_address(&player, &gate_mint);
+    let join_gated = |gate_token_account| {
+        instructions::join_user(
+            &player,
+            &game_server,
+            gated,
+            0,
+            JoinCredentials::default(),
+            gate_token_account,
+            None,
+        )
+    };
+    assert_wager_error(svm.process(&join_gated(None)), WagerError::TokenGateNotMet);
+    svm.create_token_account(gate_account, gate_mint, player, 4);
+    assert_wager_error(
+        svm.process(&join_gated(Some(gate_account))),
+        WagerError::TokenGateNotMet,
+    );
+    svm.create_token_account(gate_account, gate_mint, player, 5);
+    svm.process(&join_gated(Some(gate_account))).unwrap();
+
+    // Rated lobbies only admit players inside their band
+    let rated = svm.create_session(
+        &game_server,
+        "rating-band",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        SessionOptions {
+            rating_band: Some(RatingBand {
+                min_rating: DEFAULT_RATING + 100,
+                max_rating: DEFAULT_RATING + 300,
+            }),
+            ..Default::default()
+        },
+    );
+    assert_wager_error(
+        svm.join(&player, &game_server, rated, 0),
+        WagerError::RatingOutOfBand,
+    );
+
+    // Linked wallets can play together but never against each other
+    let linked = svm.create_session(
+        &game_server,
+        "linked-wallets",
+        GameMode::WinnerTakesAll,
+        2,
+        2,
+        SessionOptions {
+            linked_wallets: vec![vec![player, teammate]],
+            ..Default::default()
+        },
+    );
+    svm.join(&player, &game_server, linked, 0).unwrap();
+    assert_wager_error(
+        svm.join(&teammate, &game_server, linked, 1),
+        WagerError::LinkedWalletOpponent,
+    );
+    svm.join(&teammate, &game_server, linked, 0).unwrap();
+}
+
+#[test]
+fn join_user_keeps_linked_wallets_apart_across_teams() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let linked: Vec<Pubkey> = (0..3).map(|_| svm.create_player(PLAYER_TOKENS)).collect();
+    let other_linked: Vec<Pubkey> = (0..2).map(|_| svm.create_player(PLAYER_TOKENS)).collect();
+    let session_hash = svm.create_session(
+        &game_server,
+        "linked-teams",
+        GameMode::WinnerTakesAll,
+        2,
+        3,
+        SessionOptions {
+            linked_wallets: vec![linked.clone(), other_linked.clone()],
+            ..Default::default()
+        },
+    );
+
+    // A wallet holds a single seat, whichever team it tries
+    svm.join(&linked[0], &game_server, session_hash, 0).unwrap();
+    for team in 0..3 {
+        assert_wager_error(
+            svm.join(&linked[0], &game_server, session_hash, team),
+            WagerError::PlayerAlreadyJoined,
+        );
+    }
+
+    // Every other team is an opponent, and each group is checked on its own
+    for team in 1..3 {
+        assert_wager_error(
+            svm.join(&linked[1], &game_server, session_hash, team),
+            WagerError::LinkedWalletOpponent,
+        );
+    }
+    svm.join(&linked[1], &game_server, session_hash, 0).unwrap();
+    svm.join(&other_linked[0], &game_server, session_hash, 1)
+        .unwrap();
+    svm.join(&other_linked[1], &game_server, session_hash, 1)
+        .unwrap();
+    assert_wager_error(
+        svm.join(&linked[2], &game_server, session_hash, 2),
+        WagerError::LinkedWalletOpponent,
+    );
+}
+
+#[test]
+fn token_gate_checks_owner_mint_and_balance() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let player = svm.create_player(PLAYER_TOKENS);
+    let holder = svm.create_wallet();
+    let (gate_mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    svm.create_mint(gate_mint);
+    svm.create_mint(other_mint);
+    let gated = svm.create_session(
+        &game_server,
+        "token-gate",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        SessionOptions {
+            token_gate: Some(TokenGate {
+                mint: gate_mint,
+                min_balance: 5,
+            }),
+            ..Default::default()
+        },
+    );
+    let join_gated = |gate_token_account| {
+        instructions::join_user(
+            &player,
+            &game_server,
+            gated,
+            0,
+            JoinCredentials::default(),
+            Some(gate_token_account),
+            None,
+        )
+    };
+
+    // Someone else's balance or another token's doesn't count
+    let holder_account = get_associated_token_address(&holder, &gate_mint);
+    svm.create_token_account(holder_account, gate_mint, holder, 10);
+    assert_wager_error(
+        svm.process(&join_gated(holder_account)),
+        WagerError::TokenGateNotMet,
+    );
+    let other_account = get_associated_token_address(&player, &other_mint);
+    svm.create_token_account(other_account, other_mint, player, 10);
+    assert_wager_error(
+        svm.process(&join_gated(other_account)),
+        WagerError::TokenGateNotMet,
+    );
+    assert_eq!(svm.player_balance(&player), PLAYER_TOKENS);
+
+    // NFT collections gate free-for-all lobbies with a balance of one
+    let nft_mint = Pubkey::new_unique();
+    svm.create_mint(nft_mint);
+    let nft_gated = svm.create_session(
+        &game_server,
+        "nft-gate",
+        GameMode::FreeForAll {
+            max_players: 2,
+            payout_percentages: [100, 0, 0],
+        },
+        0,
+        0,
+        SessionOptions {
+            token_gate: Some(TokenGate {
+                mint: nft_mint,
+                min_balance: 1,
+            }),
+            ..Default::default()
+        },
+    );
+    let nft_account = get_associated_token_address(&player, &nft_mint);
+    let join_free_for_all = |gate_token_account| {
+        instructions::join_free_for_all(
+            &player,
+            &game_server,
+            nft_gated,
+            JoinCredentials::default(),
+            gate_token_account,
+            None,
+        )
+    };
+    assert_wager_error(
+        svm.process(&join_free_for_all(None)),
+        WagerError::TokenGateNotMet,
+    );
+    svm.create_token_account(nft_account, nft_mint, player, 1);
+    svm.process(&join_free_for_all(Some(nft_account))).unwrap();
+}
+
+#[test]
+fn join_free_for_all_fills_lobby() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "free-for-all",
+        GameMode::FreeForAll {
+            max_players: 3,
+            payout_percentages: [70, 30, 0],
+        },
+        0,
+        0,
+        SessionOptions::default(),
+    );
+    let players: Vec<Pubkey> = (0..4).map(|_| svm.create_player(PLAYER_TOKENS)).collect();
+    let join = |player: &Pubkey| {
+        instructions::join_free_for_all(
+            player,
+            &game_server,
+            session_hash,
+            JoinCredentials::default(),
+            None,
+            None,
+        )
+    };
+
+    for player in &players[..3] {
+        svm.process(&join(player)).unwrap();
+        let joined = svm.events::<PlayerJoined>();
+        assert_eq!(joined[0].team, None);
+    }
+
+    let session = svm.session(session_hash);
+    assert_eq!(session.free_for_all_players, players[..3]);
+    assert!(session.status == GameStatus::InProgress);
+    assert_eq!(svm.vault_balance(session_hash), 3 * BET);
+
+    let match_state = svm.match_state(session_hash);
+    assert_eq!(match_state.free_for_all, 1);
+    assert_eq!(match_state.players[..3], players[..3]);
+    assert!(match_state.is_status(GameStatus::InProgress));
+
+    assert_wager_error(
+        svm.process(&join(&players[3])),
+        WagerError::InvalidGameState,
+    );
+
+    // Team games are joined through join_user
+    let team_session = svm.create_team_session(&game_server, "team-game", 1, 2);
+    let team_join = instructions::join_free_for_all(
+        &players[3],
+        &game_server,
+        team_session,
+        JoinCredentials::default(),
+        None,
+        None,
+    );
+    assert_wager_error(svm.process(&team_join), WagerError::InvalidGameMode);
+}
+
+#[test]
+fn join_free_for_all_rejects_repeat_and_linked_players() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let player = svm.create_player(PLAYER_TOKENS);
+    let alt = svm.create_player(PLAYER_TOKENS);
+    let session_hash = svm.create_session(
+        &game_server,
+        "free-for-all",
+        GameMode::FreeForAll {
+            max_players: 4,
+            payout_percentages: [100, 0, 0],
+        },
+        0,
+        0,
+        SessionOptions {
+            linked_wallets: vec![vec![player, alt]],
+            ..Default::default()
+        },
+    );
+    let join = |player: &Pubkey| {
+        instructions::join_free_for_all(
+            player,
+            &game_server,
+            session_hash,
+            JoinCredentials::default(),
+            None,
+            None,
+        )
+    };
+
+    svm.process(&join(&player)).unwrap();
+    assert_wager_error(svm.process(&join(&player)), WagerError::PlayerAlreadyJoined);
+    // Every other free-for-all player is an opponent
+    assert_wager_error(svm.process(&join(&alt)), WagerError::LinkedWalletOpponent);
+
+    // Players have to be able to cover the stake
+    let broke = svm.create_player(BET - 1);
+    assert_wager_error(svm.process(&join(&broke)), WagerError::InsufficientFunds);
+}
+
+#[test]
+fn migrate_session_moves_legacy_session_to_its_hash_address() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let players = [
+        svm.create_player(PLAYER_TOKENS - BET),
+        svm.create_player(PLAYER_TOKENS - BET),
+    ];
+    let legacy =
+        create_legacy_session(&mut svm, &game_server, "legacy", players, [(10, 2), (8, 0)]);
+    let (legacy_session, _) = pda::find_legacy_session("legacy");
+    let (legacy_vault, _) = pda::find_legacy_vault("legacy");
+    let legacy_vault_token_account = pda::legacy_vault_token_account("legacy");
+
+    // Only the session's game server can migrate it
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::migrate_session(&intruder, &legacy)),
+        WagerError::UnauthorizedDistribution,
+    );
+
+    // Every seated player's profile has to be passed along
+    let mut ix = instructions::migrate_session(&game_server, &legacy);
+    ix.accounts.pop();
+    assert_wager_error(svm.process(&ix), WagerError::InvalidRemainingAccounts);
+    let mut ix = instructions::migrate_session(&game_server, &legacy);
+    ix.accounts.swap(13, 14);
+    assert_wager_error(svm.process(&ix), WagerError::InvalidPlayerProfile);
+
+    // Lamports sent to a profile address ahead of time don't block its creation
+    let (prefunded, _) = pda::find_player_profile(&players[1]);
+    svm.set_account(
+        prefunded,
+        StoredAccount {
+            lamports: 1_000,
+            data: Vec::new(),
+            owner: anchor_lang::system_program::ID,
+            executable: false,
+        },
+    );
+
+    svm.process(&instructions::migrate_session(&game_server, &legacy))
+        .unwrap();
+
+    let session_hash = pda::session_hash("legacy");
+    let session_key = pda::find_game_session(&session_hash).0;
+    let session = svm.session(session_hash);
+    assert_eq!(session.version, GAME_SESSION_VERSION);
+    assert_eq!(session.session_hash, session_hash);
+    assert_eq!(session.session_id.as_deref(), Some("legacy"));
+    assert_eq!(session.authority, game_server);
+    assert!(session.game_mode == GameMode::WinnerTakesAll);
+    assert_eq!(session.team_size, 1);
+    assert_eq!(session.get_all_players(), players.to_vec());
+    assert!(session.teams.iter().all(|team| team.total_bet == BET));
+    assert!(session.status == GameStatus::InProgress);
+    assert_eq!(session.k_factor, DEFAULT_K_FACTOR);
+
+    // Spawns and kills carry over to the match state
+    let match_state = svm.match_state(session_hash);
+    assert!(match_state.is_status(GameStatus::InProgress));
+    assert_eq!(match_state.get_kills_and_spawns(players[0]).unwrap(), 12);
+    assert_eq!(match_state.get_kills_and_spawns(players[1]).unwrap(), 8);
+
+    // The escrow moves to the new vault and the legacy accounts are closed
+    assert_eq!(svm.vault_balance(session_hash), 2 * BET);
+    assert!(svm.account(&legacy_session).is_none());
+    assert!(svm.account(&legacy_vault).is_none());
+    assert!(svm.account(&legacy_vault_token_account).is_none());
+
+    let migrated = svm.events::<SessionMigrated>();
+    assert_eq!(migrated.len(), 1);
+    assert_eq!(migrated[0].session, session_key);
+
+    // The original layout kept no profiles, so the legacy game starts them off
+    for player in players {
+        let profile = svm.profile(&player);
+        assert_eq!(profile.player, player);
+        assert_eq!((profile.games_played, profile.total_wagered), (1, BET));
+        assert_eq!(profile.rating, DEFAULT_RATING);
+    }
+
+    // The migrated session plays and settles like any other
+    svm.record_kill(&game_server, session_hash, (0, players[0]), (1, players[1]))
+        .unwrap();
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        0,
+    ))
+    .unwrap();
+    assert_eq!(svm.player_balance(&players[0]), PLAYER_TOKENS + BET);
+    assert_eq!(svm.player_balance(&players[1]), PLAYER_TOKENS - BET);
+    assert_eq!(svm.vault_balance(session_hash), 0);
+    let (winner, loser) = (svm.profile(&players[0]), svm.profile(&players[1]));
+    assert_eq!(
+        (winner.wins, winner.kills, winner.total_won),
+        (1, 1, 2 * BET)
+    );
+    assert_eq!((loser.losses, loser.deaths), (1, 1));
+}
+
+#[test]
+fn migrate_session_rejects_mismatched_and_current_sessions() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let players = [svm.create_player(0), svm.create_player(0)];
+    let legacy = create_legacy_session(&mut svm, &game_server, "legacy", players, [(10, 0); 2]);
+
+    // The new address has to be the hash of the legacy ID
+    let mut other = legacy.clone();
+    other.session_id = "other".to_string();
+    let mut mismatched = instructions::migrate_session(&game_server, &other);
+    let legacy_ix = instructions::migrate_session(&game_server, &legacy);
+    mismatched.accounts[1..4].clone_from_slice(&legacy_ix.accounts[1..4]);
+    mismatched.data = instruction::MigrateSession {
+        session_id: "legacy".to_string(),
+        session_hash: pda::session_hash("other"),
+    }
+    .data();
+    assert_wager_error(svm.process(&mismatched), WagerError::SessionHashMismatch);
+
+    // An account on the current layout has nothing to migrate
+    let current = svm.create_team_session(&game_server, "current", 1, 2);
+    let account = svm
+        .account(&pda::find_game_session(&current).0)
+        .unwrap()
+        .clone();
+    svm.set_account(pda::find_legacy_session("legacy").0, account);
+    assert_wager_error(
+        svm.process(&instructions::migrate_session(&game_server, &legacy)),
+        WagerError::SessionAlreadyMigrated,
+    );
+}
+
+#[test]
+fn close_game_session_returns_rent_once_settled() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "close", 1, 2);
+    let player = svm.create_player(PLAYER_TOKENS);
+    svm.join(&player, &game_server, session_hash, 0).unwrap();
+
+    let session = svm.session(session_hash);
+    assert_wager_error(
+        svm.process(&instructions::close_game_session(&game_server, &session)),
+        WagerError::GameNotCompleted,
+    );
+
+    svm.process(&instructions::refund_wager(&game_server, &session))
+        .unwrap();
+
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::close_game_session(&intruder, &session)),
+        WagerError::UnauthorizedDistribution,
+    );
+
+    let session_key = pda::find_game_session(&session_hash).0;
+    let match_state_key = pda::find_match_state(&session_hash).0;
+    let rent = svm.lamports(&session_key) + svm.lamports(&match_state_key);
+    let server_lamports = svm.lamports(&game_server);
+    svm.process(&instructions::close_game_session(&game_server, &session))
+        .unwrap();
+    let closed = svm.events::<SessionClosed>();
+    assert_eq!(closed.len(), 1);
+    assert_eq!(closed[0].session, session_key);
+    assert_eq!(closed[0].authority, game_server);
+
+    assert!(svm.account(&session_key).is_none());
+    assert!(svm.account(&match_state_key).is_none());
+    assert_eq!(svm.lamports(&game_server), server_lamports + rent);
+}
+
+#[test]
+fn close_game_session_closes_match_log() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "close-log", 1, 2);
+    svm.process(&instructions::create_match_log(&game_server, session_hash))
+        .unwrap();
+    let player = svm.create_player(PLAYER_TOKENS);
+    svm.join(&player, &game_server, session_hash, 0).unwrap();
+    let session = svm.session(session_hash);
+    svm.process(&instructions::refund_wager(&game_server, &session))
+        .unwrap();
+
+    // The log has to be closed along with the session
+    let mut without_log = session.clone();
+    without_log.match_log = None;
+    assert_wager_error(
+        svm.process(&instructions::close_game_session(
+            &game_server,
+            &without_log,
+        )),
+        WagerError::MissingMatchLog,
+    );
+    svm.process(&instructions::close_game_session(&game_server, &session))
+        .unwrap();
+    assert!(svm.account(&pda::find_match_log(&session_hash).0).is_none());
+}
@@ -0,0 +1,789 @@
+mod common;
+
+use common::*;
+use wager_client::{accounts::decode_match_log, instructions, pda, replay::merkle_root_and_proof};
+use wager_program::{
+    errors::WagerError,
+    events::{
+        KillRecorded, MatchLogCreated, ReplayCommitted, ReplayEventVerified, SeriesGameRecorded,
+        SessionMigrated, SpawnPurchased, StatusChanged,
+    },
+    state::*,
+};
+
+/// Compute unit ceilings of the hot-path instructions, shared with COMPUTE_BUDGETS
+/// in tests/utils.ts
+const RECORD_KILL_BUDGET: u64 = 30_000;
+const PAY_TO_SPAWN_BUDGET: u64 = 45_000;
+
+#[test]
+fn record_kill_ends_match_when_a_team_is_eliminated() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "kills", 1, 2);
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let (killer, victim) = ((0, teams[0][0]), (1, teams[1][0]));
+
+    for _ in 0..STARTING_SPAWNS - 1 {
+        svm.record_kill(&game_server, session_hash, killer, victim)
+            .unwrap();
+    }
+    let recorded = svm.events::<KillRecorded>();
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(recorded[0].killer, killer.1);
+    assert_eq!(recorded[0].victim, victim.1);
+    assert!(svm.events::<StatusChanged>().is_empty());
+
+    let match_state = svm.match_state(session_hash);
+    let (killer_seat, victim_seat) = (MatchState::team_seat(0, 0), MatchState::team_seat(1, 0));
+    assert_eq!(match_state.kills[killer_seat], STARTING_SPAWNS - 1);
+    assert_eq!(match_state.spawns[victim_seat], 1);
+    assert!(match_state.is_status(GameStatus::InProgress));
+    assert!(svm.session(session_hash).status == GameStatus::InProgress);
+
+    // The last spawn eliminates the only player of the team, ending the match
+    svm.record_kill(&game_server, session_hash, killer, victim)
+        .unwrap();
+    let match_state = svm.match_state(session_hash);
+    assert_eq!(match_state.eliminated[victim_seat], 1);
+    assert!(match_state.is_status(GameStatus::Ended));
+    assert_eq!(match_state.get_surviving_team(), Some(0));
+    let changed = svm.events::<StatusChanged>();
+    assert_eq!(changed.len(), 1);
+    assert!(changed[0].from == GameStatus::InProgress);
+    assert!(changed[0].to == GameStatus::Ended);
+    assert!(svm.session(session_hash).status == GameStatus::Ended);
+
+    // Kills are carried over to the players' lifetime profiles
+    assert_eq!(svm.profile(&killer.1).kills, STARTING_SPAWNS as u32);
+    assert_eq!(svm.profile(&victim.1).deaths, STARTING_SPAWNS as u32);
+
+    assert_wager_error(
+        svm.record_kill(&game_server, session_hash, killer, victim),
+        WagerError::GameNotInProgress,
+    );
+}
+
+#[test]
+fn record_kill_keeps_match_going_while_a_teammate_survives() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "teammates", 2, 2);
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let killer = (0, teams[0][0]);
+    let (first_victim, second_victim) = ((1, teams[1][0]), (1, teams[1][1]));
+
+    // Running out of spawns eliminates only that player
+    for _ in 0..STARTING_SPAWNS {
+        svm.record_kill(&game_server, session_hash, killer, first_victim)
+            .unwrap();
+    }
+    let match_state = svm.match_state(session_hash);
+    assert_eq!(match_state.eliminated[MatchState::team_seat(1, 0)], 1);
+    assert_eq!(match_state.eliminated[MatchState::team_seat(1, 1)], 0);
+    assert!(!match_state.is_team_eliminated(1));
+    assert!(match_state.is_status(GameStatus::InProgress));
+
+    // An eliminated player can't be killed again, and nothing is recorded
+    assert_wager_error(
+        svm.record_kill(&game_server, session_hash, killer, first_victim),
+        WagerError::PlayerHasNoSpawns,
+    );
+    assert_eq!(
+        svm.match_state(session_hash).kills[MatchState::team_seat(0, 0)],
+        STARTING_SPAWNS
+    );
+
+    // The match ends with the team's last player
+    for _ in 0..STARTING_SPAWNS {
+        svm.record_kill(&game_server, session_hash, killer, second_victim)
+            .unwrap();
+    }
+    let match_state = svm.match_state(session_hash);
+    assert!(match_state.is_team_eliminated(1));
+    assert_eq!(match_state.get_surviving_team(), Some(0));
+    assert!(match_state.is_status(GameStatus::Ended));
+}
+
+#[test]
+fn record_kill_rejects_invalid_kills() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "invalid-kills", 1, 2);
+    let first = svm.create_player(PLAYER_TOKENS);
+    let second = svm.create_player(PLAYER_TOKENS);
+    svm.join(&first, &game_server, session_hash, 0).unwrap();
+    svm.join(&second, &game_server, session_hash, 1).unwrap();
+
+    // A player with a profile who isn't seated in this session
+    let other_session = svm.create_team_session(&game_server, "other", 1, 2);
+    let outsider = svm.create_player(PLAYER_TOKENS);
+    svm.join(&outsider, &game_server, other_session, 0).unwrap();
+
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.record_kill(&intruder, session_hash, (0, first), (1, second)),
+        WagerError::UnauthorizedKill,
+    );
+    assert_wager_error(
+        svm.record_kill(&game_server, session_hash, (2, first), (1, second)),
+        WagerError::InvalidTeam,
+    );
+    assert_wager_error(
+        svm.record_kill(&game_server, session_hash, (1, first), (1, second)),
+        WagerError::PlayerNotFound,
+    );
+    assert_wager_error(
+        svm.record_kill(&game_server, session_hash, (0, outsider), (1, second)),
+        WagerError::PlayerNotFound,
+    );
+
+    // Nothing has been recorded by the rejected kills
+    let match_state = svm.match_state(session_hash);
+    assert!(match_state.kills.iter().all(|kills| *kills == 0));
+
+    // Kill counters can't wrap around
+    svm.update_match_state(session_hash, |match_state| {
+        match_state.kills[MatchState::team_seat(0, 0)] = u16::MAX;
+    });
+    assert_wager_error(
+        svm.record_kill(&game_server, session_hash, (0, first), (1, second)),
+        WagerError::ArithmeticError,
+    );
+
+    // Kills can't be recorded before every seat is taken
+    let waiting = svm.create_team_session(&game_server, "waiting", 1, 2);
+    let early = svm.create_player(PLAYER_TOKENS);
+    svm.join(&early, &game_server, waiting, 0).unwrap();
+    assert_wager_error(
+        svm.record_kill(&game_server, waiting, (0, early), (0, early)),
+        WagerError::GameNotInProgress,
+    );
+}
+
+#[test]
+fn pay_to_spawn_buys_back_into_the_match() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "pay-to-spawn",
+        GameMode::PayToSpawn,
+        1,
+        2,
+        SessionOptions::default(),
+    );
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let (killer, victim) = ((0, teams[0][0]), (1, teams[1][0]));
+
+    // Pay-to-spawn matches go on after a team is wiped out
+    for _ in 0..STARTING_SPAWNS {
+        svm.record_kill(&game_server, session_hash, killer, victim)
+            .unwrap();
+    }
+    let victim_seat = MatchState::team_seat(1, 0);
+    let match_state = svm.match_state(session_hash);
+    assert_eq!(match_state.eliminated[victim_seat], 1);
+    assert!(match_state.is_status(GameStatus::InProgress));
+    assert_wager_error(
+        svm.record_kill(&game_server, session_hash, killer, victim),
+        WagerError::PlayerHasNoSpawns,
+    );
+
+    svm.process(&instructions::pay_to_spawn(
+        &victim.1,
+        &game_server,
+        session_hash,
+        1,
+        false,
+    ))
+    .unwrap();
+    let purchased = svm.events::<SpawnPurchased>();
+    assert_eq!(purchased.len(), 1);
+    assert_eq!(purchased[0].player, victim.1);
+    assert_eq!(purchased[0].amount, BET);
+
+    let match_state = svm.match_state(session_hash);
+    assert_eq!(match_state.spawns[victim_seat], STARTING_SPAWNS);
+    assert_eq!(match_state.eliminated[victim_seat], 0);
+    assert_eq!(svm.session(session_hash).teams[1].total_bet, 2 * BET);
+    assert_eq!(svm.vault_balance(session_hash), 3 * BET);
+    assert_eq!(svm.player_balance(&victim.1), PLAYER_TOKENS - 2 * BET);
+
+    svm.record_kill(&game_server, session_hash, killer, victim)
+        .unwrap();
+}
+
+#[test]
+fn hot_path_instructions_stay_within_compute_budgets() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "compute-budgets",
+        GameMode::PayToSpawn,
+        5,
+        2,
+        SessionOptions::default(),
+    );
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let (killer, victim) = ((0, teams[0][4]), (1, teams[1][4]));
+
+    // The meter panics as soon as an instruction goes over its budget
+    svm.set_compute_limit(RECORD_KILL_BUDGET);
+    svm.record_kill(&game_server, session_hash, killer, victim)
+        .unwrap();
+    let record_kill_units = svm.compute_units();
+
+    svm.set_compute_limit(PAY_TO_SPAWN_BUDGET);
+    svm.process(&instructions::pay_to_spawn(
+        &victim.1,
+        &game_server,
+        session_hash,
+        1,
+        false,
+    ))
+    .unwrap();
+    let pay_to_spawn_units = svm.compute_units();
+
+    assert!((1..=RECORD_KILL_BUDGET).contains(&record_kill_units));
+    assert!((1..=PAY_TO_SPAWN_BUDGET).contains(&pay_to_spawn_units));
+}
+
+#[test]
+fn pay_to_spawn_rejects_invalid_purchases() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "invalid-spawns",
+        GameMode::PayToSpawn,
+        1,
+        2,
+        SessionOptions::default(),
+    );
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let player = teams[0][0];
+    let buy = |team| instructions::pay_to_spawn(&player, &game_server, session_hash, team, false);
+
+    assert_wager_error(svm.process(&buy(2)), WagerError::InvalidTeamSelection);
+    assert_wager_error(svm.process(&buy(1)), WagerError::UnauthorizedPayToSpawn);
+
+    // Spawn counters can't wrap around
+    svm.update_match_state(session_hash, |match_state| {
+        match_state.spawns[MatchState::team_seat(0, 0)] = u16::MAX;
+    });
+    assert_wager_error(svm.process(&buy(0)), WagerError::InvalidSpawns);
+    assert_eq!(svm.player_balance(&player), PLAYER_TOKENS - BET);
+
+    // Only pay-to-spawn games sell spawns
+    let winner_takes_all = svm.create_team_session(&game_server, "winner-takes-all", 1, 2);
+    let teams = svm.fill_teams(&game_server, winner_takes_all);
+    assert_wager_error(
+        svm.process(&instructions::pay_to_spawn(
+            &teams[0][0],
+            &game_server,
+            winner_takes_all,
+            0,
+            false,
+        )),
+        WagerError::InvalidGameState,
+    );
+
+    // Players have to be able to pay for their spawns
+    let broke_session = svm.create_session(
+        &game_server,
+        "broke",
+        GameMode::PayToSpawn,
+        1,
+        2,
+        SessionOptions::default(),
+    );
+    let broke = svm.create_player(BET);
+    svm.join(&broke, &game_server, broke_session, 0).unwrap();
+    let opponent = svm.create_player(PLAYER_TOKENS);
+    svm.join(&opponent, &game_server, broke_session, 1).unwrap();
+    assert_wager_error(
+        svm.process(&instructions::pay_to_spawn(
+            &broke,
+            &game_server,
+            broke_session,
+            0,
+            false,
+        )),
+        WagerError::InsufficientFunds,
+    );
+}
+
+#[test]
+fn match_log_records_kills_and_spawn_purchases() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "match-log",
+        GameMode::PayToSpawn,
+        1,
+        2,
+        SessionOptions::default(),
+    );
+
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::create_match_log(&intruder, session_hash)),
+        WagerError::UnauthorizedDistribution,
+    );
+    svm.process(&instructions::create_match_log(&game_server, session_hash))
+        .unwrap();
+    assert_eq!(
+        svm.session(session_hash).match_log,
+        Some(pda::find_match_log(&session_hash).0)
+    );
+    assert_eq!(svm.match_state(session_hash).match_log, 1);
+    let created = svm.events::<MatchLogCreated>();
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].session, pda::find_game_session(&session_hash).0);
+    assert_eq!(created[0].match_log, pda::find_match_log(&session_hash).0);
+
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let (killer, victim) = ((0, teams[0][0]), (1, teams[1][0]));
+
+    // Sessions with a log have to pass it to every kill and spawn purchase
+    let kill_without_log = instructions::record_kill(
+        &game_server,
+        session_hash,
+        killer.0,
+        killer.1,
+        victim.0,
+        victim.1,
+        false,
+    );
+    assert_wager_error(svm.process(&kill_without_log), WagerError::MissingMatchLog);
+    let spawn_without_log =
+        instructions::pay_to_spawn(&victim.1, &game_server, session_hash, 1, false);
+    assert_wager_error(svm.process(&spawn_without_log), WagerError::MissingMatchLog);
+
+    svm.record_kill(&game_server, session_hash, killer, victim)
+        .unwrap();
+    svm.process(&instructions::pay_to_spawn(
+        &victim.1,
+        &game_server,
+        session_hash,
+        1,
+        true,
+    ))
+    .unwrap();
+
+    let log_account = svm.account(&pda::find_match_log(&session_hash).0).unwrap();
+    let match_log = decode_match_log(&log_account.data).unwrap();
+    assert_eq!(match_log.session, pda::find_game_session(&session_hash).0);
+    assert_eq!(match_log.total_events, 2);
+
+    let events = match_log.events();
+    assert_eq!(events[0].kind, MatchEventKind::Kill as u8);
+    assert_eq!(events[0].player, killer.1);
+    assert_eq!(events[0].target, victim.1);
+    assert_eq!(events[0].target_team, 1);
+    assert_eq!(events[0].slot, CLOCK_SLOT);
+    assert_eq!(events[0].timestamp, CLOCK_TIMESTAMP);
+    assert_eq!(events[1].kind, MatchEventKind::Spawn as u8);
+    assert_eq!(events[1].player, victim.1);
+    assert_eq!(events[1].team, 1);
+
+    // A session only ever has one log, created before the match is decided
+    let settled = svm.create_team_session(&game_server, "settled", 1, 2);
+    let player = svm.create_player(PLAYER_TOKENS);
+    svm.join(&player, &game_server, settled, 0).unwrap();
+    let session = svm.session(settled);
+    svm.process(&instructions::refund_wager(&game_server, &session))
+        .unwrap();
+    assert_wager_error(
+        svm.process(&instructions::create_match_log(&game_server, settled)),
+        WagerError::InvalidGameState,
+    );
+}
+
+#[test]
+fn series_is_won_by_majority() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "series",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        SessionOptions {
+            best_of: 3,
+            ..Default::default()
+        },
+    );
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let (first, second) = (teams[0][0], teams[1][0]);
+
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::record_series_game(
+            &intruder,
+            session_hash,
+            0,
+        )),
+        WagerError::UnauthorizedDistribution,
+    );
+    assert_wager_error(
+        svm.process(&instructions::record_series_game(
+            &game_server,
+            session_hash,
+            2,
+        )),
+        WagerError::InvalidWinningTeam,
+    );
+
+    // A series can't be paid out before it is decided
+    let session = svm.session(session_hash);
+    assert_wager_error(
+        svm.process(&instructions::distribute_winnings(
+            &game_server,
+            &session,
+            0,
+        )),
+        WagerError::SeriesNotDecided,
+    );
+
+    // Each game played resets the match for the next one
+    svm.record_kill(&game_server, session_hash, (0, first), (1, second))
+        .unwrap();
+    svm.process(&instructions::record_series_game(
+        &game_server,
+        session_hash,
+        1,
+    ))
+    .unwrap();
+    let recorded = svm.events::<SeriesGameRecorded>();
+    assert_eq!(recorded[0].winning_team, 1);
+    assert_eq!(recorded[0].games_played, 1);
+    let match_state = svm.match_state(session_hash);
+    assert!(match_state.kills.iter().all(|kills| *kills == 0));
+    assert_eq!(
+        match_state.spawns[MatchState::team_seat(1, 0)],
+        STARTING_SPAWNS
+    );
+    assert!(svm.session(session_hash).status == GameStatus::InProgress);
+
+    // Stakes were escrowed once for the whole series
+    assert_eq!(svm.vault_balance(session_hash), 2 * BET);
+    assert_eq!(svm.player_balance(&first), PLAYER_TOKENS - BET);
+
+    svm.process(&instructions::record_series_game(
+        &game_server,
+        session_hash,
+        0,
+    ))
+    .unwrap();
+    svm.process(&instructions::record_series_game(
+        &game_server,
+        session_hash,
+        0,
+    ))
+    .unwrap();
+    let session = svm.session(session_hash);
+    let series = session.series.as_ref().unwrap();
+    assert_eq!(series.team_wins, vec![2, 1]);
+    assert_eq!(series.game_winners, vec![1, 0, 0]);
+    assert!(session.status == GameStatus::Ended);
+    assert!(svm.match_state(session_hash).is_status(GameStatus::Ended));
+
+    assert_wager_error(
+        svm.process(&instructions::record_series_game(
+            &game_server,
+            session_hash,
+            1,
+        )),
+        WagerError::GameNotInProgress,
+    );
+
+    // Only the series winner can be paid
+    assert_wager_error(
+        svm.process(&instructions::distribute_winnings(
+            &game_server,
+            &session,
+            1,
+        )),
+        WagerError::InvalidWinningTeam,
+    );
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        0,
+    ))
+    .unwrap();
+    assert_eq!(svm.player_balance(&first), PLAYER_TOKENS + BET);
+
+    // Single games have no series to record
+    let single = svm.create_team_session(&game_server, "single", 1, 2);
+    svm.fill_teams(&game_server, single);
+    assert_wager_error(
+        svm.process(&instructions::record_series_game(&game_server, single, 0)),
+        WagerError::InvalidGameMode,
+    );
+}
+
+#[test]
+fn series_game_won_by_elimination_is_recorded() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "series-elimination",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        SessionOptions {
+            best_of: 3,
+            ..Default::default()
+        },
+    );
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let (first, second) = (teams[0][0], teams[1][0]);
+    let eliminate_second = |svm: &mut Svm| {
+        for _ in 0..STARTING_SPAWNS {
+            svm.record_kill(&game_server, session_hash, (0, first), (1, second))
+                .unwrap();
+        }
+        assert!(svm.session(session_hash).status == GameStatus::Ended);
+        assert!(svm.match_state(session_hash).is_status(GameStatus::Ended));
+    };
+
+    // Wiping out a team ends the game, which is then recorded for the series
+    eliminate_second(&mut svm);
+    assert_wager_error(
+        svm.process(&instructions::record_series_game(
+            &game_server,
+            session_hash,
+            1,
+        )),
+        WagerError::InvalidWinningTeam,
+    );
+    svm.process(&instructions::record_series_game(
+        &game_server,
+        session_hash,
+        0,
+    ))
+    .unwrap();
+    let changed = svm.events::<StatusChanged>();
+    assert!(changed[0].from == GameStatus::Ended);
+    assert!(changed[0].to == GameStatus::InProgress);
+    assert!(svm.session(session_hash).status == GameStatus::InProgress);
+    let match_state = svm.match_state(session_hash);
+    assert!(match_state.is_status(GameStatus::InProgress));
+    assert_eq!(
+        match_state.spawns[MatchState::team_seat(1, 0)],
+        STARTING_SPAWNS
+    );
+    assert_eq!(match_state.eliminated[MatchState::team_seat(1, 0)], 0);
+
+    // The game that decides the series leaves the session ended for settlement
+    eliminate_second(&mut svm);
+    svm.process(&instructions::record_series_game(
+        &game_server,
+        session_hash,
+        0,
+    ))
+    .unwrap();
+    let session = svm.session(session_hash);
+    assert_eq!(session.series.as_ref().unwrap().team_wins, vec![2, 0]);
+    assert!(session.status == GameStatus::Ended);
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        0,
+    ))
+    .unwrap();
+    assert_eq!(svm.player_balance(&first), PLAYER_TOKENS + BET);
+    assert_eq!(svm.vault_balance(session_hash), 0);
+}
+
+#[test]
+fn series_length_is_validated() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let series = |best_of| SessionOptions {
+        best_of,
+        ..Default::default()
+    };
+
+    // Series need an odd number of games between two teams
+    for (best_of, team_count) in [(2, 2), (MAX_SERIES_GAMES + 2, 2), (3, 3)] {
+        assert_wager_error(
+            svm.process(&instructions::create_game_session(
+                &game_server,
+                pda::session_hash("invalid"),
+                BET,
+                GameMode::WinnerTakesAll,
+                1,
+                team_count,
+                series(best_of),
+            )),
+            WagerError::InvalidSeriesLength,
+        );
+    }
+
+    // A best of one is a single game
+    let single = svm.create_session(
+        &game_server,
+        "best-of-one",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        series(1),
+    );
+    assert!(svm.session(single).series.is_none());
+
+    let longest = svm.create_session(
+        &game_server,
+        "best-of-seven",
+        GameMode::Practice,
+        1,
+        2,
+        series(MAX_SERIES_GAMES),
+    );
+    let session = svm.session(longest);
+    let series = session.series.as_ref().unwrap();
+    assert_eq!(series.best_of, MAX_SERIES_GAMES);
+    assert_eq!(series.team_wins, vec![0, 0]);
+}
+
+#[test]
+fn replay_commitment_proves_logged_events() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "replay", 1, 2);
+    let teams = svm.fill_teams(&game_server, session_hash);
+
+    let events: Vec<ReplayEvent> = (0..4)
+        .map(|index| ReplayEvent {
+            index,
+            kind: MatchEventKind::Kill,
+            player: teams[0][0],
+            team: 0,
+            target: teams[1][0],
+            target_team: 1,
+            slot: CLOCK_SLOT + index as u64,
+            timestamp: CLOCK_TIMESTAMP + index as i64,
+        })
+        .collect();
+    let leaves: Vec<[u8; 32]> = events.iter().map(ReplayEvent::leaf).collect();
+    let (root, proof) = merkle_root_and_proof(&leaves, 2);
+
+    assert_wager_error(
+        svm.process(&instructions::verify_replay_event(
+            session_hash,
+            events[2],
+            proof.clone(),
+        )),
+        WagerError::ReplayNotCommitted,
+    );
+
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::commit_replay(
+            &intruder,
+            session_hash,
+            root,
+            4,
+        )),
+        WagerError::UnauthorizedDistribution,
+    );
+
+    // Sessions allocated on the version 1 layout have to be upgraded before committing
+    let address = pda::find_game_session(&session_hash).0;
+    let mut data = svm.account(&address).unwrap().data.clone();
+    data[8] = 1;
+    data.truncate(GAME_SESSION_V1_LEN);
+    svm.set_data(address, wager_program::ID, data);
+    assert_wager_error(
+        svm.process(&instructions::commit_replay(
+            &game_server,
+            session_hash,
+            root,
+            4,
+        )),
+        WagerError::SessionNotUpgraded,
+    );
+    assert_wager_error(
+        svm.process(&instructions::upgrade_session(&intruder, session_hash)),
+        WagerError::UnauthorizedDistribution,
+    );
+
+    svm.process(&instructions::upgrade_session(&game_server, session_hash))
+        .unwrap();
+    assert_eq!(svm.account(&address).unwrap().data.len(), GameSession::LEN);
+    let upgraded = svm.session(session_hash);
+    assert_eq!(upgraded.version, GAME_SESSION_VERSION);
+    assert!(upgraded.replay.is_none());
+    let migrated = svm.events::<SessionMigrated>();
+    assert_eq!(migrated[0].version, GAME_SESSION_VERSION);
+    assert_wager_error(
+        svm.process(&instructions::upgrade_session(&game_server, session_hash)),
+        WagerError::SessionAlreadyMigrated,
+    );
+
+    svm.process(&instructions::commit_replay(
+        &game_server,
+        session_hash,
+        root,
+        4,
+    ))
+    .unwrap();
+    let committed = svm.events::<ReplayCommitted>();
+    assert_eq!(committed[0].root, root);
+    assert_eq!(committed[0].event_count, 4);
+
+    // The replay is committed once
+    assert_wager_error(
+        svm.process(&instructions::commit_replay(
+            &game_server,
+            session_hash,
+            [0; 32],
+            4,
+        )),
+        WagerError::InvalidGameState,
+    );
+
+    svm.process(&instructions::verify_replay_event(
+        session_hash,
+        events[2],
+        proof.clone(),
+    ))
+    .unwrap();
+    let verified = svm.events::<ReplayEventVerified>();
+    assert!(verified[0].event == events[2]);
+
+    let tampered = ReplayEvent {
+        target: teams[0][0],
+        ..events[2]
+    };
+    assert_wager_error(
+        svm.process(&instructions::verify_replay_event(
+            session_hash,
+            tampered,
+            proof.clone(),
+        )),
+        WagerError::InvalidReplayProof,
+    );
+    assert_wager_error(
+        svm.process(&instructions::verify_replay_event(
+            session_hash,
+            events[3],
+            proof,
+        )),
+        WagerError::InvalidReplayProof,
+    );
+
+    // Sessions that haven't started have nothing to commit
+    let waiting = svm.create_team_session(&game_server, "waiting", 1, 2);
+    assert_wager_error(
+        svm.process(&instructions::commit_replay(&game_server, waiting, root, 4)),
+        WagerError::InvalidGameState,
+    );
+}
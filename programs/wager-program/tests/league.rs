@@ -0,0 +1,520 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::*;
+use wager_client::{instructions, pda};
+use wager_program::{
+    errors::WagerError,
+    events::{
+        LeagueCreated, LeagueMatchDrawn, LeagueMatchLinked, LeagueTeamRegistered, PrizePaid,
+        RefundIssued,
+    },
+    state::*,
+};
+
+/// Creates a league and registers a funded captain for every team, each the sole
+/// player on its roster
+fn create_full_league(
+    svm: &mut Svm,
+    game_server: &Pubkey,
+    league_id: &str,
+    max_teams: u8,
+    prize_percentages: [u8; 3],
+) -> Vec<Pubkey> {
+    svm.process(&instructions::create_league(
+        game_server,
+        league_id,
+        BET,
+        max_teams,
+        prize_percentages,
+    ))
+    .unwrap();
+    (0..max_teams)
+        .map(|_| {
+            let captain = svm.create_player(PLAYER_TOKENS);
+            svm.process(&instructions::register_league_team(
+                &captain,
+                league_id,
+                vec![captain],
+            ))
+            .unwrap();
+            captain
+        })
+        .collect()
+}
+
+/// Creates a one against one session and links it as the fixture between two teams
+fn create_fixture(
+    svm: &mut Svm,
+    game_server: &Pubkey,
+    league_id: &str,
+    session_id: &str,
+    (home, away): (u8, u8),
+) -> [u8; 32] {
+    let session_hash = svm.create_team_session(game_server, session_id, 1, 2);
+    svm.process(&instructions::link_league_match(
+        game_server,
+        league_id,
+        session_hash,
+        home,
+        away,
+    ))
+    .unwrap();
+    session_hash
+}
+
+/// Seats the captains of both teams of a one against one fixture
+fn play_fixture(
+    svm: &mut Svm,
+    game_server: &Pubkey,
+    fixture: [u8; 32],
+    captains: &[Pubkey],
+    (home, away): (u8, u8),
+) {
+    svm.join(&captains[home as usize], game_server, fixture, 0)
+        .unwrap();
+    svm.join(&captains[away as usize], game_server, fixture, 1)
+        .unwrap();
+}
+
+#[test]
+fn league_season_pays_out_standings() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let captains = create_full_league(&mut svm, &game_server, "league", 3, [60, 30, 10]);
+    assert_eq!(
+        svm.token_balance(&pda::league_vault_token_account("league")),
+        3 * BET
+    );
+
+    // The first team wins its fixture against the second
+    let fixture = create_fixture(&mut svm, &game_server, "league", "first", (0, 1));
+    let session = svm.session(fixture);
+    assert_eq!(
+        session.league_match.map(|link| (link.home, link.away)),
+        Some((0, 1))
+    );
+
+    // Only the league teams' rosters can take the fixture's seats
+    let ringer = svm.create_player(PLAYER_TOKENS);
+    assert_wager_error(
+        svm.join(&ringer, &game_server, fixture, 0),
+        WagerError::NotOnAllowlist,
+    );
+    assert_wager_error(
+        svm.join(&captains[1], &game_server, fixture, 0),
+        WagerError::NotOnAllowlist,
+    );
+    play_fixture(&mut svm, &game_server, fixture, &captains, (0, 1));
+    let session = svm.session(fixture);
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        0,
+    ))
+    .unwrap();
+
+    // A fixture called off before kick-off can be scheduled again
+    let called_off = create_fixture(&mut svm, &game_server, "league", "called-off", (1, 2));
+    svm.join(&captains[1], &game_server, called_off, 0).unwrap();
+    let session = svm.session(called_off);
+    svm.process(&instructions::refund_wager(&game_server, &session))
+        .unwrap();
+
+    // So can one refunded after kick-off, which records no result
+    let refunded = create_fixture(&mut svm, &game_server, "league", "refunded", (1, 2));
+    play_fixture(&mut svm, &game_server, refunded, &captains, (1, 2));
+    let session = svm.session(refunded);
+    svm.process(&instructions::refund_wager(&game_server, &session))
+        .unwrap();
+
+    // The rescheduled fixture is drawn, handing both stakes back
+    let drawn = create_fixture(&mut svm, &game_server, "league", "rescheduled", (2, 1));
+    let session = svm.session(drawn);
+    assert_wager_error(
+        svm.process(&instructions::settle_league_draw(&game_server, &session)),
+        WagerError::GameNotInProgress,
+    );
+    play_fixture(&mut svm, &game_server, drawn, &captains, (2, 1));
+    let session = svm.session(drawn);
+    svm.process(&instructions::settle_league_draw(&game_server, &session))
+        .unwrap();
+    let draws = svm.events::<LeagueMatchDrawn>();
+    assert_eq!(draws.len(), 1);
+    assert_eq!(draws[0].session, pda::find_game_session(&drawn).0);
+    assert_eq!((draws[0].home, draws[0].away), (2, 1));
+    assert_eq!(svm.events::<RefundIssued>().len(), 2);
+    // Captains are down their entry fee, and the second team its lost fixture
+    assert_eq!(svm.player_balance(&captains[1]), PLAYER_TOKENS - 2 * BET);
+    assert_eq!(svm.player_balance(&captains[2]), PLAYER_TOKENS - BET);
+    assert_eq!(svm.vault_balance(drawn), 0);
+    assert!(svm.session(drawn).status == GameStatus::Completed);
+
+    // Unlike the refunded fixture, the draw was played and stays on the profiles
+    assert_eq!(svm.profile(&captains[2]).games_played, 1);
+    assert_wager_error(
+        svm.process(&instructions::settle_league_draw(&game_server, &session)),
+        WagerError::GameNotInProgress,
+    );
+
+    // Only league fixtures can be drawn
+    let friendly = svm.create_team_session(&game_server, "friendly", 1, 2);
+    svm.fill_teams(&game_server, friendly);
+    let session = svm.session(friendly);
+    assert_wager_error(
+        svm.process(&instructions::settle_league_draw(&game_server, &session)),
+        WagerError::InvalidLeagueMatch,
+    );
+
+    let league: League = svm.get(&pda::find_league("league").0);
+    assert!(league.status == LeagueStatus::InProgress);
+    let records: Vec<(u16, u16, u16, u16)> = league
+        .teams
+        .iter()
+        .map(|team| (team.wins, team.losses, team.draws, team.points))
+        .collect();
+    assert_eq!(records, vec![(1, 0, 0, 3), (0, 1, 1, 1), (0, 0, 1, 1)]);
+    assert_eq!(league.standings, vec![0, 1, 2]);
+
+    // A fixture still being played when the season closes
+    let unfinished = create_fixture(&mut svm, &game_server, "league", "unfinished", (0, 2));
+    play_fixture(&mut svm, &game_server, unfinished, &captains, (0, 2));
+
+    assert_wager_error(
+        svm.process(&instructions::close_league_season(
+            &game_server,
+            "league",
+            &captains[..2],
+        )),
+        WagerError::InvalidRemainingAccounts,
+    );
+    assert_wager_error(
+        svm.process(&instructions::close_league_season(
+            &game_server,
+            "league",
+            &[captains[1], captains[0], captains[2]],
+        )),
+        WagerError::InvalidWinner,
+    );
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::close_league_season(
+            &intruder, "league", &captains,
+        )),
+        WagerError::UnauthorizedDistribution,
+    );
+
+    svm.process(&instructions::close_league_season(
+        &game_server,
+        "league",
+        &captains,
+    ))
+    .unwrap();
+    let prizes: Vec<(Pubkey, u8, u64)> = svm
+        .events::<PrizePaid>()
+        .iter()
+        .map(|prize| (prize.player, prize.place, prize.amount))
+        .collect();
+    assert_eq!(
+        prizes,
+        vec![
+            (captains[0], 1, 18 * BET / 10),
+            (captains[1], 2, 9 * BET / 10),
+            (captains[2], 3, 3 * BET / 10),
+        ]
+    );
+    assert_eq!(
+        svm.token_balance(&pda::league_vault_token_account("league")),
+        0
+    );
+
+    // can no longer change the final standings, so it can only be refunded
+    let session = svm.session(unfinished);
+    assert_wager_error(
+        svm.process(&instructions::distribute_winnings(
+            &game_server,
+            &session,
+            1,
+        )),
+        WagerError::InvalidGameState,
+    );
+    assert_wager_error(
+        svm.process(&instructions::settle_league_draw(&game_server, &session)),
+        WagerError::InvalidGameState,
+    );
+    svm.process(&instructions::refund_wager(&game_server, &session))
+        .unwrap();
+    let league: League = svm.get(&pda::find_league("league").0);
+    assert_eq!(league.teams[2].points, 1);
+    assert_eq!(league.standings, vec![0, 1, 2]);
+
+    // A closed season takes no more fixtures and pays out once
+    assert_wager_error(
+        svm.process(&instructions::close_league_season(
+            &game_server,
+            "league",
+            &captains,
+        )),
+        WagerError::InvalidGameState,
+    );
+    let late = svm.create_team_session(&game_server, "late", 1, 2);
+    assert_wager_error(
+        svm.process(&instructions::link_league_match(
+            &game_server,
+            "league",
+            late,
+            0,
+            2,
+        )),
+        WagerError::InvalidGameState,
+    );
+}
+
+#[test]
+fn create_league_rejects_invalid_configurations() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let cases = [
+        (1, [100, 0, 0], WagerError::InvalidTeamCount),
+        (17, [60, 30, 10], WagerError::InvalidTeamCount),
+        (4, [50, 30, 10], WagerError::InvalidPayoutPercentages),
+        (2, [60, 30, 10], WagerError::InvalidPayoutPercentages),
+    ];
+    for (max_teams, prize_percentages, error) in cases {
+        assert_wager_error(
+            svm.process(&instructions::create_league(
+                &game_server,
+                "invalid",
+                BET,
+                max_teams,
+                prize_percentages,
+            )),
+            error,
+        );
+    }
+
+    // Free leagues have no prize pool, and the ID must fit its reserved space
+    assert_wager_error(
+        svm.process(&instructions::create_league(
+            &game_server,
+            "free",
+            0,
+            2,
+            [100, 0, 0],
+        )),
+        WagerError::InvalidBetAmount,
+    );
+    assert_wager_error(
+        svm.process(&instructions::create_league(
+            &game_server,
+            "",
+            BET,
+            2,
+            [100, 0, 0],
+        )),
+        WagerError::InvalidCompetitionId,
+    );
+}
+
+#[test]
+fn register_league_team_rejects_invalid_registrations() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let captains = create_full_league(&mut svm, &game_server, "league", 2, [100, 0, 0]);
+
+    assert_wager_error(
+        svm.process(&instructions::register_league_team(
+            &captains[0],
+            "league",
+            vec![captains[0]],
+        )),
+        WagerError::InvalidPlayer,
+    );
+    let late = svm.create_player(PLAYER_TOKENS);
+    assert_wager_error(
+        svm.process(&instructions::register_league_team(
+            &late,
+            "league",
+            vec![late],
+        )),
+        WagerError::SessionIsFull,
+    );
+
+    // Rosters field a whole team, each wallet once and for a single team
+    svm.process(&instructions::create_league(
+        &game_server,
+        "open",
+        BET,
+        3,
+        [100, 0, 0],
+    ))
+    .unwrap();
+    let created = svm.events::<LeagueCreated>();
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].league, pda::find_league("open").0);
+    assert_eq!(created[0].authority, game_server);
+    assert_eq!((created[0].entry_fee, created[0].max_teams), (BET, 3));
+    let oversized = (0..=MAX_TEAM_SIZE).map(|_| Pubkey::new_unique()).collect();
+    for (roster, error) in [
+        (Vec::new(), WagerError::InvalidPlayerCount),
+        (oversized, WagerError::InvalidPlayerCount),
+        (vec![late, late], WagerError::InvalidPlayer),
+    ] {
+        assert_wager_error(
+            svm.process(&instructions::register_league_team(&late, "open", roster)),
+            error,
+        );
+    }
+    for captain in &captains {
+        svm.process(&instructions::register_league_team(
+            captain,
+            "open",
+            vec![*captain],
+        ))
+        .unwrap();
+    }
+    let registered = svm.events::<LeagueTeamRegistered>();
+    assert_eq!(registered.len(), 1);
+    assert_eq!(
+        (registered[0].captain, registered[0].team),
+        (captains[1], 1)
+    );
+    assert_eq!((registered[0].roster_size, registered[0].amount), (1, BET));
+    assert_wager_error(
+        svm.process(&instructions::register_league_team(
+            &late,
+            "open",
+            vec![late, captains[0]],
+        )),
+        WagerError::PlayerAlreadyJoined,
+    );
+
+    // Registration closes with the first fixture
+    create_fixture(&mut svm, &game_server, "open", "fixture", (0, 1));
+    assert_wager_error(
+        svm.process(&instructions::register_league_team(
+            &late,
+            "open",
+            vec![late],
+        )),
+        WagerError::InvalidGameState,
+    );
+    assert_eq!(svm.player_balance(&late), PLAYER_TOKENS);
+}
+
+#[test]
+fn link_league_match_rejects_invalid_fixtures() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    create_full_league(&mut svm, &game_server, "league", 3, [100, 0, 0]);
+    let linked = create_fixture(&mut svm, &game_server, "league", "linked", (0, 1));
+    let session_hash = svm.create_team_session(&game_server, "fixture", 1, 2);
+    let link = |session_hash, home, away| {
+        instructions::link_league_match(&game_server, "league", session_hash, home, away)
+    };
+
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::link_league_match(
+            &intruder,
+            "league",
+            session_hash,
+            0,
+            2,
+        )),
+        WagerError::UnauthorizedDistribution,
+    );
+
+    // Each pairing is played once, between two registered teams
+    for (home, away) in [(0, 1), (1, 0), (2, 2), (0, 3)] {
+        assert_wager_error(
+            svm.process(&link(session_hash, home, away)),
+            WagerError::InvalidLeagueMatch,
+        );
+    }
+    assert_wager_error(
+        svm.process(&link(linked, 0, 2)),
+        WagerError::InvalidLeagueMatch,
+    );
+
+    // Fixtures are unstarted one against one winner takes all games
+    let three_teams = svm.create_team_session(&game_server, "three-teams", 1, 3);
+    let series = svm.create_session(
+        &game_server,
+        "series",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        SessionOptions {
+            best_of: 3,
+            ..Default::default()
+        },
+    );
+    let started = svm.create_team_session(&game_server, "started", 1, 2);
+    svm.fill_teams(&game_server, started);
+    for session_hash in [three_teams, series, started] {
+        assert_wager_error(
+            svm.process(&link(session_hash, 0, 2)),
+            WagerError::InvalidGameMode,
+        );
+    }
+
+    // Both rosters have to be able to field a full team
+    let two_a_side = svm.create_team_session(&game_server, "two-a-side", 2, 2);
+    assert_wager_error(
+        svm.process(&link(two_a_side, 0, 2)),
+        WagerError::InvalidLeagueMatch,
+    );
+
+    svm.process(&link(session_hash, 0, 2)).unwrap();
+    let linked = svm.events::<LeagueMatchLinked>();
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked[0].league, pda::find_league("league").0);
+    assert_eq!(linked[0].session, pda::find_game_session(&session_hash).0);
+    assert_eq!((linked[0].home, linked[0].away), (0, 2));
+}
+
+#[test]
+fn league_fixture_settles_against_its_league() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let captains = create_full_league(&mut svm, &game_server, "league", 2, [100, 0, 0]);
+    let fixture = create_fixture(&mut svm, &game_server, "league", "fixture", (0, 1));
+    play_fixture(&mut svm, &game_server, fixture, &captains, (0, 1));
+
+    // Leaving out the league would drop the result from the standings
+    let mut session = svm.session(fixture);
+    session.league_match = None;
+    assert_wager_error(
+        svm.process(&instructions::distribute_winnings(
+            &game_server,
+            &session,
+            0,
+        )),
+        WagerError::InvalidLeague,
+    );
+    assert_wager_error(
+        svm.process(&instructions::refund_wager(&game_server, &session)),
+        WagerError::InvalidLeague,
+    );
+}
+
+#[test]
+fn close_league_season_rejects_overflowing_pool() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let captains = create_full_league(&mut svm, &game_server, "league", 2, [100, 0, 0]);
+
+    // Entry fees are escrowed as teams register, so only a corrupted fee can overflow
+    svm.update::<League>(&pda::find_league("league").0, |league| {
+        league.entry_fee = u64::MAX;
+    });
+    assert_wager_error(
+        svm.process(&instructions::close_league_season(
+            &game_server,
+            "league",
+            &captains,
+        )),
+        WagerError::TotalPotCalculationError,
+    );
+}
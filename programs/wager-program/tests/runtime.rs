@@ -0,0 +1,108 @@
+mod common;
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::Instruction, program_pack::Pack, system_instruction, system_program,
+    },
+};
+use anchor_spl::token::spl_token;
+use common::*;
+use wager_program::TOKEN_ID;
+
+/// Stores an empty account owned by `owner`, holding `lamports`
+fn create_owned_account(svm: &mut Svm, owner: Pubkey, lamports: u64) -> Pubkey {
+    let key = Pubkey::new_unique();
+    svm.set_account(
+        key,
+        StoredAccount {
+            lamports,
+            data: Vec::new(),
+            owner,
+            executable: false,
+        },
+    );
+    key
+}
+
+#[test]
+fn runtime_meters_instructions() {
+    let mut svm = Svm::new();
+    let from = svm.create_wallet();
+    let to = svm.create_wallet();
+    svm.process(&system_instruction::transfer(&from, &to, 1_000))
+        .unwrap();
+    assert_eq!(svm.lamports(&to), WALLET_LAMPORTS + 1_000);
+    assert!(svm.compute_units() > 0);
+
+    let game_server = svm.create_wallet();
+    svm.create_team_session(&game_server, "metered", 1, 2);
+    assert!((1..=COMPUTE_UNIT_LIMIT).contains(&svm.compute_units()));
+}
+
+#[test]
+#[should_panic(expected = "exceeded its limit")]
+fn runtime_enforces_compute_limit() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    svm.set_compute_limit(1_000);
+    svm.create_team_session(&game_server, "over-budget", 1, 2);
+}
+
+#[test]
+#[should_panic(expected = "below rent exemption")]
+fn runtime_enforces_rent_exemption() {
+    let mut svm = Svm::new();
+    let from = svm.create_wallet();
+    let to = svm.create_wallet();
+    let _ = svm.process(&system_instruction::transfer(
+        &from,
+        &to,
+        WALLET_LAMPORTS - 1,
+    ));
+}
+
+#[test]
+#[should_panic(expected = "debited account")]
+fn runtime_only_lets_owners_debit_lamports() {
+    let mut svm = Svm::new();
+    let from = create_owned_account(&mut svm, wager_program::ID, WALLET_LAMPORTS);
+    let to = svm.create_wallet();
+    let _ = svm.process(&system_instruction::transfer(&from, &to, 1_000));
+}
+
+#[test]
+#[should_panic(expected = "reassigned account")]
+fn runtime_only_lets_owners_reassign_accounts() {
+    let mut svm = Svm::new();
+    let account = create_owned_account(&mut svm, wager_program::ID, WALLET_LAMPORTS);
+    let _ = svm.process(&system_instruction::assign(&account, &system_program::ID));
+}
+
+#[test]
+#[should_panic(expected = "wrote to the data")]
+fn runtime_only_lets_owners_write_data() {
+    let mut svm = Svm::new();
+    let owner = svm.create_wallet();
+    let account = Pubkey::new_unique();
+    svm.set_data(
+        account,
+        system_program::ID,
+        vec![0; spl_token::state::Account::LEN],
+    );
+    let _ = svm.process(
+        &spl_token::instruction::initialize_account3(&spl_token::ID, &account, &TOKEN_ID, &owner)
+            .unwrap(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "changed read-only account")]
+fn runtime_keeps_read_only_accounts_unchanged() {
+    let mut svm = Svm::new();
+    let from = svm.create_wallet();
+    let to = svm.create_wallet();
+    let mut instruction: Instruction = system_instruction::transfer(&from, &to, 1_000);
+    instruction.accounts[1] = AccountMeta::new_readonly(to, false);
+    let _ = svm.process(&instruction);
+}
@@ -0,0 +1,700 @@
+mod common;
+
+use anchor_lang::{prelude::*, solana_program::instruction::Instruction};
+use anchor_spl::associated_token::get_associated_token_address;
+use common::*;
+use wager_client::{instructions, pda};
+use wager_program::{
+    errors::WagerError,
+    events::{RefundIssued, StatusChanged, WinningsPaid},
+    state::*,
+    TOKEN_ID,
+};
+
+/// Accounts every distribute_winnings instruction names before its remaining accounts
+const DISTRIBUTE_ACCOUNTS: usize = 10;
+
+/// Accounts every refund_wager instruction names before its remaining accounts
+const REFUND_ACCOUNTS: usize = 9;
+
+/// Replaces the payout pair at `index` of the remaining accounts with `pair`
+fn replace_pair(ix: &mut Instruction, named: usize, index: usize, pair: [AccountMeta; 2]) {
+    let start = named + index * 2;
+    ix.accounts[start..start + 2].clone_from_slice(&pair);
+}
+
+/// Creates a token account of `owner` for a mint other than the wager mint
+fn create_foreign_token_account(svm: &mut Svm, owner: &Pubkey) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    svm.create_mint(mint);
+    let token_account = get_associated_token_address(owner, &mint);
+    svm.create_token_account(token_account, mint, *owner, 0);
+    token_account
+}
+
+#[test]
+fn distribute_winnings_pays_winning_team() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "winner-takes-all", 2, 2);
+    let teams = svm.fill_teams(&game_server, session_hash);
+    assert_eq!(svm.vault_balance(session_hash), 4 * BET);
+
+    let session = svm.session(session_hash);
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        1,
+    ))
+    .unwrap();
+
+    let paid = svm.events::<WinningsPaid>();
+    assert_eq!(paid.len(), 2);
+    for (payment, winner) in paid.iter().zip(&teams[1]) {
+        assert_eq!(payment.player, *winner);
+        assert_eq!(payment.amount, 2 * BET);
+    }
+    assert_eq!(svm.vault_balance(session_hash), 0);
+    assert!(svm.session(session_hash).status == GameStatus::Completed);
+
+    for winner in &teams[1] {
+        assert_eq!(svm.player_balance(winner), PLAYER_TOKENS + BET);
+        let profile = svm.profile(winner);
+        assert_eq!((profile.wins, profile.losses), (1, 0));
+        assert_eq!(profile.total_won, 2 * BET);
+        assert_eq!(profile.rating, DEFAULT_RATING + 16);
+    }
+    for loser in &teams[0] {
+        assert_eq!(svm.player_balance(loser), PLAYER_TOKENS - BET);
+        let profile = svm.profile(loser);
+        assert_eq!((profile.wins, profile.losses), (0, 1));
+        assert_eq!(profile.rating, DEFAULT_RATING - 16);
+    }
+}
+
+#[test]
+fn distribute_winnings_pays_last_team_standing_among_several() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "three-teams", 1, 3);
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let player = |team: u8| (team, teams[team as usize][0]);
+
+    // Teams are addressed by index, and the match goes on while two remain
+    for _ in 0..STARTING_SPAWNS {
+        svm.record_kill(&game_server, session_hash, player(2), player(0))
+            .unwrap();
+    }
+    let match_state = svm.match_state(session_hash);
+    assert!(match_state.is_team_eliminated(0));
+    assert!(match_state.is_status(GameStatus::InProgress));
+    assert_eq!(match_state.get_surviving_team(), None);
+
+    for _ in 0..STARTING_SPAWNS {
+        svm.record_kill(&game_server, session_hash, player(1), player(2))
+            .unwrap();
+    }
+    let match_state = svm.match_state(session_hash);
+    assert!(match_state.is_status(GameStatus::Ended));
+    assert_eq!(match_state.get_surviving_team(), Some(1));
+
+    // The winning team takes every team's stake
+    let session = svm.session(session_hash);
+    assert_wager_error(
+        svm.process(&instructions::distribute_winnings(
+            &game_server,
+            &session,
+            2,
+        )),
+        WagerError::InvalidWinningTeam,
+    );
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        1,
+    ))
+    .unwrap();
+    assert_eq!(svm.player_balance(&teams[1][0]), PLAYER_TOKENS + 2 * BET);
+    assert_eq!(svm.player_balance(&teams[0][0]), PLAYER_TOKENS - BET);
+    assert_eq!(svm.player_balance(&teams[2][0]), PLAYER_TOKENS - BET);
+    assert_eq!(svm.vault_balance(session_hash), 0);
+    assert_eq!(svm.profile(&teams[1][0]).wins, 1);
+    assert_eq!(svm.profile(&teams[0][0]).losses, 1);
+    assert_eq!(svm.profile(&teams[2][0]).losses, 1);
+}
+
+#[test]
+fn player_profile_accumulates_lifetime_stats() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let player = svm.create_player(PLAYER_TOKENS);
+    let opponent = svm.create_player(PLAYER_TOKENS);
+    let play = |svm: &mut Svm, session_id: &str| {
+        let session_hash = svm.create_team_session(&game_server, session_id, 1, 2);
+        svm.join(&player, &game_server, session_hash, 0).unwrap();
+        svm.join(&opponent, &game_server, session_hash, 1).unwrap();
+        svm.session(session_hash)
+    };
+
+    // A win with a kill, then a loss with a death
+    let won = play(&mut svm, "won");
+    svm.record_kill(&game_server, won.session_hash, (0, player), (1, opponent))
+        .unwrap();
+    svm.process(&instructions::distribute_winnings(&game_server, &won, 0))
+        .unwrap();
+    let lost = play(&mut svm, "lost");
+    svm.record_kill(&game_server, lost.session_hash, (1, opponent), (0, player))
+        .unwrap();
+    svm.process(&instructions::distribute_winnings(&game_server, &lost, 1))
+        .unwrap();
+
+    // A refunded game is taken off the profile again
+    let refunded = play(&mut svm, "refunded");
+    assert_eq!(svm.profile(&player).games_played, 3);
+    svm.process(&instructions::refund_wager(&game_server, &refunded))
+        .unwrap();
+
+    let profile = svm.profile(&player);
+    assert_eq!(profile.games_played, 2);
+    assert_eq!((profile.wins, profile.losses), (1, 1));
+    assert_eq!((profile.kills, profile.deaths), (1, 1));
+    assert_eq!(profile.total_wagered, 2 * BET);
+    assert_eq!(profile.total_won, 2 * BET);
+    assert_eq!(svm.player_balance(&player), PLAYER_TOKENS);
+}
+
+#[test]
+fn distribute_winnings_rates_players_by_session_k_factor() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let k_factor = |k_factor| SessionOptions {
+        k_factor,
+        ..Default::default()
+    };
+
+    // Sessions can weigh their result more than the default
+    let ranked = svm.create_session(
+        &game_server,
+        "ranked",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        k_factor(64),
+    );
+    let teams = svm.fill_teams(&game_server, ranked);
+    let (winner, loser) = (teams[0][0], teams[1][0]);
+    let session = svm.session(ranked);
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        0,
+    ))
+    .unwrap();
+    assert_eq!(svm.profile(&winner).rating, DEFAULT_RATING + 32);
+    assert_eq!(svm.profile(&loser).rating, DEFAULT_RATING - 32);
+
+    // Practice games are unranked whatever their K-factor
+    let practice = svm.create_session(
+        &game_server,
+        "practice",
+        GameMode::Practice,
+        1,
+        2,
+        k_factor(64),
+    );
+    svm.join(&winner, &game_server, practice, 1).unwrap();
+    svm.join(&loser, &game_server, practice, 0).unwrap();
+    let session = svm.session(practice);
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        0,
+    ))
+    .unwrap();
+    assert_eq!(svm.profile(&winner).rating, DEFAULT_RATING + 32);
+    assert_eq!(svm.profile(&loser).rating, DEFAULT_RATING - 32);
+
+    // Rating bands include their bounds
+    let banded = svm.create_session(
+        &game_server,
+        "banded",
+        GameMode::WinnerTakesAll,
+        1,
+        2,
+        SessionOptions {
+            rating_band: Some(RatingBand {
+                min_rating: DEFAULT_RATING,
+                max_rating: DEFAULT_RATING + 32,
+            }),
+            ..Default::default()
+        },
+    );
+    svm.join(&winner, &game_server, banded, 0).unwrap();
+    assert_wager_error(
+        svm.join(&loser, &game_server, banded, 1),
+        WagerError::RatingOutOfBand,
+    );
+}
+
+#[test]
+fn distribute_winnings_shares_pot_by_stake() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "asymmetric",
+        GameMode::WinnerTakesAll,
+        2,
+        2,
+        SessionOptions {
+            team_configs: vec![
+                TeamConfig {
+                    size: 1,
+                    player_bet: 2 * BET,
+                },
+                TeamConfig {
+                    size: 2,
+                    player_bet: BET,
+                },
+            ],
+            ..Default::default()
+        },
+    );
+    let teams = svm.fill_teams(&game_server, session_hash);
+    assert_eq!(svm.vault_balance(session_hash), 4 * BET);
+
+    // The lone player staked as much as the whole other team, so takes the full pot
+    let session = svm.session(session_hash);
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        0,
+    ))
+    .unwrap();
+    assert_eq!(svm.player_balance(&teams[0][0]), PLAYER_TOKENS + 2 * BET);
+    for loser in &teams[1] {
+        assert_eq!(svm.player_balance(loser), PLAYER_TOKENS - BET);
+    }
+    assert_eq!(svm.vault_balance(session_hash), 0);
+}
+
+#[test]
+fn distribute_winnings_gives_rounding_dust_to_the_last_winner() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "rounding-dust",
+        GameMode::WinnerTakesAll,
+        2,
+        2,
+        SessionOptions {
+            team_configs: vec![
+                TeamConfig {
+                    size: 2,
+                    player_bet: BET,
+                },
+                TeamConfig {
+                    size: 1,
+                    player_bet: BET + 1,
+                },
+            ],
+            ..Default::default()
+        },
+    );
+    let teams = svm.fill_teams(&game_server, session_hash);
+    assert_eq!(svm.vault_balance(session_hash), 3 * BET + 1);
+
+    let session = svm.session(session_hash);
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        0,
+    ))
+    .unwrap();
+    let paid = svm.events::<WinningsPaid>();
+    assert_eq!(paid[0].amount, 3 * BET / 2);
+    assert_eq!(paid[1].amount, 3 * BET / 2 + 1);
+    assert_eq!(paid[1].player, teams[0][1]);
+    assert_eq!(svm.vault_balance(session_hash), 0);
+}
+
+#[test]
+fn distribute_winnings_rejects_invalid_payouts() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "invalid-payouts", 2, 2);
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let session = svm.session(session_hash);
+    let distribute =
+        |winning_team| instructions::distribute_winnings(&game_server, &session, winning_team);
+
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::distribute_winnings(&intruder, &session, 0)),
+        WagerError::UnauthorizedDistribution,
+    );
+    assert_wager_error(svm.process(&distribute(2)), WagerError::InvalidWinningTeam);
+
+    // Too few accounts for the profiles, then for the winners
+    let mut ix = distribute(0);
+    ix.accounts.truncate(DISTRIBUTE_ACCOUNTS + 2);
+    assert_wager_error(svm.process(&ix), WagerError::InvalidRemainingAccounts);
+    let mut ix = distribute(0);
+    ix.accounts
+        .drain(DISTRIBUTE_ACCOUNTS + 2..DISTRIBUTE_ACCOUNTS + 4);
+    assert_wager_error(svm.process(&ix), WagerError::InvalidRemainingAccounts);
+
+    // Profiles have to follow the joined players' order
+    let mut ix = distribute(0);
+    let last = ix.accounts.len() - 1;
+    ix.accounts.swap(last - 1, last);
+    assert_wager_error(svm.process(&ix), WagerError::InvalidPlayerProfile);
+
+    // Only the winning team is paid, and each winner once
+    let mut ix = distribute(0);
+    replace_pair(
+        &mut ix,
+        DISTRIBUTE_ACCOUNTS,
+        0,
+        instructions::payout_pair(&teams[1][0]),
+    );
+    assert_wager_error(svm.process(&ix), WagerError::InvalidWinner);
+    let mut ix = distribute(0);
+    replace_pair(
+        &mut ix,
+        DISTRIBUTE_ACCOUNTS,
+        1,
+        instructions::payout_pair(&teams[0][0]),
+    );
+    assert_wager_error(svm.process(&ix), WagerError::InvalidWinner);
+
+    // Winnings go to the winner's own wager token account
+    let mut ix = distribute(0);
+    ix.accounts[DISTRIBUTE_ACCOUNTS + 1] = instructions::payout_pair(&teams[0][1])[1].clone();
+    assert_wager_error(svm.process(&ix), WagerError::InvalidWinnerTokenAccount);
+    let foreign = create_foreign_token_account(&mut svm, &teams[0][0]);
+    let mut ix = distribute(0);
+    ix.accounts[DISTRIBUTE_ACCOUNTS + 1] = AccountMeta::new(foreign, false);
+    assert_wager_error(svm.process(&ix), WagerError::InvalidTokenMint);
+
+    // A winning team needs a stake to share the pot by
+    let session_key = pda::find_game_session(&session_hash).0;
+    svm.update::<GameSession>(&session_key, |session| session.teams[0].total_bet = 0);
+    assert_wager_error(svm.process(&distribute(0)), WagerError::NoWinnersFound);
+    svm.update::<GameSession>(&session_key, |session| session.teams[0].total_bet = 2 * BET);
+
+    // An eliminated team can't win
+    svm.update_match_state(session_hash, |match_state| {
+        for slot in 0..2 {
+            match_state.eliminated[MatchState::team_seat(0, slot)] = 1;
+        }
+    });
+    assert_wager_error(svm.process(&distribute(0)), WagerError::InvalidWinningTeam);
+
+    // Nothing left the vault
+    assert_eq!(svm.vault_balance(session_hash), 4 * BET);
+}
+
+#[test]
+fn distribute_winnings_requires_every_seat_filled() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "empty-team", 2, 2);
+    for _ in 0..2 {
+        let player = svm.create_player(PLAYER_TOKENS);
+        svm.join(&player, &game_server, session_hash, 0).unwrap();
+    }
+
+    // The empty team's open seats are the default key, whose token account exists
+    // like any other wallet's
+    let empty_seat = Pubkey::default();
+    svm.create_token_account(
+        get_associated_token_address(&empty_seat, &TOKEN_ID),
+        TOKEN_ID,
+        empty_seat,
+        0,
+    );
+    let session = svm.session(session_hash);
+    assert_wager_error(
+        svm.process(&instructions::distribute_winnings(
+            &game_server,
+            &session,
+            1,
+        )),
+        WagerError::NotAllPlayersJoined,
+    );
+    assert_eq!(svm.vault_balance(session_hash), 2 * BET);
+}
+
+#[test]
+fn distribute_winnings_settles_a_session_once() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "practice",
+        GameMode::Practice,
+        1,
+        2,
+        SessionOptions::default(),
+    );
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let session = svm.session(session_hash);
+    let distribute = || instructions::distribute_winnings(&game_server, &session, 0);
+
+    svm.process(&distribute()).unwrap();
+    let changed = svm.events::<StatusChanged>();
+    assert_eq!(changed.len(), 1);
+    assert!(changed[0].to == GameStatus::Completed);
+
+    // A repeat would record the result on the profiles a second time
+    assert_wager_error(svm.process(&distribute()), WagerError::InvalidGameState);
+    let profile = svm.profile(&teams[0][0]);
+    assert_eq!((profile.games_played, profile.wins), (1, 1));
+    assert_eq!(svm.profile(&teams[1][0]).losses, 1);
+}
+
+#[test]
+fn distribute_winnings_pays_spawn_earnings() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "spawn-earnings",
+        GameMode::PayToSpawn,
+        1,
+        2,
+        SessionOptions::default(),
+    );
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let (first, second) = (teams[0][0], teams[1][0]);
+    for _ in 0..3 {
+        svm.record_kill(&game_server, session_hash, (0, first), (1, second))
+            .unwrap();
+    }
+    let session = svm.session(session_hash);
+    let distribute = || instructions::distribute_winnings(&game_server, &session, 0);
+
+    // Every player with earnings needs a payout pair
+    let mut ix = distribute();
+    ix.accounts
+        .drain(DISTRIBUTE_ACCOUNTS..DISTRIBUTE_ACCOUNTS + 2);
+    assert_wager_error(svm.process(&ix), WagerError::PlayerAccountNotFound);
+    let mut ix = distribute();
+    ix.accounts[DISTRIBUTE_ACCOUNTS + 1] = instructions::payout_pair(&second)[1].clone();
+    assert_wager_error(svm.process(&ix), WagerError::InvalidPlayerTokenAccount);
+    let foreign = create_foreign_token_account(&mut svm, &second);
+    let mut ix = distribute();
+    ix.accounts[DISTRIBUTE_ACCOUNTS + 3] = AccountMeta::new(foreign, false);
+    assert_wager_error(svm.process(&ix), WagerError::InvalidTokenMint);
+
+    // Nobody is paid unless the vault covers everyone's earnings
+    let seat = MatchState::team_seat(0, 0);
+    svm.update_match_state(session_hash, |match_state| match_state.kills[seat] += 1);
+    assert_wager_error(
+        svm.process(&distribute()),
+        WagerError::IncompleteDistribution,
+    );
+    svm.update_match_state(session_hash, |match_state| match_state.kills[seat] -= 1);
+
+    // Each kill and spawn left is worth a tenth of the bet
+    svm.process(&distribute()).unwrap();
+    assert_eq!(
+        svm.player_balance(&first),
+        PLAYER_TOKENS - BET + 13 * BET / 10
+    );
+    assert_eq!(
+        svm.player_balance(&second),
+        PLAYER_TOKENS - BET + 7 * BET / 10
+    );
+    assert_eq!(svm.profile(&first).total_won, 13 * BET / 10);
+    assert_eq!(svm.vault_balance(session_hash), 0);
+    assert_wager_error(svm.process(&distribute()), WagerError::InvalidGameState);
+}
+
+#[test]
+fn distribute_free_for_all_winnings_pays_by_place() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "free-for-all",
+        GameMode::FreeForAll {
+            max_players: 3,
+            payout_percentages: [70, 30, 0],
+        },
+        0,
+        0,
+        SessionOptions::default(),
+    );
+    let players: Vec<Pubkey> = (0..3).map(|_| svm.create_player(PLAYER_TOKENS)).collect();
+    for player in &players {
+        svm.process(&instructions::join_free_for_all(
+            player,
+            &game_server,
+            session_hash,
+            JoinCredentials::default(),
+            None,
+            None,
+        ))
+        .unwrap();
+    }
+
+    // The first player eliminates everyone else
+    for victim in &players[1..] {
+        for _ in 0..STARTING_SPAWNS {
+            svm.record_kill(&game_server, session_hash, (0, players[0]), (0, *victim))
+                .unwrap();
+        }
+    }
+    // Stats are kept per player, seated in joining order
+    let match_state = svm.match_state(session_hash);
+    assert!(match_state.is_status(GameStatus::Ended));
+    assert_eq!(match_state.get_free_for_all_survivor(), Some(players[0]));
+    assert_eq!(match_state.kills[0], 2 * STARTING_SPAWNS);
+    assert_eq!(match_state.spawns[0], STARTING_SPAWNS);
+    assert_eq!(match_state.eliminated[1..3], [1, 1]);
+    assert_eq!(svm.profile(&players[0]).kills, 2 * STARTING_SPAWNS as u32);
+
+    let session = svm.session(session_hash);
+    let distribute = |placings: &[Pubkey]| {
+        instructions::distribute_free_for_all_winnings(&game_server, &session, placings)
+    };
+
+    assert_wager_error(
+        svm.process(&distribute(&players[..1])),
+        WagerError::InvalidRemainingAccounts,
+    );
+    assert_wager_error(
+        svm.process(&distribute(&[players[1], players[0]])),
+        WagerError::InvalidWinner,
+    );
+    assert_wager_error(
+        svm.process(&distribute(&[players[0], players[0]])),
+        WagerError::InvalidWinner,
+    );
+    let outsider = svm.create_player(PLAYER_TOKENS);
+    assert_wager_error(
+        svm.process(&distribute(&[players[0], outsider])),
+        WagerError::PlayerNotFound,
+    );
+
+    svm.process(&distribute(&[players[0], players[2]])).unwrap();
+    assert_eq!(
+        svm.player_balance(&players[0]),
+        PLAYER_TOKENS - BET + 21 * BET / 10
+    );
+    assert_eq!(svm.player_balance(&players[1]), PLAYER_TOKENS - BET);
+    assert_eq!(
+        svm.player_balance(&players[2]),
+        PLAYER_TOKENS - BET + 9 * BET / 10
+    );
+    assert_eq!(svm.vault_balance(session_hash), 0);
+
+    // Only first place counts as a win
+    assert_eq!(svm.profile(&players[0]).wins, 1);
+    assert_eq!(svm.profile(&players[2]).losses, 1);
+    assert_wager_error(
+        svm.process(&distribute(&[players[0], players[2]])),
+        WagerError::InvalidGameState,
+    );
+}
+
+#[test]
+fn refund_wager_returns_every_stake() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_team_session(&game_server, "refund", 2, 2);
+    let players: Vec<Pubkey> = (0..3).map(|_| svm.create_player(PLAYER_TOKENS)).collect();
+    for (player, team) in players.iter().zip([0, 0, 1]) {
+        svm.join(player, &game_server, session_hash, team).unwrap();
+    }
+    let session = svm.session(session_hash);
+    let refund = || instructions::refund_wager(&game_server, &session);
+
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::refund_wager(&intruder, &session)),
+        WagerError::UnauthorizedDistribution,
+    );
+    let mut ix = refund();
+    ix.accounts.truncate(REFUND_ACCOUNTS);
+    assert_wager_error(svm.process(&ix), WagerError::InvalidRemainingAccounts);
+    let mut ix = refund();
+    ix.accounts.remove(REFUND_ACCOUNTS);
+    assert_wager_error(svm.process(&ix), WagerError::InvalidRemainingAccounts);
+
+    // Every joined player needs their own wager token account
+    let mut ix = refund();
+    replace_pair(
+        &mut ix,
+        REFUND_ACCOUNTS,
+        2,
+        instructions::payout_pair(&players[0]),
+    );
+    assert_wager_error(svm.process(&ix), WagerError::InvalidPlayer);
+    let mut ix = refund();
+    ix.accounts[REFUND_ACCOUNTS + 1] = instructions::payout_pair(&players[1])[1].clone();
+    assert_wager_error(svm.process(&ix), WagerError::InvalidPlayerTokenAccount);
+    let foreign = create_foreign_token_account(&mut svm, &players[0]);
+    let mut ix = refund();
+    ix.accounts[REFUND_ACCOUNTS + 1] = AccountMeta::new(foreign, false);
+    assert_wager_error(svm.process(&ix), WagerError::InvalidTokenMint);
+
+    svm.process(&refund()).unwrap();
+    let refunds = svm.events::<RefundIssued>();
+    assert_eq!(refunds.len(), 3);
+    assert!(refunds.iter().all(|refund| refund.amount == BET));
+    for player in &players {
+        assert_eq!(svm.player_balance(player), PLAYER_TOKENS);
+        let profile = svm.profile(player);
+        assert_eq!((profile.games_played, profile.total_wagered), (0, 0));
+    }
+    assert_eq!(svm.vault_balance(session_hash), 0);
+    assert!(svm.session(session_hash).status == GameStatus::Completed);
+
+    // A settled session can't be refunded again
+    assert_wager_error(svm.process(&refund()), WagerError::InvalidGameState);
+}
+
+#[test]
+fn refund_wager_returns_spawn_purchases() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let session_hash = svm.create_session(
+        &game_server,
+        "spawn-refund",
+        GameMode::PayToSpawn,
+        1,
+        2,
+        SessionOptions::default(),
+    );
+    let teams = svm.fill_teams(&game_server, session_hash);
+    let (first, second) = (teams[0][0], teams[1][0]);
+    for _ in 0..2 {
+        svm.process(&instructions::pay_to_spawn(
+            &second,
+            &game_server,
+            session_hash,
+            1,
+            false,
+        ))
+        .unwrap();
+    }
+    assert_eq!(svm.match_state(session_hash).get_spawn_purchases(second), 2);
+    assert_eq!(svm.vault_balance(session_hash), 4 * BET);
+
+    let session = svm.session(session_hash);
+    svm.process(&instructions::refund_wager(&game_server, &session))
+        .unwrap();
+    let refunds = svm.events::<RefundIssued>();
+    assert_eq!(refunds.len(), 2);
+    assert_eq!(refunds[0].player, first);
+    assert_eq!(refunds[0].amount, BET);
+    assert_eq!(refunds[1].player, second);
+    assert_eq!(refunds[1].amount, 3 * BET);
+    assert_eq!(svm.player_balance(&first), PLAYER_TOKENS);
+    assert_eq!(svm.player_balance(&second), PLAYER_TOKENS);
+    assert_eq!(svm.vault_balance(session_hash), 0);
+}
@@ -0,0 +1,412 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::*;
+use wager_client::{instructions, pda};
+use wager_program::{
+    errors::WagerError,
+    events::{PrizePaid, TournamentCreated, TournamentJoined},
+    state::*,
+};
+
+/// Creates a tournament and registers a funded player for every spot
+fn create_full_tournament(
+    svm: &mut Svm,
+    game_server: &Pubkey,
+    tournament_id: &str,
+    entry_fee: u64,
+    max_players: u8,
+    prize_percentages: [u8; 3],
+) -> Vec<Pubkey> {
+    svm.process(&instructions::create_tournament(
+        game_server,
+        tournament_id,
+        entry_fee,
+        max_players,
+        prize_percentages,
+    ))
+    .unwrap();
+    (0..max_players)
+        .map(|_| {
+            let player = svm.create_player(entry_fee.max(PLAYER_TOKENS));
+            svm.process(&instructions::join_tournament(&player, tournament_id))
+                .unwrap();
+            player
+        })
+        .collect()
+}
+
+/// Spawns bracket match `match_index` and settles it for `winning_team`
+fn play_match(
+    svm: &mut Svm,
+    game_server: &Pubkey,
+    tournament_id: &str,
+    match_index: u8,
+    winning_team: u8,
+) -> [u8; 32] {
+    let session_hash = pda::session_hash(&format!("{tournament_id}-{match_index}"));
+    svm.process(&instructions::create_tournament_match(
+        game_server,
+        tournament_id,
+        match_index,
+        session_hash,
+    ))
+    .unwrap();
+    let session = svm.session(session_hash);
+    svm.process(&instructions::distribute_winnings(
+        game_server,
+        &session,
+        winning_team,
+    ))
+    .unwrap();
+    session_hash
+}
+
+#[test]
+fn tournament_pays_out_bracket() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let players = create_full_tournament(&mut svm, &game_server, "cup", BET, 4, [60, 30, 10]);
+    assert_eq!(
+        svm.token_balance(&pda::tournament_vault_token_account("cup")),
+        4 * BET
+    );
+
+    let tournament: Tournament = svm.get(&pda::find_tournament("cup").0);
+    assert!(tournament.status == TournamentStatus::InProgress);
+    assert_eq!(
+        (
+            tournament.matches[0].player_a,
+            tournament.matches[0].player_b
+        ),
+        (players[0], players[1])
+    );
+    assert_eq!(svm.profile(&players[0]).total_wagered, BET);
+
+    // Semifinal winners advance into the final's slots
+    let semifinal = play_match(&mut svm, &game_server, "cup", 0, 1);
+    play_match(&mut svm, &game_server, "cup", 1, 0);
+    let tournament: Tournament = svm.get(&pda::find_tournament("cup").0);
+    assert_eq!(tournament.matches[0].winner, players[1]);
+    assert_eq!(
+        tournament.matches[0].game_session,
+        pda::find_game_session(&semifinal).0
+    );
+    let final_match = tournament.get_final();
+    assert_eq!(
+        (final_match.player_a, final_match.player_b),
+        (players[1], players[2])
+    );
+
+    // Bracket matches count towards the players' profiles without a stake
+    let profile = svm.profile(&players[1]);
+    assert_eq!((profile.games_played, profile.wins), (1, 1));
+    assert_eq!(profile.total_wagered, BET);
+    assert_eq!(svm.profile(&players[0]).losses, 1);
+
+    assert_wager_error(
+        svm.process(&instructions::distribute_tournament_prizes(
+            &game_server,
+            "cup",
+            &[],
+        )),
+        WagerError::TournamentNotFinished,
+    );
+    play_match(&mut svm, &game_server, "cup", 2, 1);
+
+    let placings = [players[2], players[1], players[0], players[3]];
+    svm.process(&instructions::distribute_tournament_prizes(
+        &game_server,
+        "cup",
+        &placings,
+    ))
+    .unwrap();
+    let prizes = svm.events::<PrizePaid>();
+    let places: Vec<(Pubkey, u8, u64)> = prizes
+        .iter()
+        .map(|prize| (prize.player, prize.place, prize.amount))
+        .collect();
+    assert_eq!(
+        places,
+        vec![
+            (players[2], 1, 24 * BET / 10),
+            (players[1], 2, 12 * BET / 10),
+            (players[0], 3, 2 * BET / 10),
+            (players[3], 3, 2 * BET / 10),
+        ]
+    );
+    assert_eq!(
+        svm.player_balance(&players[2]),
+        PLAYER_TOKENS + 14 * BET / 10
+    );
+    assert_eq!(
+        svm.token_balance(&pda::tournament_vault_token_account("cup")),
+        0
+    );
+
+    let tournament: Tournament = svm.get(&pda::find_tournament("cup").0);
+    assert!(tournament.status == TournamentStatus::Completed);
+    assert_wager_error(
+        svm.process(&instructions::distribute_tournament_prizes(
+            &game_server,
+            "cup",
+            &placings,
+        )),
+        WagerError::InvalidGameState,
+    );
+}
+
+#[test]
+fn create_tournament_rejects_invalid_configurations() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let cases = [
+        (1, [100, 0, 0], WagerError::InvalidTournamentSize),
+        (6, [60, 30, 10], WagerError::InvalidTournamentSize),
+        (32, [60, 30, 10], WagerError::InvalidTournamentSize),
+        (4, [60, 30, 0], WagerError::InvalidPayoutPercentages),
+        (4, [30, 60, 10], WagerError::InvalidPayoutPercentages),
+        (2, [60, 30, 10], WagerError::InvalidPayoutPercentages),
+    ];
+    for (max_players, prize_percentages, error) in cases {
+        assert_wager_error(
+            svm.process(&instructions::create_tournament(
+                &game_server,
+                "invalid",
+                BET,
+                max_players,
+                prize_percentages,
+            )),
+            error,
+        );
+    }
+
+    // Free tournaments have no prize pool, and the ID must fit its reserved space
+    assert_wager_error(
+        svm.process(&instructions::create_tournament(
+            &game_server,
+            "free",
+            0,
+            2,
+            [100, 0, 0],
+        )),
+        WagerError::InvalidBetAmount,
+    );
+    assert_wager_error(
+        svm.process(&instructions::create_tournament(
+            &game_server,
+            "",
+            BET,
+            2,
+            [100, 0, 0],
+        )),
+        WagerError::InvalidCompetitionId,
+    );
+}
+
+#[test]
+fn join_tournament_rejects_invalid_registrations() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    svm.process(&instructions::create_tournament(
+        &game_server,
+        "cup",
+        BET,
+        2,
+        [100, 0, 0],
+    ))
+    .unwrap();
+    let created = svm.events::<TournamentCreated>();
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0].tournament, pda::find_tournament("cup").0);
+    assert_eq!(created[0].authority, game_server);
+    assert_eq!((created[0].entry_fee, created[0].max_players), (BET, 2));
+
+    let first = svm.create_player(PLAYER_TOKENS);
+    svm.process(&instructions::join_tournament(&first, "cup"))
+        .unwrap();
+    let joined = svm.events::<TournamentJoined>();
+    assert_eq!(joined.len(), 1);
+    assert_eq!((joined[0].player, joined[0].amount), (first, BET));
+    assert_wager_error(
+        svm.process(&instructions::join_tournament(&first, "cup")),
+        WagerError::InvalidPlayer,
+    );
+
+    // Bracket matches can only be spawned once registration closes
+    assert_wager_error(
+        svm.process(&instructions::create_tournament_match(
+            &game_server,
+            "cup",
+            0,
+            pda::session_hash("early"),
+        )),
+        WagerError::InvalidGameState,
+    );
+
+    let second = svm.create_player(PLAYER_TOKENS);
+    svm.process(&instructions::join_tournament(&second, "cup"))
+        .unwrap();
+    let late = svm.create_player(PLAYER_TOKENS);
+    assert_wager_error(
+        svm.process(&instructions::join_tournament(&late, "cup")),
+        WagerError::InvalidGameState,
+    );
+    assert_eq!(svm.player_balance(&late), PLAYER_TOKENS);
+}
+
+#[test]
+fn create_tournament_match_rejects_invalid_matches() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    create_full_tournament(&mut svm, &game_server, "cup", BET, 4, [60, 30, 10]);
+    let spawn = |match_index, session_id| {
+        instructions::create_tournament_match(
+            &game_server,
+            "cup",
+            match_index,
+            pda::session_hash(session_id),
+        )
+    };
+
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::create_tournament_match(
+            &intruder,
+            "cup",
+            0,
+            pda::session_hash("intruder"),
+        )),
+        WagerError::UnauthorizedDistribution,
+    );
+    assert_wager_error(
+        svm.process(&spawn(3, "missing")),
+        WagerError::InvalidBracketMatch,
+    );
+
+    // The final waits for both semifinals
+    assert_wager_error(
+        svm.process(&spawn(2, "final")),
+        WagerError::InvalidBracketMatch,
+    );
+
+    // A match is only ever spawned once, and settled once
+    let session_hash = play_match(&mut svm, &game_server, "cup", 0, 0);
+    assert_wager_error(
+        svm.process(&spawn(0, "again")),
+        WagerError::InvalidBracketMatch,
+    );
+    let session = svm.session(session_hash);
+    assert_wager_error(
+        svm.process(&instructions::distribute_winnings(
+            &game_server,
+            &session,
+            1,
+        )),
+        WagerError::InvalidGameState,
+    );
+}
+
+#[test]
+fn tournament_match_settles_against_its_tournament() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    create_full_tournament(&mut svm, &game_server, "cup", BET, 2, [100, 0, 0]);
+    let session_hash = pda::session_hash("final");
+    svm.process(&instructions::create_tournament_match(
+        &game_server,
+        "cup",
+        0,
+        session_hash,
+    ))
+    .unwrap();
+
+    // Leaving out the tournament would drop the bracket result
+    let mut session = svm.session(session_hash);
+    session.tournament_match = None;
+    assert_wager_error(
+        svm.process(&instructions::distribute_winnings(
+            &game_server,
+            &session,
+            0,
+        )),
+        WagerError::InvalidTournament,
+    );
+
+    // Refunding the match would leave the bracket waiting on it forever
+    let session = svm.session(session_hash);
+    assert_wager_error(
+        svm.process(&instructions::refund_wager(&game_server, &session)),
+        WagerError::InvalidBracketMatch,
+    );
+    svm.process(&instructions::distribute_winnings(
+        &game_server,
+        &session,
+        0,
+    ))
+    .unwrap();
+    let tournament: Tournament = svm.get(&pda::find_tournament("cup").0);
+    assert_eq!(tournament.get_final().winner, session.teams[0].players[0]);
+}
+
+#[test]
+fn distribute_tournament_prizes_rejects_invalid_payouts() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let players = create_full_tournament(&mut svm, &game_server, "cup", BET, 2, [70, 30, 0]);
+    play_match(&mut svm, &game_server, "cup", 0, 0);
+    let distribute = |winners: &[Pubkey]| {
+        instructions::distribute_tournament_prizes(&game_server, "cup", winners)
+    };
+
+    let intruder = svm.create_wallet();
+    assert_wager_error(
+        svm.process(&instructions::distribute_tournament_prizes(
+            &intruder, "cup", &players,
+        )),
+        WagerError::UnauthorizedDistribution,
+    );
+    assert_wager_error(
+        svm.process(&distribute(&players[..1])),
+        WagerError::InvalidRemainingAccounts,
+    );
+    assert_wager_error(
+        svm.process(&distribute(&[players[1], players[0]])),
+        WagerError::InvalidWinner,
+    );
+    let mut ix = distribute(&players);
+    ix.accounts[8] = instructions::payout_pair(&players[1])[1].clone();
+    assert_wager_error(svm.process(&ix), WagerError::InvalidWinnerTokenAccount);
+
+    svm.process(&distribute(&players)).unwrap();
+    assert_eq!(
+        svm.player_balance(&players[0]),
+        PLAYER_TOKENS + 4 * BET / 10
+    );
+    assert_eq!(
+        svm.player_balance(&players[1]),
+        PLAYER_TOKENS - 4 * BET / 10
+    );
+}
+
+#[test]
+fn distribute_tournament_prizes_rejects_overflowing_prizes() {
+    let mut svm = Svm::new();
+    let game_server = svm.create_wallet();
+    let players = create_full_tournament(&mut svm, &game_server, "cup", BET, 2, [50, 50, 0]);
+    play_match(&mut svm, &game_server, "cup", 0, 0);
+
+    // Entry fees are capped at creation, so only a corrupted fee can overflow
+    svm.update::<Tournament>(&pda::find_tournament("cup").0, |tournament| {
+        tournament.entry_fee = u64::MAX / 4;
+    });
+
+    assert_wager_error(
+        svm.process(&instructions::distribute_tournament_prizes(
+            &game_server,
+            "cup",
+            &players,
+        )),
+        WagerError::WinningsCalculationError,
+    );
+}
@@ -7,6 +7,7 @@ import { assert } from "chai";
 import {
   generateSessionId,
   deriveGameSessionPDA,
+  deriveSessionHash,
   deriveVaultPDA,
   loadKeypair,
   getVaultTokenAccount,
@@ -38,7 +39,7 @@ describe("Game Session Creation", () => {
     const vaultTokenAccount = await getVaultTokenAccount(TOKEN_ID, vaultPda);
 
     await program.methods
-      .createGameSession(sessionId, betAmount, { winnerTakesAllOneVsOne: {} })
+      .createGameSession(deriveSessionHash(sessionId), betAmount, { winnerTakesAll: {} }, 1, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
       .accounts({
         gameServer: gameServer.publicKey,
       })
@@ -59,7 +60,7 @@ describe("Game Session Creation", () => {
     const vaultTokenAccount = await getVaultTokenAccount(TOKEN_ID, vaultPda);
 
     await program.methods
-      .createGameSession(sessionId, betAmount, { winnerTakesAllThreeVsThree: {} })
+      .createGameSession(deriveSessionHash(sessionId), betAmount, { winnerTakesAll: {} }, 3, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
       .accounts({
         gameServer: gameServer.publicKey,
       })
@@ -80,7 +81,7 @@ describe("Game Session Creation", () => {
     const vaultTokenAccount = await getVaultTokenAccount(TOKEN_ID, vaultPda);
 
     await program.methods
-      .createGameSession(sessionId, betAmount, { winnerTakesAllFiveVsFive: {} })
+      .createGameSession(deriveSessionHash(sessionId), betAmount, { winnerTakesAll: {} }, 5, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
       .accounts({
         gameServer: gameServer.publicKey,
       })
@@ -101,7 +102,7 @@ describe("Game Session Creation", () => {
     const vaultTokenAccount = await getVaultTokenAccount(TOKEN_ID, vaultPda);
 
     await program.methods
-      .createGameSession(sessionId, betAmount, { payToSpawnOneVsOne: {} })
+      .createGameSession(deriveSessionHash(sessionId), betAmount, { payToSpawn: {} }, 1, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
       .accounts({
         gameServer: gameServer.publicKey,
       })
@@ -122,7 +123,7 @@ describe("Game Session Creation", () => {
     const vaultTokenAccount = await getVaultTokenAccount(TOKEN_ID, vaultPda);
 
     await program.methods
-      .createGameSession(sessionId, betAmount, { payToSpawnThreeVsThree: {} })
+      .createGameSession(deriveSessionHash(sessionId), betAmount, { payToSpawn: {} }, 3, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
       .accounts({
         gameServer: gameServer.publicKey,
       })
@@ -143,7 +144,7 @@ describe("Game Session Creation", () => {
     const vaultTokenAccount = await getVaultTokenAccount(TOKEN_ID, vaultPda);
 
     await program.methods
-      .createGameSession(sessionId, betAmount, { payToSpawnFiveVsFive: {} })
+      .createGameSession(deriveSessionHash(sessionId), betAmount, { payToSpawn: {} }, 5, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
       .accounts({
         gameServer: gameServer.publicKey,
       })
@@ -166,7 +167,7 @@ describe("Game Session Creation", () => {
   
     // try {
     //   let tx = await program.methods
-    //   .createGameSession(sessionId, betAmount, { winnerTakesAllFiveVsFive: {} })
+    //   .createGameSession(deriveSessionHash(sessionId), betAmount, { winnerTakesAll: {} }, 5, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
     //   .accounts({
     //     gameServer: gameServer.publicKey,
     //   })
@@ -190,7 +191,7 @@ describe("Game Session Creation", () => {
 
     // First creation should succeed
     await program.methods
-      .createGameSession(sessionId, betAmount, { winnerTakesAllOneVsOne: {} })
+      .createGameSession(deriveSessionHash(sessionId), betAmount, { winnerTakesAll: {} }, 1, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
       .accounts({
         gameServer: gameServer.publicKey,
       })
@@ -200,7 +201,7 @@ describe("Game Session Creation", () => {
     try {
       // Second creation should fail
       await program.methods
-        .createGameSession(sessionId, betAmount, { winnerTakesAllOneVsOne: {} })
+        .createGameSession(deriveSessionHash(sessionId), betAmount, { winnerTakesAll: {} }, 1, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
         .accounts({
           gameServer: gameServer.publicKey,
         })
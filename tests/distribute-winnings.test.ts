@@ -7,6 +7,7 @@
   import {
     generateSessionId,
     deriveGameSessionPDA,
+    deriveSessionHash,
     deriveVaultPDA,
     loadKeypair,
     setupTokenAccount,
@@ -14,7 +15,8 @@
     getBalance,
     getVaultTokenAccount,
     TOKEN_ID,
-    getTokenBalance
+    getTokenBalance,
+    playerProfileAccounts
   } from "./utils";
   import { PublicKey } from "@solana/web3.js";
   import * as borsh from "borsh";
@@ -113,7 +115,7 @@
       // Create game session and join users
       console.log("\nCreating game session...");
       const tx = await program.methods
-        .createGameSession(sessionId, betAmount, { winnerTakesAllOneVsOne: {} })
+        .createGameSession(deriveSessionHash(sessionId), betAmount, { winnerTakesAll: {} }, 1, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
         .accounts({
           gameServer: gameServer.publicKey,
         })
@@ -147,7 +149,7 @@
       // Join users
       console.log("\nJoining user 1...");
       const joinUser1Tx = await program.methods
-        .joinUser(sessionId, 0)
+        .joinUser(deriveSessionHash(sessionId), 0, { merkleProof: [] })
         .accounts({
           user: user1.publicKey,
           gameServer: gameServer.publicKey,
@@ -159,7 +161,7 @@
 
       console.log("\nJoining user 2...");
       const joinUser2Tx = await program.methods
-        .joinUser(sessionId, 1)
+        .joinUser(deriveSessionHash(sessionId), 1, { merkleProof: [] })
         .accounts({
           user: user2.publicKey,
           gameServer: gameServer.publicKey,
@@ -176,7 +178,7 @@
       // Distribute winnings
       console.log("\nDistributing winnings...");
       const txDistribute = await program.methods
-        .distributeWinnings(sessionId, 0)
+        .distributeWinnings(deriveSessionHash(sessionId), 0)
         .accounts({
           gameServer: gameServer.publicKey,
         })
@@ -191,6 +193,7 @@
             isSigner: false,
             isWritable: true,
           },
+          ...playerProfileAccounts(program.programId, [user1.publicKey, user2.publicKey]),
         ])
         .signers([gameServer])
         .rpc(confirmOptions);
@@ -228,7 +231,7 @@
       // Create game session and join users
       console.log("\nCreating game session...");
       const tx = await program.methods
-        .createGameSession(sessionId, betAmount, { winnerTakesAllThreeVsThree: {} })
+        .createGameSession(deriveSessionHash(sessionId), betAmount, { winnerTakesAll: {} }, 3, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
         .accounts({
           gameServer: gameServer.publicKey,
         })
@@ -271,7 +274,7 @@
       // Join users
       console.log("\nJoining user 1...");
       const joinUser1Tx = await program.methods
-        .joinUser(sessionId, 0)
+        .joinUser(deriveSessionHash(sessionId), 0, { merkleProof: [] })
         .accounts({
           user: user1.publicKey,
           gameServer: gameServer.publicKey,
@@ -283,7 +286,7 @@
 
       console.log("\nJoining user 2...");
       const joinUser2Tx = await program.methods
-        .joinUser(sessionId, 1)
+        .joinUser(deriveSessionHash(sessionId), 1, { merkleProof: [] })
         .accounts({
           user: user2.publicKey,
           gameServer: gameServer.publicKey,
@@ -295,7 +298,7 @@
 
       console.log("\nJoining user 3...");
       const joinUser3Tx = await program.methods
-        .joinUser(sessionId, 0)
+        .joinUser(deriveSessionHash(sessionId), 0, { merkleProof: [] })
         .accounts({
           user: user3.publicKey,
           gameServer: gameServer.publicKey,
@@ -307,7 +310,7 @@
 
       console.log("\nJoining user 4...");
       const joinUser4Tx = await program.methods
-        .joinUser(sessionId, 1)
+        .joinUser(deriveSessionHash(sessionId), 1, { merkleProof: [] })
         .accounts({
           user: user4.publicKey,
           gameServer: gameServer.publicKey,
@@ -319,7 +322,7 @@
 
       console.log("Joining user 5...");
       const joinUser5Tx = await program.methods
-        .joinUser(sessionId, 0)
+        .joinUser(deriveSessionHash(sessionId), 0, { merkleProof: [] })
         .accounts({
           user: user5.publicKey,
           gameServer: gameServer.publicKey,
@@ -332,14 +335,14 @@
       const [gameSessionPda] = deriveGameSessionPDA(program.programId, sessionId);
       const account = await program.account.gameSession.fetch(gameSessionPda);
       console.log("\nGame session state after join:");
-      console.log("Team A players:", account.teamA.players.map(p => p.toString()));
-      assert.equal(account.teamA.players[0].toString(), user1.publicKey.toString());
-      console.log("Team B players:", account.teamB.players.map(p => p.toString()));
-      assert.equal(account.teamB.players[0].toString(), user2.publicKey.toString());
+      console.log("Team A players:", account.teams[0].players.map(p => p.toString()));
+      assert.equal(account.teams[0].players[0].toString(), user1.publicKey.toString());
+      console.log("Team B players:", account.teams[1].players.map(p => p.toString()));
+      assert.equal(account.teams[1].players[0].toString(), user2.publicKey.toString());
 
       console.log("Joining user 6...");
       const joinUser6Tx = await program.methods
-        .joinUser(sessionId, 1)
+        .joinUser(deriveSessionHash(sessionId), 1, { merkleProof: [] })
         .accounts({
           user: user6.publicKey,
           gameServer: gameServer.publicKey,
@@ -361,7 +364,7 @@
       // Distribute winnings
       console.log("\nDistributing winnings...");
       const txDistribute = await program.methods
-        .distributeWinnings(sessionId, 0)
+        .distributeWinnings(deriveSessionHash(sessionId), 0)
         .accounts({
           gameServer: gameServer.publicKey,
         })
@@ -396,6 +399,14 @@
             isSigner: false,
             isWritable: true,
           },
+          ...playerProfileAccounts(program.programId, [
+            user1.publicKey,
+            user3.publicKey,
+            user5.publicKey,
+            user2.publicKey,
+            user4.publicKey,
+            user6.publicKey,
+          ]),
         ])
         .signers([gameServer])
         .rpc(confirmOptions);
@@ -7,6 +7,7 @@ import { assert } from "chai";
 import {
   generateSessionId,
   deriveGameSessionPDA,
+  deriveSessionHash,
   deriveVaultPDA,
   getBalance,
   TOKEN_ID,
@@ -131,7 +132,7 @@ describe("Join User Tests", () => {
     console.log("\nCreating game session...");
     try {
       await program.methods
-        .createGameSession(sessionId, betAmount, { winnerTakesAllOneVsOne: {} })
+        .createGameSession(deriveSessionHash(sessionId), betAmount, { winnerTakesAll: {} }, 1, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
         .accounts({
           gameServer: gameServer.publicKey,
         })
@@ -149,7 +150,7 @@ describe("Join User Tests", () => {
       console.log("user1 balance: ", await getTokenBalance(provider.connection, user1TokenAccount));
 
       await program.methods
-        .joinUser(sessionId, 0)
+        .joinUser(deriveSessionHash(sessionId), 0, { merkleProof: [] })
         .accounts({
           user: user1.publicKey,
           gameServer: gameServer.publicKey,
@@ -161,8 +162,8 @@ describe("Join User Tests", () => {
 
       const account = await program.account.gameSession.fetch(gameSessionPda);
       console.log("\nGame session state after join:");
-      console.log("Team A players:", account.teamA.players.map(p => p.toString()));
-      assert.equal(account.teamA.players[0].toString(), user1.publicKey.toString());
+      console.log("Team A players:", account.teams[0].players.map(p => p.toString()));
+      assert.equal(account.teams[0].players[0].toString(), user1.publicKey.toString());
     } catch (error) {
       console.error("Error joining user:", error);
       throw error;
@@ -171,7 +172,7 @@ describe("Join User Tests", () => {
     console.log("\nJoining user2 to team...");
     try {
       await program.methods
-        .joinUser(sessionId, 1)
+        .joinUser(deriveSessionHash(sessionId), 1, { merkleProof: [] })
         .accounts({
           user: user2.publicKey,
           gameServer: gameServer.publicKey,
@@ -183,8 +184,8 @@ describe("Join User Tests", () => {
 
       const account = await program.account.gameSession.fetch(gameSessionPda);
       console.log("\nGame session state after join:");
-      console.log("Team B players:", account.teamB.players.map(p => p.toString()));
-      assert.equal(account.teamB.players[0].toString(), user2.publicKey.toString());
+      console.log("Team B players:", account.teams[1].players.map(p => p.toString()));
+      assert.equal(account.teams[1].players[0].toString(), user2.publicKey.toString());
     } catch (error) {
       console.error("Error joining user:", error);
       throw error;
@@ -211,7 +212,7 @@ describe("Join User Tests", () => {
     console.log("\nCreating game session...");
     try {
       await program.methods
-        .createGameSession(sessionId, betAmount, { payToSpawnOneVsOne: {} })
+        .createGameSession(deriveSessionHash(sessionId), betAmount, { payToSpawn: {} }, 1, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
         .accounts({
           gameServer: gameServer.publicKey,
         })
@@ -229,7 +230,7 @@ describe("Join User Tests", () => {
       console.log("user1 balance: ", await getTokenBalance(provider.connection, user1TokenAccount));
 
       await program.methods
-        .joinUser(sessionId, 0)
+        .joinUser(deriveSessionHash(sessionId), 0, { merkleProof: [] })
         .accounts({
           user: user1.publicKey,
           gameServer: gameServer.publicKey,
@@ -241,8 +242,8 @@ describe("Join User Tests", () => {
 
       const account = await program.account.gameSession.fetch(gameSessionPda);
       console.log("\nGame session state after join:");
-      console.log("Team A players:", account.teamA.players.map(p => p.toString()));
-      assert.equal(account.teamA.players[0].toString(), user1.publicKey.toString());
+      console.log("Team A players:", account.teams[0].players.map(p => p.toString()));
+      assert.equal(account.teams[0].players[0].toString(), user1.publicKey.toString());
     } catch (error) {
       console.error("Error joining user:", error);
       throw error;
@@ -251,7 +252,7 @@ describe("Join User Tests", () => {
     console.log("\nJoining user2 to team...");
     try {
       await program.methods
-        .joinUser(sessionId, 1)
+        .joinUser(deriveSessionHash(sessionId), 1, { merkleProof: [] })
         .accounts({
           user: user2.publicKey,
           gameServer: gameServer.publicKey,
@@ -263,8 +264,8 @@ describe("Join User Tests", () => {
 
       const account = await program.account.gameSession.fetch(gameSessionPda);
       console.log("\nGame session state after join:");
-      console.log("Team B players:", account.teamB.players.map(p => p.toString()));
-      assert.equal(account.teamB.players[0].toString(), user2.publicKey.toString());
+      console.log("Team B players:", account.teams[1].players.map(p => p.toString()));
+      assert.equal(account.teams[1].players[0].toString(), user2.publicKey.toString());
     } catch (error) {
       console.error("Error joining user:", error);
       throw error;
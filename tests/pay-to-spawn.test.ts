@@ -7,6 +7,7 @@ import { assert } from "chai";
 import {
   generateSessionId,
   deriveGameSessionPDA,
+  deriveSessionHash,
   deriveVaultPDA,
   loadKeypair,
   setupTokenAccount,
@@ -15,7 +16,11 @@ import {
   getVaultTokenAccount,
   TOKEN_ID,
   getTokenBalance,
-  printGameState
+  printGameState,
+  playerProfileAccounts,
+  deriveMatchStatePDA,
+  assertComputeBudget,
+  COMPUTE_BUDGETS
 } from "./utils";
 import { PublicKey } from "@solana/web3.js";
 import * as borsh from "borsh";
@@ -114,7 +119,7 @@ describe("Distribute Winnings Tests", () => {
     // Create game session and join users
     console.log("\nCreating game session...");
     const tx = await program.methods
-      .createGameSession(sessionId, betAmount, { payToSpawnOneVsOne: {} })
+      .createGameSession(deriveSessionHash(sessionId), betAmount, { payToSpawn: {} }, 1, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
       .accounts({
         gameServer: gameServer.publicKey,
       })
@@ -147,7 +152,7 @@ describe("Distribute Winnings Tests", () => {
     // Join users
     console.log("\nJoining user 1...");
     const joinUser1Tx = await program.methods
-      .joinUser(sessionId, 0)
+      .joinUser(deriveSessionHash(sessionId), 0, { merkleProof: [] })
       .accounts({
         user: user1.publicKey,
         gameServer: gameServer.publicKey,
@@ -158,7 +163,7 @@ describe("Distribute Winnings Tests", () => {
 
     console.log("\nJoining user 2...");
     const joinUser2Tx = await program.methods
-      .joinUser(sessionId, 1)
+      .joinUser(deriveSessionHash(sessionId), 1, { merkleProof: [] })
       .accounts({
         user: user2.publicKey,
         gameServer: gameServer.publicKey,
@@ -174,54 +179,61 @@ describe("Distribute Winnings Tests", () => {
     const [gameSessionPda] = deriveGameSessionPDA(program.programId, sessionId);
     console.log("Game session PDA:", gameSessionPda.toString());
     const account = await program.account.gameSession.fetch(gameSessionPda);
+    const [matchStatePda] = deriveMatchStatePDA(program.programId, sessionId);
 
     console.log("Simulating game...");
-    const gameStateJoin = await program.account.gameSession.fetch(gameSessionPda);
+    const gameStateJoin = await program.account.matchState.fetch(matchStatePda);
     await printGameState(gameStateJoin, "Game stats after join:", vaultTokenAccount, provider.connection);
 
 
-    const killTx1 = await program.methods.recordKill(sessionId, 0, user1.publicKey, 1, user2.publicKey).accounts({
+    const killTx1 = await program.methods.recordKill(deriveSessionHash(sessionId), 0, user1.publicKey, 1, user2.publicKey).accounts({
       gameServer: gameServer.publicKey,
+      matchLog: null,
     })
     .signers([gameServer])
     .rpc(confirmOptions);
+    await assertComputeBudget(provider.connection, killTx1, COMPUTE_BUDGETS.recordKill, "recordKill");
     
     // Get and print stats after first kill
-    const gameState1 = await program.account.gameSession.fetch(gameSessionPda);
+    const gameState1 = await program.account.matchState.fetch(matchStatePda);
     await printGameState(gameState1, "Game stats after kill 1", vaultTokenAccount, provider.connection);
 
     for (let i = 1; i < 10; i++) {
-        const killTx2 = await program.methods.recordKill(sessionId, 0, user1.publicKey, 1, user2.publicKey).accounts({
+        const killTx2 = await program.methods.recordKill(deriveSessionHash(sessionId), 0, user1.publicKey, 1, user2.publicKey).accounts({
             gameServer: gameServer.publicKey,
+            matchLog: null,
         })
         .signers([gameServer])
         .rpc(confirmOptions);
     }       
-    const gameState2 = await program.account.gameSession.fetch(gameSessionPda);
+    const gameState2 = await program.account.matchState.fetch(matchStatePda);
     await printGameState(gameState2, "Game stats after kill 10", vaultTokenAccount, provider.connection);
 
-    const txspawn1 = await program.methods.payToSpawn(sessionId, 1).accounts({
+    const txspawn1 = await program.methods.payToSpawn(deriveSessionHash(sessionId), 1).accounts({
         user: user2.publicKey,
         gameServer: gameServer.publicKey,
         userTokenAccount: user2TokenAccount,
+        matchLog: null,
     })
     .signers([user2])
     .rpc(confirmOptions);
+    await assertComputeBudget(provider.connection, txspawn1, COMPUTE_BUDGETS.payToSpawn, "payToSpawn");
 
     // Get and print stats after user2 spawns
-    const gameStateSpawn1 = await program.account.gameSession.fetch(gameSessionPda);
+    const gameStateSpawn1 = await program.account.matchState.fetch(matchStatePda);
     await printGameState(gameStateSpawn1, "Player pays to spawn, Game stats after that:", vaultTokenAccount, provider.connection);
 
     for (let i = 0; i < 10; i++) {
-        const killTx2 = await program.methods.recordKill(sessionId, 0, user1.publicKey, 1, user2.publicKey).accounts({
+        const killTx2 = await program.methods.recordKill(deriveSessionHash(sessionId), 0, user1.publicKey, 1, user2.publicKey).accounts({
             gameServer: gameServer.publicKey,
+            matchLog: null,
         })
         .signers([gameServer])
         .rpc(confirmOptions);
     }
 
     // Get and print stats after third kill
-    const gameState3 = await program.account.gameSession.fetch(gameSessionPda);
+    const gameState3 = await program.account.matchState.fetch(matchStatePda);
     await printGameState(gameState3, "Game stats after player kills player B 10 times", vaultTokenAccount, provider.connection);
    
     console.log("user1 balance: ", await getTokenBalance(provider.connection, user1TokenAccount));
@@ -231,7 +243,7 @@ describe("Distribute Winnings Tests", () => {
     // Distribute winnings
     console.log("\nDistributing winnings...");
     const txDistribute = await program.methods
-      .distributeWinnings(sessionId, 0)
+      .distributeWinnings(deriveSessionHash(sessionId), 0)
       .accounts({
         gameServer: gameServer.publicKey,
       })
@@ -256,12 +268,13 @@ describe("Distribute Winnings Tests", () => {
           isSigner: false,
           isWritable: true,
         },
+        ...playerProfileAccounts(program.programId, [user1.publicKey, user2.publicKey]),
       ])
       .signers([gameServer])
       .rpc(confirmOptions);
 
       // Get and print stats after third kill
-    const gameStateFinal = await program.account.gameSession.fetch(gameSessionPda);
+    const gameStateFinal = await program.account.matchState.fetch(matchStatePda);
     await printGameState(gameStateFinal, "Final game stats", vaultTokenAccount, provider.connection);
 
     const user1finalBalance = await getTokenBalance(provider.connection, user1TokenAccount);
@@ -7,6 +7,7 @@ import { assert } from "chai";
 import {
   generateSessionId,
   deriveGameSessionPDA,
+  deriveSessionHash,
   deriveVaultPDA,
   loadKeypair,
   setupTokenAccount,
@@ -114,7 +115,7 @@ describe("Distribute Winnings Tests", () => {
     // Create game session and join users
     console.log("\nCreating game session...");
     const tx = await program.methods
-      .createGameSession(sessionId, betAmount, { winnerTakesAllThreeVsThree: {} })
+      .createGameSession(deriveSessionHash(sessionId), betAmount, { winnerTakesAll: {} }, 3, 2, { teamConfigs: [], bestOf: 1, kFactor: 0, ratingBand: null, linkedWallets: [], allowlist: null, teamAllowlists: [], inviteKey: null, tokenGate: null, sessionId })
       .accounts({
         gameServer: gameServer.publicKey,
       })
@@ -151,7 +152,7 @@ describe("Distribute Winnings Tests", () => {
     // Join users
     console.log("\nJoining user 1...");
     const joinUser1Tx = await program.methods
-      .joinUser(sessionId, 0)
+      .joinUser(deriveSessionHash(sessionId), 0, { merkleProof: [] })
       .accounts({
         user: user1.publicKey,
         gameServer: gameServer.publicKey,
@@ -163,7 +164,7 @@ describe("Distribute Winnings Tests", () => {
 
     console.log("\nJoining user 2...");
     const joinUser2Tx = await program.methods
-      .joinUser(sessionId, 1)
+      .joinUser(deriveSessionHash(sessionId), 1, { merkleProof: [] })
       .accounts({
         user: user2.publicKey,
         gameServer: gameServer.publicKey,
@@ -175,7 +176,7 @@ describe("Distribute Winnings Tests", () => {
 
     console.log("\nJoining user 3...");
     const joinUser3Tx = await program.methods
-      .joinUser(sessionId, 0)
+      .joinUser(deriveSessionHash(sessionId), 0, { merkleProof: [] })
       .accounts({
         user: user3.publicKey,
         gameServer: gameServer.publicKey,
@@ -2,6 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Keypair, LAMPORTS_PER_SOL, PublicKey, Connection } from "@solana/web3.js";
 import { BN } from "@coral-xyz/anchor";
 import { readFileSync } from "fs";
+import { createHash } from "crypto";
 import { 
   getAssociatedTokenAddress, 
   getOrCreateAssociatedTokenAccount,
@@ -41,9 +42,21 @@ export async function createAndFundAccount(
   return account;
 }
 
+// Sessions are addressed by the SHA-256 of their human-readable ID
+export function deriveSessionHash(sessionId: string): number[] {
+  return Array.from(createHash("sha256").update(sessionId).digest());
+}
+
 export function deriveGameSessionPDA(programId: PublicKey, sessionId: string): [PublicKey, number] {
   return PublicKey.findProgramAddressSync(
-    [Buffer.from("game_session"), Buffer.from(sessionId)],
+    [Buffer.from("game_session"), Buffer.from(deriveSessionHash(sessionId))],
+    programId
+  );
+}
+
+export function deriveMatchStatePDA(programId: PublicKey, sessionId: string): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("match_state"), Buffer.from(deriveSessionHash(sessionId))],
     programId
   );
 }
@@ -55,6 +68,22 @@ export function deriveVaultPDA(programId: PublicKey, gameSessionPda: PublicKey):
   );
 }
 
+export function derivePlayerProfilePDA(programId: PublicKey, player: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("player_profile"), player.toBuffer()],
+    programId
+  );
+}
+
+// Profile accounts appended after the payout pairs of distributeWinnings, in seating order
+export function playerProfileAccounts(programId: PublicKey, players: PublicKey[]) {
+  return players.map((player) => ({
+    pubkey: derivePlayerProfilePDA(programId, player)[0],
+    isSigner: false,
+    isWritable: true,
+  }));
+}
+
 export function loadKeypair(path: string): Keypair {
   const data = JSON.parse(readFileSync(path, 'utf-8'));
   return anchor.web3.Keypair.fromSecretKey(new Uint8Array(data));
@@ -108,34 +137,48 @@ export async function getTokenBalance(connection: Connection, tokenAccount: Publ
 } 
 
 export const printGameState = async (
-    gameState: any,
+    matchState: any,
     message: string,
     vaultTokenAccount?: string,
     connection?: Connection
 ) => {
     console.log(`\n${message}:`);
-    console.log("\nTeam A:");
-    gameState.teamA.players.forEach((player: PublicKey, index: number) => {
-        if (player.toString() !== PublicKey.default.toString()) {
-            console.log(`Player ${player.toString()}:`);
-            console.log(`  Kills: ${gameState.teamA.playerKills[index]}`);
-            console.log(`  Spawns remaining: ${gameState.teamA.playerSpawns[index]}`);
-        }
-    });
-    
-    console.log("\nTeam B:");
-    gameState.teamB.players.forEach((player: PublicKey, index: number) => {
+    matchState.players.forEach((player: PublicKey, seat: number) => {
         if (player.toString() !== PublicKey.default.toString()) {
-            console.log(`Player ${player.toString()}:`);
-            console.log(`  Kills: ${gameState.teamB.playerKills[index]}`);
-            console.log(`  Spawns remaining: ${gameState.teamB.playerSpawns[index]}`);
+            console.log(`Seat ${seat} player ${player.toString()}:`);
+            console.log(`  Kills: ${matchState.kills[seat]}`);
+            console.log(`  Spawns remaining: ${matchState.spawns[seat]}`);
         }
     });
-    
-    console.log("Game status:", gameState.status);
-    
+
+    console.log("Game status:", matchState.status);
+
     if (vaultTokenAccount && connection) {
         const vaultBalance = await getTokenBalance(connection, new PublicKey(vaultTokenAccount));
         console.log("Vault balance:", vaultBalance);
     }
-}; 
\ No newline at end of file
+};
+
+// Compute unit ceilings for the hot-path instructions, checked against confirmed transactions
+export const COMPUTE_BUDGETS = {
+  recordKill: 30_000,
+  payToSpawn: 45_000,
+};
+
+export async function assertComputeBudget(
+  connection: Connection,
+  signature: string,
+  budget: number,
+  label: string
+): Promise<number> {
+  const transaction = await connection.getTransaction(signature, {
+    commitment: "confirmed",
+    maxSupportedTransactionVersion: 0,
+  });
+  const consumed = transaction?.meta?.computeUnitsConsumed ?? 0;
+  console.log(`${label} consumed ${consumed} compute units (budget ${budget})`);
+  if (consumed > budget) {
+    throw new Error(`${label} used ${consumed} compute units, over its budget of ${budget}`);
+  }
+  return consumed;
+} 
\ No newline at end of file